@@ -0,0 +1,122 @@
+use anyhow::{anyhow, Result};
+
+/// Options that take no value
+const SWITCHES: [&str; 4] = ["decrypt", "once", "base32", "help"];
+
+/// A command line split into its parts, options are taken out by the
+/// command they belong to and whatever is left is a setting
+#[derive(Debug, PartialEq)]
+pub struct Args {
+    pub command: String,
+    pub paths: Vec<String>,
+    /// `--key value` and `--key=value`, in the order given
+    pub options: Vec<(String, String)>,
+    pub switches: Vec<String>,
+}
+
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Args> {
+        let mut args = args.into_iter();
+        let command = args.next().ok_or(anyhow!("No command given"))?;
+
+        let mut parsed = Args {
+            command,
+            paths: Vec::new(),
+            options: Vec::new(),
+            switches: Vec::new(),
+        };
+
+        let mut only_paths = false;
+        while let Some(arg) = args.next() {
+            let name = match arg.strip_prefix("--") {
+                Some(name) if !only_paths => name,
+                _ => {
+                    parsed.paths.push(arg);
+                    continue;
+                }
+            };
+
+            if name.is_empty() {
+                only_paths = true;
+            } else if SWITCHES.contains(&name) {
+                parsed.switches.push(name.to_owned());
+            } else if let Some((key, value)) = name.split_once('=') {
+                parsed.options.push((key.to_owned(), value.to_owned()));
+            } else {
+                let value = args
+                    .next()
+                    .ok_or_else(|| anyhow!("--{} needs a value", name))?;
+                parsed.options.push((name.to_owned(), value));
+            }
+        }
+
+        return Ok(parsed);
+    }
+
+    /// Removes the last `--name` option and returns its value
+    pub fn take(&mut self, name: &str) -> Option<String> {
+        let index = self.options.iter().rposition(|(key, _)| key == name)?;
+        let (_, value) = self.options.remove(index);
+
+        // Earlier duplicates are overridden, not settings
+        self.options.retain(|(key, _)| key != name);
+
+        Some(value)
+    }
+
+    pub fn switch(&self, name: &str) -> bool {
+        self.switches.iter().any(|switch| switch == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Args;
+
+    fn parse(line: &str) -> Args {
+        Args::parse(line.split(' ').map(String::from)).unwrap()
+    }
+
+    #[test]
+    fn splits_paths_options_and_switches() {
+        let args = parse("decrypt a.txt --out dir --aes-key=k=v b.txt --once");
+
+        assert_eq!(args.command, "decrypt");
+        assert_eq!(args.paths, ["a.txt", "b.txt"]);
+        assert_eq!(
+            args.options,
+            [
+                (String::from("out"), String::from("dir")),
+                (String::from("aes-key"), String::from("k=v")),
+            ]
+        );
+        assert!(args.switch("once"));
+        assert!(!args.switch("decrypt"));
+    }
+
+    #[test]
+    fn double_dash_ends_the_options() {
+        let args = parse("hash --hash md5 -- --weird-name");
+
+        assert_eq!(args.paths, ["--weird-name"]);
+        assert_eq!(args.options.len(), 1);
+    }
+
+    #[test]
+    fn take_returns_the_last_value() {
+        let mut args = parse("send f --to a:1 --algorithm aes --to b:2");
+
+        assert_eq!(args.take("to").as_deref(), Some("b:2"));
+        assert_eq!(args.take("to"), None);
+        assert_eq!(
+            args.options,
+            [(String::from("algorithm"), String::from("aes"))]
+        );
+    }
+
+    #[test]
+    fn options_need_values() {
+        assert!(Args::parse(["send", "f", "--to"].map(String::from)).is_err());
+        assert!(Args::parse(Vec::<String>::new()).is_err());
+    }
+}
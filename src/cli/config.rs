@@ -0,0 +1,447 @@
+//! Settings as `key = value` pairs, given either as `--key value` flags or
+//! as the lines of a config file. Keys are listed in [`KEYS`].
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+
+use zi_core::algorithms::aes::alg::AesKeySize;
+use zi_core::algorithms::enigma::alg::{EnigmaMachine, EnigmaTextMode};
+use zi_core::algorithms::enigma::catalogue::{self, Component};
+use zi_core::algorithms::kdf::DEFAULT_ITERATIONS;
+use zi_core::algorithms::modes::alg::BlockMode;
+use zi_core::algorithms::xxtea::alg::XxteaMode;
+use zi_core::algorithms::AlgorithmOption;
+use zi_core::config::{
+    AesConfig, BinaryEnigmaConfig, Config, EnigmaConfig, RotorConfig, Secret, XxteaConfig,
+};
+use zi_core::container::ContainerFormat;
+use zi_core::hash::HashAlgorithm;
+
+/// Every key [`apply`] understands, for the usage text
+pub const KEYS: [&str; 35] = [
+    "algorithm",
+    "format",
+    "tcp-hash",
+    "shared-secret",
+    "kdf-iterations",
+    "enigma-machine",
+    "enigma-reflector",
+    "enigma-greek",
+    "enigma-greek-ring",
+    "enigma-greek-position",
+    "enigma-rotor1",
+    "enigma-notch1",
+    "enigma-ring1",
+    "enigma-position1",
+    "enigma-rotor2",
+    "enigma-notch2",
+    "enigma-ring2",
+    "enigma-position2",
+    "enigma-rotor3",
+    "enigma-notch3",
+    "enigma-ring3",
+    "enigma-position3",
+    "enigma-plugboard",
+    "enigma-passphrase",
+    "enigma-text-mode",
+    "enigma-groups",
+    "xxtea-key",
+    "xxtea-mode",
+    "xxtea-iv",
+    "xxtea-block-size",
+    "aes-key",
+    "aes-key-size",
+    "aes-mode",
+    "aes-iv",
+    "chacha-key",
+];
+
+pub const ALGORITHMS: [(&str, AlgorithmOption); 4] = [
+    ("enigma", AlgorithmOption::Enigma),
+    ("xxtea", AlgorithmOption::Xxtea),
+    ("aes", AlgorithmOption::Aes),
+    ("chacha20-poly1305", AlgorithmOption::ChaCha20Poly1305),
+];
+
+pub const FORMATS: [(&str, ContainerFormat); 2] = [
+    ("container", ContainerFormat::Container),
+    ("raw", ContainerFormat::Raw),
+];
+
+pub const MACHINES: [(&str, EnigmaMachine); 3] = [
+    ("m3", EnigmaMachine::M3),
+    ("m4", EnigmaMachine::M4),
+    ("binary", EnigmaMachine::Binary),
+];
+
+pub const TEXT_MODES: [(&str, EnigmaTextMode); 2] = [
+    ("letters", EnigmaTextMode::LettersOnly),
+    ("keep", EnigmaTextMode::PassThrough),
+];
+
+pub const SWITCH: [(&str, bool); 2] = [("true", true), ("false", false)];
+
+pub const KEY_SIZES: [(&str, AesKeySize); 3] = [
+    ("128", AesKeySize::Aes128),
+    ("192", AesKeySize::Aes192),
+    ("256", AesKeySize::Aes256),
+];
+
+/// Reads `key = value` lines, skipping blank lines and `#` comments
+pub fn parse(text: &str) -> Result<Vec<(String, String)>> {
+    text.lines()
+        .enumerate()
+        .map(|(number, line)| (number, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| {
+            let (key, value) = line
+                .split_once('=')
+                .ok_or(anyhow!("Line {} is not `key = value`", number + 1))?;
+
+            Ok((key.trim().to_owned(), value.trim().to_owned()))
+        })
+        .collect()
+}
+
+/// Settings the keys start from, the defaults of the core with a Greek wheel
+/// set up, like on the settings page
+pub fn defaults() -> Config {
+    let mut config = Config::default();
+    if let Some(enigma) = config.enigma.as_mut() {
+        enigma.greek = Some(RotorConfig {
+            wiring: catalogue::GREEK_BETA.to_owned(),
+            notches: Vec::new(),
+            ringstellung: 0,
+            position: 0,
+        });
+    }
+
+    config
+}
+
+/// Sets the field `key` names, parsed into its type. What the types can't
+/// tell, like whether a wiring is a permutation, is checked when the
+/// algorithm is built.
+pub fn apply(config: &mut Config, key: &str, value: &str) -> Result<()> {
+    let secret = || {
+        if value.is_empty() {
+            Err(anyhow!("{} is empty", key))
+        } else {
+            Ok(Secret::from(value.to_owned()))
+        }
+    };
+
+    let enigma = config.enigma.get_or_insert_with(Default::default);
+
+    match key {
+        "algorithm" => config.algorithm = choice(key, value, &ALGORITHMS)?,
+        "format" => config.container_format = choice(key, value, &FORMATS)?,
+        "tcp-hash" => config.integrity_hash = hash_algorithm(key, value)?,
+        "shared-secret" => config.shared_secret = secret().ok(),
+        "kdf-iterations" => {
+            config.kdf_iterations = match value.parse() {
+                _ if value.is_empty() => DEFAULT_ITERATIONS,
+                Ok(0) | Err(_) => return Err(anyhow!("{} must be a positive number", key)),
+                Ok(iterations) => iterations,
+            }
+        }
+
+        "enigma-machine" => config.enigma_machine = choice(key, value, &MACHINES)?,
+        "enigma-reflector" => {
+            let reflectors = [&catalogue::REFLECTORS[..], &catalogue::THIN_REFLECTORS].concat();
+            enigma.reflector = wiring(&reflectors, value.to_owned());
+        }
+        "enigma-greek" if value.is_empty() => enigma.greek = None,
+        "enigma-greek" => {
+            let wiring = wiring(&catalogue::GREEK_WHEELS, value.to_owned());
+            match enigma.greek.as_mut() {
+                Some(greek) => greek.wiring = wiring,
+                None => {
+                    enigma.greek = Some(RotorConfig {
+                        wiring,
+                        notches: Vec::new(),
+                        ringstellung: 0,
+                        position: 0,
+                    })
+                }
+            }
+        }
+        "enigma-greek-ring" => greek(enigma, key)?.ringstellung = number(key, value)?,
+        "enigma-greek-position" => greek(enigma, key)?.position = number(key, value)?,
+        "enigma-rotor1" => rotor(&mut enigma.rotors[0], value),
+        "enigma-notch1" => enigma.rotors[0].notches = notches(key, value)?,
+        "enigma-ring1" => enigma.rotors[0].ringstellung = number(key, value)?,
+        "enigma-position1" => enigma.rotors[0].position = number(key, value)?,
+        "enigma-rotor2" => rotor(&mut enigma.rotors[1], value),
+        "enigma-notch2" => enigma.rotors[1].notches = notches(key, value)?,
+        "enigma-ring2" => enigma.rotors[1].ringstellung = number(key, value)?,
+        "enigma-position2" => enigma.rotors[1].position = number(key, value)?,
+        "enigma-rotor3" => rotor(&mut enigma.rotors[2], value),
+        "enigma-notch3" => enigma.rotors[2].notches = notches(key, value)?,
+        "enigma-ring3" => enigma.rotors[2].ringstellung = number(key, value)?,
+        "enigma-position3" => enigma.rotors[2].position = number(key, value)?,
+        "enigma-plugboard" => enigma.plugboard = value.to_owned(),
+        "enigma-passphrase" => {
+            config.binary_enigma = Some(BinaryEnigmaConfig {
+                passphrase: secret()?,
+            })
+        }
+        "enigma-text-mode" => enigma.text_mode = choice(key, value, &TEXT_MODES)?,
+        "enigma-groups" => enigma.five_letter_groups = choice(key, value, &SWITCH)?,
+
+        "xxtea-key" => xxtea(config).key = secret()?,
+        "xxtea-mode" => {
+            xxtea(config).mode = if value.eq_ignore_ascii_case("whole") {
+                XxteaMode::WholeMessage
+            } else {
+                XxteaMode::Block(block_mode(key, value)?)
+            }
+        }
+        "xxtea-iv" => xxtea(config).iv = value.as_bytes().to_vec(),
+        "xxtea-block-size" => xxtea(config).block_size = number(key, value)?,
+
+        "aes-key" => aes(config).key = secret()?,
+        "aes-key-size" => aes(config).key_size = choice(key, value, &KEY_SIZES)?,
+        "aes-mode" => aes(config).mode = block_mode(key, value)?,
+        "aes-iv" => aes(config).iv = value.as_bytes().to_vec(),
+
+        "chacha-key" => {
+            let chacha = config.chacha20poly1305.get_or_insert_with(Default::default);
+            chacha.key = secret()?;
+        }
+
+        _ => return Err(anyhow!("Unknown setting {}", key)),
+    }
+
+    Ok(())
+}
+
+fn xxtea(config: &mut Config) -> &mut XxteaConfig {
+    config.xxtea.get_or_insert_with(Default::default)
+}
+
+fn aes(config: &mut Config) -> &mut AesConfig {
+    config.aes.get_or_insert_with(Default::default)
+}
+
+/// Ring and position only mean something once there is a Greek wheel
+fn greek<'a>(enigma: &'a mut EnigmaConfig, key: &str) -> Result<&'a mut RotorConfig> {
+    enigma
+        .greek
+        .as_mut()
+        .ok_or_else(|| anyhow!("{} needs a Greek wheel in enigma-greek", key))
+}
+
+fn number<T: FromStr>(key: &str, value: &str) -> Result<T> {
+    value
+        .parse()
+        .map_err(|_| anyhow!("{} must be a number", key))
+}
+
+/// Separated by spaces
+fn notches(key: &str, value: &str) -> Result<Vec<u8>> {
+    value
+        .split_whitespace()
+        .map(|notch| number(key, notch))
+        .collect()
+}
+
+/// Writes `key = value` lines that [`parse`] reads back
+pub fn format(entries: &[(&str, String)]) -> String {
+    entries
+        .iter()
+        .map(|(key, value)| format!("{} = {}\n", key, value))
+        .collect()
+}
+
+pub fn name<T: PartialEq>(choices: &[(&str, T)], value: T) -> String {
+    choices
+        .iter()
+        .find(|(_, choice)| *choice == value)
+        .map(|(name, _)| name.to_string())
+        .expect("Every value has a name")
+}
+
+pub fn choice<T: Copy>(key: &str, value: &str, choices: &[(&str, T)]) -> Result<T> {
+    choices
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(value))
+        .map(|(_, choice)| *choice)
+        .ok_or_else(|| {
+            anyhow!(
+                "{} must be one of {}",
+                key,
+                choices
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })
+}
+
+/// Named like the extension of its sum files
+pub fn hash_algorithm(key: &str, value: &str) -> Result<HashAlgorithm> {
+    let choices: Vec<_> = HashAlgorithm::ALL
+        .into_iter()
+        .map(|algorithm| (algorithm.sum_extension(), algorithm))
+        .collect();
+
+    choice(key, value, &choices)
+}
+
+pub fn block_mode_name(mode: BlockMode) -> String {
+    format!("{}", mode).to_lowercase()
+}
+
+pub fn block_mode(key: &str, value: &str) -> Result<BlockMode> {
+    let names: Vec<_> = BlockMode::ALL
+        .into_iter()
+        .map(|mode| (block_mode_name(mode), mode))
+        .collect();
+    let choices: Vec<_> = names
+        .iter()
+        .map(|(name, mode)| (name.as_str(), *mode))
+        .collect();
+
+    choice(key, value, &choices)
+}
+
+/// Catalogue entry with this name, anything else is taken as a wiring
+pub fn component(components: &[Component], value: &str) -> Option<Component> {
+    components
+        .iter()
+        .find(|component| component.name == value)
+        .copied()
+}
+
+pub fn wiring(components: &[Component], value: String) -> String {
+    component(components, &value).map_or(value, |component| component.wiring.to_owned())
+}
+
+/// A rotor named from the catalogue brings its notches along, a hand typed
+/// wiring keeps the current ones
+fn rotor(rotor: &mut RotorConfig, value: &str) {
+    match component(&catalogue::ROTORS, value) {
+        Some(component) => {
+            rotor.wiring = component.wiring.to_owned();
+            rotor.notches = component.notch_positions();
+        }
+        None => rotor.wiring = value.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply, defaults, parse, KEYS};
+    use zi_core::algorithms::enigma::alg::EnigmaMachine;
+    use zi_core::algorithms::enigma::catalogue;
+    use zi_core::algorithms::modes::alg::BlockMode;
+    use zi_core::algorithms::xxtea::alg::XxteaMode;
+    use zi_core::algorithms::AlgorithmOption;
+    use zi_core::hash::HashAlgorithm;
+    use zi_core::utils::get_algorithm;
+
+    #[test]
+    fn config_files_are_key_value_lines() {
+        let entries = parse("# for CI\nalgorithm = aes\n\n  aes-key=  s3cret = x \n").unwrap();
+
+        assert_eq!(
+            entries,
+            [
+                (String::from("algorithm"), String::from("aes")),
+                (String::from("aes-key"), String::from("s3cret = x")),
+            ]
+        );
+        assert!(parse("algorithm aes").is_err());
+    }
+
+    #[test]
+    fn keys_set_typed_fields() {
+        let mut config = defaults();
+
+        for (key, value) in [
+            ("algorithm", "XXTEA"),
+            ("xxtea-mode", "ctr"),
+            ("xxtea-key", "k"),
+            ("xxtea-block-size", "16"),
+            ("tcp-hash", "sha256"),
+            ("shared-secret", ""),
+            ("kdf-iterations", "10"),
+            ("enigma-machine", "m4"),
+            ("enigma-notch1", "6 19"),
+            ("enigma-greek-position", "3"),
+        ] {
+            apply(&mut config, key, value).unwrap();
+        }
+
+        assert_eq!(config.algorithm, AlgorithmOption::Xxtea);
+        let xxtea = config.xxtea().unwrap();
+        assert_eq!(xxtea.mode, XxteaMode::Block(BlockMode::Ctr));
+        assert_eq!(xxtea.key.as_str(), "k");
+        assert_eq!(xxtea.block_size, 16);
+        assert_eq!(config.integrity_hash, HashAlgorithm::Sha256);
+        assert_eq!(config.shared_secret, None);
+        assert_eq!(config.kdf_iterations, 10);
+        assert_eq!(config.enigma_machine, EnigmaMachine::M4);
+        let enigma = config.enigma().unwrap();
+        assert_eq!(enigma.rotors[0].notches, vec![6, 19]);
+        assert_eq!(enigma.greek.as_ref().map(|greek| greek.position), Some(3));
+        assert!(get_algorithm(&config).is_ok());
+    }
+
+    #[test]
+    fn rotors_can_be_named() {
+        let mut config = defaults();
+
+        apply(&mut config, "enigma-rotor2", "VI").unwrap();
+        apply(&mut config, "enigma-reflector", "UKW-C").unwrap();
+
+        let enigma = config.enigma().unwrap();
+        assert_eq!(enigma.rotors[1].wiring, catalogue::ROTORS[5].wiring);
+        assert_eq!(enigma.rotors[1].notches, vec![6, 19]);
+        assert_eq!(enigma.reflector, catalogue::UKW_C);
+
+        apply(&mut config, "enigma-rotor2", "abcdefghijklmnopqrstuvwxyz").unwrap();
+        assert_eq!(config.enigma().unwrap().rotors[1].notches, vec![6, 19]);
+    }
+
+    #[test]
+    fn bad_keys_and_values_are_errors() {
+        let mut config = defaults();
+
+        assert!(apply(&mut config, "colour", "blue").is_err());
+        assert!(apply(&mut config, "algorithm", "rot13").is_err());
+        assert!(apply(&mut config, "aes-key-size", "512").is_err());
+        assert!(apply(&mut config, "aes-mode", "gcm").is_err());
+        assert!(apply(&mut config, "aes-key", "").is_err());
+        assert!(apply(&mut config, "kdf-iterations", "0").is_err());
+        assert!(apply(&mut config, "enigma-ring1", "b").is_err());
+        assert!(apply(&mut config, "enigma-notch2", "1 x").is_err());
+
+        apply(&mut config, "enigma-greek", "").unwrap();
+        assert!(apply(&mut config, "enigma-greek-ring", "1").is_err());
+    }
+
+    #[test]
+    fn every_listed_key_is_understood() {
+        for key in KEYS {
+            let mut config = defaults();
+            let value = match key {
+                "algorithm" => "aes",
+                "format" => "raw",
+                "tcp-hash" => "md5",
+                "enigma-machine" => "m3",
+                "enigma-text-mode" => "keep",
+                "enigma-groups" => "true",
+                "xxtea-mode" => "whole",
+                "aes-key-size" => "256",
+                "aes-mode" => "cbc",
+                _ => "1",
+            };
+
+            assert!(apply(&mut config, key, value).is_ok(), "{}", key);
+        }
+    }
+}
@@ -0,0 +1,543 @@
+//! Headless mode: `zi <command> ...` runs one command instead of opening the
+//! window, for scripts, CI and servers without a display.
+
+mod args;
+pub mod config;
+
+use std::fmt::Display;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use anyhow::{anyhow, Context};
+use tokio::net::TcpListener;
+
+use crate::watcher;
+use zi_core::algorithms::{AuthenticationError, Operation};
+use zi_core::config::Config;
+use zi_core::container::WrongKeyError;
+use zi_core::hash::sums::{self, Check};
+use zi_core::hash::tth::base32;
+use zi_core::hash::HashAlgorithm;
+use zi_core::transfer::{self, IntegrityError};
+use zi_core::utils::{get_algorithm, process_file};
+
+use args::Args;
+
+const USAGE: &str = "\
+Usage: zi [<command> [<args>]]
+
+Without a command the window opens.
+
+Commands:
+  encrypt <file>... [--out <dir>]     Encrypt files, next to them unless --out is given
+  decrypt <file>... [--out <dir>]     Decrypt files, the container picks the algorithm
+  hash <path>... [--hash <name>] [--base32]
+                                      Print a sum file of the files, directories recurse and
+                                      name their files relative to themselves
+  hash --check <sum file> [<dir>] [--hash <name>]
+                                      Verify the files a sum file lists, in <dir> or next to it
+  send <file> --to <host:port>        Encrypt a file and send it to a receiver
+  receive --port <port> [--bind <address>] [--out <dir>] [--once]
+                                      Receive files until interrupted, or just one
+  watch <dir> --out <dir> [--decrypt] Encrypt (or decrypt) every file created in <dir>
+
+Settings come from --config <file>, a file of `key = value` lines, and are
+overridden by --<key> <value> flags. Hashes are named like their sum files:
+tiger, tiger160, tiger128, tiger2, sha1, sha256, sha512, md5, crc32.
+
+Exit status:
+  0  success
+  1  failure, e.g. a file could not be read or written
+  2  the command line is wrong
+  3  the settings are invalid
+  4  verification failed: wrong key, tampered data or a checksum mismatch
+  5  network failure
+
+Setting keys:
+";
+
+/// Exit statuses, explained in [`USAGE`]
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Status {
+    Failure = 1,
+    Usage = 2,
+    Settings = 3,
+    Verification = 4,
+    Network = 5,
+}
+
+struct Failure {
+    status: Status,
+    error: anyhow::Error,
+}
+
+impl From<anyhow::Error> for Failure {
+    fn from(error: anyhow::Error) -> Self {
+        Failure {
+            status: classify(&error),
+            error,
+        }
+    }
+}
+
+fn usage(message: impl Display) -> Failure {
+    Failure {
+        status: Status::Usage,
+        error: anyhow!("{}", message),
+    }
+}
+
+fn invalid_settings(error: anyhow::Error) -> Failure {
+    Failure {
+        status: Status::Settings,
+        error,
+    }
+}
+
+/// Tells apart the failures a script may want to react to
+fn classify(error: &anyhow::Error) -> Status {
+    for cause in error.chain() {
+        if cause.is::<AuthenticationError>()
+            || cause.is::<WrongKeyError>()
+            || cause.is::<IntegrityError>()
+        {
+            return Status::Verification;
+        }
+
+        if let Some(err) = cause.downcast_ref::<std::io::Error>() {
+            if matches!(
+                err.kind(),
+                ErrorKind::ConnectionRefused
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::NotConnected
+                    | ErrorKind::AddrInUse
+                    | ErrorKind::AddrNotAvailable
+                    | ErrorKind::TimedOut
+            ) {
+                return Status::Network;
+            }
+        }
+    }
+
+    Status::Failure
+}
+
+pub async fn run(args: Vec<String>) -> ExitCode {
+    match execute(args).await {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(failure) => {
+            eprintln!("zi: {:#}", failure.error);
+            ExitCode::from(failure.status as u8)
+        }
+    }
+}
+
+async fn execute(args: Vec<String>) -> Result<(), Failure> {
+    let mut args = Args::parse(args).map_err(usage)?;
+
+    if args.command == "help" || args.switch("help") {
+        print!("{}", USAGE);
+        config::KEYS.iter().for_each(|key| println!("  {}", key));
+        return Ok(());
+    }
+
+    match args.command.as_str() {
+        "encrypt" => process(&mut args, Operation::Encrypt).await,
+        "decrypt" => process(&mut args, Operation::Decrypt).await,
+        "hash" => hash(&mut args).await,
+        "send" => send(&mut args).await,
+        "receive" => receive(&mut args).await,
+        "watch" => watch(&mut args).await,
+        command => Err(usage(format!("Unknown command {}, see zi help", command))),
+    }
+}
+
+/// Settings from `--config` and the options the command left over
+fn settings(args: &mut Args) -> Result<Config, Failure> {
+    let mut settings = config::defaults();
+
+    if let Some(path) = args.take("config") {
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("Could not read {}", path))
+            .map_err(invalid_settings)?;
+
+        for (key, value) in config::parse(&text).map_err(invalid_settings)? {
+            config::apply(&mut settings, &key, &value)
+                .with_context(|| format!("In {}", path))
+                .map_err(invalid_settings)?;
+        }
+    }
+
+    for (key, value) in args.options.drain(..) {
+        if !config::KEYS.contains(&key.as_str()) {
+            return Err(usage(format!("Unknown option --{}", key)));
+        }
+
+        config::apply(&mut settings, &key, &value).map_err(invalid_settings)?;
+    }
+
+    return Ok(settings);
+}
+
+fn allow_switches(args: &Args, allowed: &[&str]) -> Result<(), Failure> {
+    match args
+        .switches
+        .iter()
+        .find(|switch| !allowed.contains(&switch.as_str()))
+    {
+        Some(switch) => Err(usage(format!(
+            "--{} does not apply to {}",
+            switch, args.command
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn parent_dir(path: &Path) -> PathBuf {
+    path.parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+        .to_path_buf()
+}
+
+async fn process(args: &mut Args, operation: Operation) -> Result<(), Failure> {
+    let out = args.take("out").map(PathBuf::from);
+    let config = settings(args)?;
+    allow_switches(args, &[])?;
+
+    if args.paths.is_empty() {
+        return Err(usage(format!("{} needs at least one file", args.command)));
+    }
+
+    // When decrypting, the container header may select a different algorithm
+    if let Operation::Encrypt = operation {
+        get_algorithm(&config).map_err(invalid_settings)?;
+    }
+
+    let mut first_failure = None;
+    for path in &args.paths {
+        let file = PathBuf::from(path);
+        let dest_dir = out.clone().unwrap_or_else(|| parent_dir(&file));
+
+        match process_file(&file, &config, operation, &dest_dir).await {
+            Ok(new_file) => println!("{}", new_file.display()),
+            Err(err) => {
+                eprintln!("zi: {}: {:#}", path, err);
+                first_failure.get_or_insert(classify(&err));
+            }
+        }
+    }
+
+    match first_failure {
+        Some(status) => Err(Failure {
+            status,
+            error: anyhow!("Not every file could be processed"),
+        }),
+        None => Ok(()),
+    }
+}
+
+async fn hash(args: &mut Args) -> Result<(), Failure> {
+    let algorithm = args
+        .take("hash")
+        .map(|name| config::hash_algorithm("--hash", &name))
+        .transpose()
+        .map_err(usage)?;
+    let sum_file = args.take("check").map(PathBuf::from);
+    allow_switches(args, &["base32"])?;
+
+    if let Some((key, _)) = args.options.first() {
+        return Err(usage(format!("Unknown option --{}", key)));
+    }
+
+    let Some(sum_file) = sum_file else {
+        if args.paths.is_empty() {
+            return Err(usage("hash needs at least one file or directory"));
+        }
+
+        // Files below a directory are named relative to it, like the Hash
+        // page does, the others as given
+        let mut groups = Vec::new();
+        for path in args.paths.iter().map(PathBuf::from) {
+            if path.is_dir() {
+                groups.push((sums::collect_files(&path)?, path));
+            } else {
+                groups.push((vec![path], PathBuf::new()));
+            }
+        }
+
+        let algorithm = algorithm.unwrap_or_default();
+        let digests = tokio::task::spawn_blocking(move || {
+            groups
+                .iter()
+                .map(|(files, base)| sums::hash_files(algorithm, base, files, &mut |_| ()))
+                .collect::<anyhow::Result<Vec<_>>>()
+        })
+        .await
+        .expect("Hashing panicked")?
+        .concat();
+
+        if args.switch("base32") {
+            for file in digests {
+                println!("{}  {}", base32(&file.digest), file.name);
+            }
+        } else {
+            print!("{}", sums::format(&digests));
+        }
+
+        return Ok(());
+    };
+
+    let dir = match args.paths.as_slice() {
+        [] => parent_dir(&sum_file),
+        [dir] => PathBuf::from(dir),
+        _ => return Err(usage("--check takes at most one directory")),
+    };
+    let algorithm = algorithm
+        .or_else(|| HashAlgorithm::from_sum_file(&sum_file))
+        .unwrap_or_default();
+
+    let sums = std::fs::read_to_string(&sum_file)
+        .with_context(|| format!("Could not read {}", sum_file.display()))?;
+    let checks =
+        tokio::task::spawn_blocking(move || sums::verify(algorithm, &dir, &sums, &mut |_| ()))
+            .await
+            .expect("Verifying panicked")?;
+
+    for file in &checks {
+        println!("{}: {}", file.name, file.check);
+    }
+
+    let failed = checks.iter().filter(|file| file.check != Check::Ok).count();
+    if failed > 0 {
+        return Err(Failure {
+            status: Status::Verification,
+            error: anyhow!("{} of {} files failed", failed, checks.len()),
+        });
+    }
+
+    Ok(())
+}
+
+async fn send(args: &mut Args) -> Result<(), Failure> {
+    let address = args
+        .take("to")
+        .ok_or_else(|| usage("send needs --to <host:port>"))?;
+    let config = settings(args)?;
+    allow_switches(args, &[])?;
+
+    let [file] = args.paths.as_slice() else {
+        return Err(usage("send takes one file"));
+    };
+
+    get_algorithm(&config).map_err(invalid_settings)?;
+
+    transfer::send_file(&config, Path::new(file), &address, |message| {
+        eprintln!("{}", message)
+    })
+    .await?;
+
+    Ok(())
+}
+
+async fn receive(args: &mut Args) -> Result<(), Failure> {
+    let port: u16 = args
+        .take("port")
+        .ok_or_else(|| usage("receive needs --port <port>"))?
+        .parse()
+        .map_err(|_| usage("--port must be a number up to 65535"))?;
+    let bind = args.take("bind").unwrap_or(String::from("127.0.0.1"));
+    let out = args
+        .take("out")
+        .map(PathBuf::from)
+        .unwrap_or(PathBuf::from("."));
+    let config = settings(args)?;
+    allow_switches(args, &["once"])?;
+
+    if !args.paths.is_empty() {
+        return Err(usage("receive takes no paths, use --out <dir>"));
+    }
+
+    let listener = TcpListener::bind((bind.as_str(), port))
+        .await
+        .context("Couldn't start listening")?;
+    eprintln!(
+        "Listening on {}",
+        listener.local_addr().context("Faulty listener")?
+    );
+
+    loop {
+        let (socket, addr) = tokio::select! {
+            accepted = listener.accept() => accepted.context("Faulty connection")?,
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        };
+        eprintln!("Accepted connection with: {}", addr);
+
+        let received =
+            transfer::receive_file(socket, &config, &out, |message| eprintln!("{}", message)).await;
+
+        match received {
+            Ok(new_file) => println!("{}", new_file.display()),
+            Err(err) if args.switch("once") => return Err(err.into()),
+            Err(err) => eprintln!("zi: {:#}", err),
+        }
+
+        if args.switch("once") {
+            return Ok(());
+        }
+    }
+}
+
+async fn watch(args: &mut Args) -> Result<(), Failure> {
+    let out = args
+        .take("out")
+        .map(PathBuf::from)
+        .ok_or_else(|| usage("watch needs --out <dir>"))?;
+    let config = settings(args)?;
+    allow_switches(args, &["decrypt"])?;
+
+    let [dir] = args.paths.as_slice() else {
+        return Err(usage("watch takes one directory"));
+    };
+    let dir = PathBuf::from(dir);
+
+    watcher::check_dirs(&dir, &out).map_err(usage)?;
+
+    let operation = if args.switch("decrypt") {
+        Operation::Decrypt
+    } else {
+        get_algorithm(&config).map_err(invalid_settings)?;
+        Operation::Encrypt
+    };
+
+    let (_watcher, mut created_files) = watcher::watch(&dir)?;
+    eprintln!("Watching {}", dir.display());
+
+    loop {
+        let file = tokio::select! {
+            file = created_files.next() => match file {
+                Some(file) => file,
+                None => return Ok(()),
+            },
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        };
+
+        match process_file(&file, &config, operation, &out).await {
+            Ok(new_file) => println!("{}", new_file.display()),
+            Err(err) => eprintln!("zi: {}: {:#}", file.display(), err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use anyhow::{anyhow, Context};
+
+    use super::{classify, execute, Status};
+    use zi_core::algorithms::AlgorithmOption;
+    use zi_core::container::WrongKeyError;
+    use zi_core::transfer::IntegrityError;
+    use zi_core::utils::TempDir;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split(' ').map(String::from).collect()
+    }
+
+    async fn status(line: &str) -> Option<Status> {
+        execute(args(line))
+            .await
+            .err()
+            .map(|failure| failure.status)
+    }
+
+    #[test]
+    fn failures_are_told_apart() {
+        let wrong_key = anyhow::Error::from(WrongKeyError {
+            algorithm: AlgorithmOption::Aes,
+        });
+        let tampered = Err::<(), _>(IntegrityError).context("While receiving");
+        let refused = anyhow::Error::from(std::io::Error::from(ErrorKind::ConnectionRefused));
+        let missing = anyhow::Error::from(std::io::Error::from(ErrorKind::NotFound));
+
+        assert_eq!(classify(&wrong_key), Status::Verification);
+        assert_eq!(classify(&tampered.unwrap_err()), Status::Verification);
+        assert_eq!(classify(&refused), Status::Network);
+        assert_eq!(classify(&missing), Status::Failure);
+        assert_eq!(classify(&anyhow!("other")), Status::Failure);
+    }
+
+    #[tokio::test]
+    async fn files_round_trip_and_wrong_keys_fail() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("notes.txt");
+        std::fs::write(&file, "headless").unwrap();
+        let path = dir.path().display();
+
+        let settings = "--algorithm aes --aes-key right --kdf-iterations 10";
+        assert_eq!(
+            status(&format!("encrypt {}/notes.txt {}", path, settings)).await,
+            None
+        );
+        assert_eq!(
+            status(&format!(
+                "decrypt {}/notes_encrypted.txt --out {} {}",
+                path, path, settings
+            ))
+            .await,
+            None
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("notes_encrypted_decrypted.txt")).unwrap(),
+            "headless"
+        );
+
+        assert_eq!(
+            status(&format!(
+                "decrypt {}/notes_encrypted.txt --aes-key wrong",
+                path
+            ))
+            .await,
+            Some(Status::Verification)
+        );
+    }
+
+    #[tokio::test]
+    async fn bad_command_lines_and_settings_have_their_own_status() {
+        assert_eq!(status("frobnicate").await, Some(Status::Usage));
+        assert_eq!(status("encrypt").await, Some(Status::Usage));
+        assert_eq!(status("encrypt f --colour blue").await, Some(Status::Usage));
+        assert_eq!(status("encrypt f --once").await, Some(Status::Usage));
+        assert_eq!(status("receive --port 70000").await, Some(Status::Usage));
+        assert_eq!(
+            status("encrypt f --algorithm rot13").await,
+            Some(Status::Settings)
+        );
+        assert_eq!(
+            status("encrypt f --algorithm aes --aes-key").await,
+            Some(Status::Usage)
+        );
+        assert_eq!(
+            status("encrypt f --config /no/such/config").await,
+            Some(Status::Settings)
+        );
+        assert_eq!(status("help").await, None);
+    }
+
+    #[tokio::test]
+    async fn hash_check_reports_mismatches() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("a"), "a").unwrap();
+        let sum_file = dir.path().join("sums.md5");
+        std::fs::write(&sum_file, "0cc175b9c0f1b6a831c399e269772661  a\n").unwrap();
+        let check = format!("hash --check {}", sum_file.display());
+
+        assert_eq!(status(&check).await, None);
+
+        std::fs::write(dir.path().join("a"), "b").unwrap();
+        assert_eq!(status(&check).await, Some(Status::Verification));
+    }
+}
@@ -1,3 +1,5 @@
 mod navigation;
+mod profile_picker;
 
 pub use navigation::*;
+pub use profile_picker::*;
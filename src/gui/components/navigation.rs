@@ -2,24 +2,33 @@ use std::path::PathBuf;
 
 use iced::{
     alignment,
-    widget::{button, horizontal_space, row, svg, text},
+    widget::{button, horizontal_space, pick_list, row, svg, text},
     Border, Color, Element, Length, Shadow, Theme,
 };
 
 use crate::gui::{
     pages::Page,
     state::{
-        messages::{Message, NavigationMessage},
+        messages::{Message, NavigationMessage, ProfileMessage},
         State,
     },
 };
 
 const TAB_RADIUS: f32 = 10.0;
+const TAB_WIDTH: f32 = 100.0;
+
+pub fn navigation(state: &State) -> Element<'_, Message> {
+    let profiles = state.profiles.read().unwrap();
+    let profile_picker = pick_list(
+        profiles.names(),
+        Some(profiles.active().name.clone()),
+        |name| Message::Profile(ProfileMessage::Picked(name)),
+    )
+    .width(150);
 
-pub fn navigation(state: &State) -> Element<Message> {
     row![
         button(text("FS Watcher").align_x(alignment::Horizontal::Center))
-            .width(150)
+            .width(TAB_WIDTH)
             .on_press(Message::Navigation(NavigationMessage::GoToFSWPage))
             .style(move |theme: &Theme, status| {
                 let mut style = if let Page::Fsw = state.page {
@@ -38,7 +47,7 @@ pub fn navigation(state: &State) -> Element<Message> {
                 style
             }),
         button(text("Manual").align_x(alignment::Horizontal::Center))
-            .width(150)
+            .width(TAB_WIDTH)
             .on_press(Message::Navigation(NavigationMessage::GoToManualPage))
             .style(move |theme: &Theme, status| {
                 let mut style = if let Page::Manual = state.page {
@@ -57,7 +66,7 @@ pub fn navigation(state: &State) -> Element<Message> {
                 style
             }),
         button(text("Tcp").align_x(alignment::Horizontal::Center))
-            .width(150)
+            .width(TAB_WIDTH)
             .on_press(Message::Navigation(NavigationMessage::GoToTcpPage))
             .style(move |theme: &Theme, status| {
                 let mut style = if let Page::Tcp = state.page {
@@ -73,9 +82,71 @@ pub fn navigation(state: &State) -> Element<Message> {
                     bottom_left: 0.0,
                 };
 
+                style
+            }),
+        button(text("Hash").align_x(alignment::Horizontal::Center))
+            .width(TAB_WIDTH)
+            .on_press(Message::Navigation(NavigationMessage::GoToHashPage))
+            .style(move |theme: &Theme, status| {
+                let mut style = if let Page::Hash = state.page {
+                    button::primary(theme, status)
+                } else {
+                    button::secondary(theme, status)
+                };
+
+                style.border.radius = iced::border::Radius {
+                    top_left: TAB_RADIUS,
+                    top_right: TAB_RADIUS,
+                    bottom_right: 0.0,
+                    bottom_left: 0.0,
+                };
+
+                style
+            }),
+        button(text("Enigma").align_x(alignment::Horizontal::Center))
+            .width(TAB_WIDTH)
+            .on_press(Message::Navigation(
+                NavigationMessage::GoToEnigmaSimulatorPage
+            ))
+            .style(move |theme: &Theme, status| {
+                let mut style = if let Page::EnigmaSimulator = state.page {
+                    button::primary(theme, status)
+                } else {
+                    button::secondary(theme, status)
+                };
+
+                style.border.radius = iced::border::Radius {
+                    top_left: TAB_RADIUS,
+                    top_right: TAB_RADIUS,
+                    bottom_right: 0.0,
+                    bottom_left: 0.0,
+                };
+
+                style
+            }),
+        button(text("Analysis").align_x(alignment::Horizontal::Center))
+            .width(TAB_WIDTH)
+            .on_press(Message::Navigation(
+                NavigationMessage::GoToEnigmaAnalysisPage
+            ))
+            .style(move |theme: &Theme, status| {
+                let mut style = if let Page::EnigmaAnalysis = state.page {
+                    button::primary(theme, status)
+                } else {
+                    button::secondary(theme, status)
+                };
+
+                style.border.radius = iced::border::Radius {
+                    top_left: TAB_RADIUS,
+                    top_right: TAB_RADIUS,
+                    bottom_right: 0.0,
+                    bottom_left: 0.0,
+                };
+
                 style
             }),
         horizontal_space(),
+        profile_picker,
         button(row![
             text(""),
             svg(svg::Handle::from_path(PathBuf::from("./assets/gear.svg")))
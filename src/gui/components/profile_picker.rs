@@ -0,0 +1,27 @@
+use iced::{
+    alignment,
+    widget::{pick_list, row, text},
+    Element,
+};
+
+use crate::gui::state::{messages::Message, ProfilePin, State};
+
+/// Lets a page follow the active profile or pin one of its own
+pub fn profile_picker(
+    state: &State,
+    pin: Option<u32>,
+    on_pick: impl Fn(ProfilePin) -> Message + 'static,
+) -> Element<'_, Message> {
+    let profiles = state.profiles.read().unwrap();
+
+    row![
+        text("Profile: "),
+        pick_list(
+            ProfilePin::options(&profiles),
+            Some(ProfilePin::new(&profiles, pin)),
+            on_pick
+        ),
+    ]
+    .align_y(alignment::Vertical::Center)
+    .into()
+}
@@ -0,0 +1,216 @@
+use iced::{
+    alignment,
+    widget::{
+        button, checkbox, column, horizontal_space, pick_list, progress_bar, row, scrollable, text,
+        text_input, Row,
+    },
+    Alignment, Element, Font, Length,
+};
+
+use crate::{
+    gui::state::messages::{EnigmaAnalysisMessage, Message},
+    State,
+};
+use zi_core::algorithms::enigma::{bombe, catalogue};
+use zi_core::config::{EnigmaConfig, RotorConfig};
+
+/// Candidates listed, the rest are usually noise
+const SHOWN: usize = 5;
+/// Letters of each candidate's plaintext shown
+const PREVIEW: usize = 40;
+/// Bombe stops listed, a poor menu can give thousands
+const STOPS_SHOWN: usize = 50;
+/// Crib offsets suggested
+const OFFSETS_SHOWN: usize = 15;
+
+pub fn enigma_analysis_page(state: &State) -> Element<'_, Message> {
+    let analysis = &state.analysis;
+
+    let rotors =
+        catalogue::ROTORS
+            .iter()
+            .enumerate()
+            .fold(Row::new().spacing(10), |rotors, (i, rotor)| {
+                rotors.push(checkbox(rotor.name, analysis.rotors[i]).on_toggle_maybe(
+                    (!analysis.is_running).then_some(move |checked| {
+                        Message::EnigmaAnalysis(EnigmaAnalysisMessage::RotorToggled(i, checked))
+                    }),
+                ))
+            });
+
+    let run = |label: &'static str, message: EnigmaAnalysisMessage, can_start: bool| {
+        if analysis.is_running {
+            button(text("Cancel").align_x(alignment::Horizontal::Center))
+                .on_press(Message::EnigmaAnalysis(EnigmaAnalysisMessage::Cancel))
+        } else {
+            button(text(label).align_x(alignment::Horizontal::Center))
+                .on_press_maybe(can_start.then_some(Message::EnigmaAnalysis(message)))
+        }
+    };
+
+    let ciphertext = analysis.ciphertext.as_deref().unwrap_or("");
+    let crib = analysis.crib.as_deref().unwrap_or("");
+    let offset = analysis.crib_offset.as_deref().unwrap_or("");
+
+    let offsets = bombe::possible_offsets(ciphertext, crib);
+    let mut crib_hint = format!(
+        "Offsets where no letter encrypts to itself: {}",
+        offsets
+            .iter()
+            .take(OFFSETS_SHOWN)
+            .map(|offset| offset.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+    if offsets.len() > OFFSETS_SHOWN {
+        crib_hint.push_str(" ...");
+    }
+    let menu = offset
+        .parse()
+        .map_err(|_| anyhow::anyhow!("The offset is not a number"))
+        .and_then(|offset| bombe::menu(ciphertext, crib, offset));
+    let menu_hint = match &menu {
+        Ok(menu) => format!("The menu has {} loops", bombe::loops(menu)),
+        Err(err) => format!("{}", err),
+    };
+
+    let results = analysis
+        .candidates
+        .iter()
+        .take(SHOWN)
+        .enumerate()
+        .fold(column![].spacing(5), |results, (i, candidate)| {
+            results.push(
+                row![
+                    column![
+                        text(format!(
+                            "{}  ({:.2})",
+                            summary(&candidate.config),
+                            candidate.score
+                        ))
+                        .font(Font::MONOSPACE),
+                        text(
+                            candidate
+                                .plaintext
+                                .chars()
+                                .take(PREVIEW)
+                                .collect::<String>()
+                                .to_uppercase()
+                        )
+                        .font(Font::MONOSPACE),
+                    ]
+                    .width(Length::Fill),
+                    button(text("Apply").align_x(alignment::Horizontal::Center)).on_press(
+                        Message::EnigmaAnalysis(EnigmaAnalysisMessage::ApplyCandidate(i))
+                    ),
+                ]
+                .align_y(Alignment::Center),
+            )
+        })
+        .push_maybe(
+            (!analysis.stops.is_empty())
+                .then_some(text(format!("Bombe stops: {}", analysis.stops.len()))),
+        );
+
+    let results =
+        analysis
+            .stops
+            .iter()
+            .take(STOPS_SHOWN)
+            .enumerate()
+            .fold(results, |results, (i, stop)| {
+                results.push(
+                    row![
+                        text(summary(&stop.config))
+                            .font(Font::MONOSPACE)
+                            .width(Length::Fill),
+                        button(text("Apply").align_x(alignment::Horizontal::Center))
+                            .on_press(Message::EnigmaAnalysis(EnigmaAnalysisMessage::ApplyStop(i))),
+                    ]
+                    .align_y(Alignment::Center),
+                )
+            });
+
+    column![
+        text("Ciphertext"),
+        text_input("Letters only, everything else is skipped", ciphertext).on_input_maybe(
+            (!analysis.is_running).then_some(|val: String| {
+                let value = if val.is_empty() { None } else { Some(val) };
+                Message::EnigmaAnalysis(EnigmaAnalysisMessage::CiphertextChanged(value))
+            })
+        ),
+        horizontal_space().height(10),
+        text("Rotors to try"),
+        rotors,
+        horizontal_space().height(10),
+        row![
+            text("Reflector"),
+            horizontal_space().width(10),
+            pick_list(
+                catalogue::REFLECTORS,
+                Some(analysis.reflector),
+                |reflector| {
+                    Message::EnigmaAnalysis(EnigmaAnalysisMessage::ReflectorPicked(reflector))
+                }
+            ),
+            horizontal_space(),
+            run(
+                "Search",
+                EnigmaAnalysisMessage::Start,
+                analysis.ciphertext.is_some()
+            ),
+        ]
+        .align_y(Alignment::Center),
+        horizontal_space().height(10),
+        row![
+            text_input("Crib, a suspected piece of plaintext", crib)
+                .width(Length::FillPortion(3))
+                .on_input_maybe((!analysis.is_running).then_some(|val: String| {
+                    let value = if val.is_empty() { None } else { Some(val) };
+                    Message::EnigmaAnalysis(EnigmaAnalysisMessage::CribChanged(value))
+                })),
+            text_input("Offset", offset)
+                .width(Length::FillPortion(1))
+                .on_input_maybe((!analysis.is_running).then_some(|val: String| {
+                    let value = if val.is_empty() { None } else { Some(val) };
+                    Message::EnigmaAnalysis(EnigmaAnalysisMessage::CribOffsetChanged(value))
+                })),
+            run("Bombe", EnigmaAnalysisMessage::StartBombe, menu.is_ok()),
+        ]
+        .spacing(5),
+        text(crib_hint).size(12),
+        text(menu_hint).size(12),
+        horizontal_space().height(10),
+        progress_bar(0.0..=1.0, analysis.progress).height(10),
+        horizontal_space().height(10),
+        scrollable(results).height(Length::Fill),
+    ]
+    .padding([20, 40])
+    .into()
+}
+
+/// Rotor order, start positions, ring settings and plugs of a key
+fn summary(config: &EnigmaConfig) -> String {
+    let [first, second, third] = &config.rotors;
+    let name = |rotor: &RotorConfig| {
+        catalogue::identify(&catalogue::ROTORS, Some(&rotor.wiring), None).name
+    };
+
+    format!(
+        "{} {} {}  pos {} {} {}  rings {} {} {}  plugs {}",
+        name(first),
+        name(second),
+        name(third),
+        first.position,
+        second.position,
+        third.position,
+        first.ringstellung,
+        second.ringstellung,
+        third.ringstellung,
+        if config.plugboard.is_empty() {
+            "none"
+        } else {
+            &config.plugboard
+        },
+    )
+}
@@ -0,0 +1,148 @@
+use iced::{
+    alignment,
+    widget::{button, column, container, horizontal_space, row, text, Row},
+    Alignment, Element, Font, Length, Theme,
+};
+
+use crate::{
+    gui::state::messages::{EnigmaSimulatorMessage, Message},
+    State,
+};
+use zi_core::algorithms::enigma::simulator::SignalPath;
+
+/// Key and lamp layout of the German machines
+const LAYOUT: [&str; 3] = ["QWERTZUIO", "ASDFGHJK", "PYXCVBNML"];
+
+const KEY_SIZE: f32 = 30.0;
+
+pub fn enigma_simulator_page(state: &State) -> Element<'_, Message> {
+    let simulator = &state.simulator;
+
+    let reset = button(text("Reset").align_x(alignment::Horizontal::Center))
+        .on_press(Message::EnigmaSimulator(EnigmaSimulatorMessage::Reset));
+
+    let Some(machine) = &simulator.machine else {
+        return column![
+            text(simulator.error.clone().unwrap_or_default()),
+            horizontal_space().height(10),
+            reset
+        ]
+        .align_x(Alignment::Center)
+        .padding([50, 100])
+        .into();
+    };
+
+    let windows = machine
+        .windows()
+        .into_iter()
+        .fold(Row::new().spacing(5), |windows, letter| {
+            windows.push(
+                container(text(letter.to_string()).font(Font::MONOSPACE))
+                    .padding([2, 8])
+                    .style(container::bordered_box),
+            )
+        });
+
+    let lit = simulator
+        .last_path
+        .as_ref()
+        .map(|path| path.lamp.to_ascii_uppercase() as char);
+
+    let lampboard = LAYOUT.iter().fold(column![].spacing(3), |board, line| {
+        board.push(line.chars().fold(Row::new().spacing(3), |lamps, letter| {
+            let is_lit = lit == Some(letter);
+
+            lamps.push(
+                container(text(letter.to_string()).font(Font::MONOSPACE))
+                    .center_x(KEY_SIZE)
+                    .center_y(KEY_SIZE)
+                    .style(move |theme: &Theme| {
+                        if is_lit {
+                            let palette = theme.extended_palette();
+                            container::Style::default()
+                                .background(palette.primary.strong.color)
+                                .color(palette.primary.strong.text)
+                                .border(iced::border::rounded(KEY_SIZE / 2.0))
+                        } else {
+                            container::bordered_box(theme)
+                                .border(iced::border::rounded(KEY_SIZE / 2.0))
+                        }
+                    }),
+            )
+        }))
+    });
+
+    let keyboard = LAYOUT.iter().fold(column![].spacing(3), |board, line| {
+        board.push(line.chars().fold(Row::new().spacing(3), |keys, letter| {
+            keys.push(
+                button(
+                    text(letter.to_string())
+                        .font(Font::MONOSPACE)
+                        .align_x(alignment::Horizontal::Center),
+                )
+                .width(KEY_SIZE)
+                .on_press(Message::EnigmaSimulator(
+                    EnigmaSimulatorMessage::KeyPressed(letter),
+                )),
+            )
+        }))
+    });
+
+    let signal = simulator
+        .last_path
+        .as_ref()
+        .map(signal_path)
+        .unwrap_or(String::from("Press a key or type to follow the signal"));
+
+    column![
+        row![
+            text("Rotors"),
+            horizontal_space().width(10),
+            windows,
+            horizontal_space(),
+            reset
+        ]
+        .align_y(Alignment::Center),
+        horizontal_space().height(10),
+        lampboard.align_x(Alignment::Center),
+        horizontal_space().height(10),
+        keyboard.align_x(Alignment::Center),
+        horizontal_space().height(10),
+        text(signal).font(Font::MONOSPACE),
+        horizontal_space().height(10),
+        text(format!("In:  {}", simulator.input_tape)).font(Font::MONOSPACE),
+        text(format!("Out: {}", simulator.output_tape)).font(Font::MONOSPACE),
+    ]
+    .width(Length::Fill)
+    .align_x(Alignment::Center)
+    .padding([20, 40])
+    .into()
+}
+
+/// "A > B > ... > lamp" with a label for every component the current passes
+fn signal_path(path: &SignalPath) -> String {
+    // The M4 has its Greek wheel after the left rotor
+    let rotors = ["R", "M", "L", "G"];
+    let letter = |l: u8| l.to_ascii_uppercase() as char;
+
+    let mut steps = vec![
+        format!("Key {}", letter(path.key)),
+        format!("Plug {}", letter(path.plugboard_in)),
+    ];
+    steps.extend(
+        path.rotors_in
+            .iter()
+            .zip(rotors)
+            .map(|(&l, rotor)| format!("{} {}", rotor, letter(l))),
+    );
+    steps.push(format!("UKW {}", letter(path.reflector)));
+    steps.extend(
+        path.rotors_out
+            .iter()
+            .zip(rotors[..path.rotors_out.len()].iter().rev())
+            .map(|(&l, rotor)| format!("{} {}", rotor, letter(l))),
+    );
+    steps.push(format!("Plug, lamp {}", letter(path.lamp)));
+
+    steps.join(" > ")
+}
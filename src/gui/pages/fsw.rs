@@ -6,11 +6,12 @@ use iced::{
     Element, Length,
 };
 
-use crate::algorithms::Operation;
+use crate::gui::components::profile_picker;
 use crate::gui::state::messages::{FSWPageMessage, Message};
 use crate::State;
+use zi_core::algorithms::Operation;
 
-pub fn fsw_page(state: &State) -> Element<Message> {
+pub fn fsw_page(state: &State) -> Element<'_, Message> {
     let can_run = state.fsw.from.is_some() && state.fsw.to.is_some();
     let from = state
         .fsw
@@ -70,6 +71,12 @@ pub fn fsw_page(state: &State) -> Element<Message> {
         .width(Length::Fill)
         .align_x(alignment::Horizontal::Center),
         vertical_space().height(10),
+        container(profile_picker(state, state.fsw.profile, |pin| {
+            Message::FSW(FSWPageMessage::ProfilePinned(pin))
+        }))
+        .width(Length::Fill)
+        .align_x(alignment::Horizontal::Center),
+        vertical_space().height(10),
         container(
             button(
                 text(if state.fsw.is_on {
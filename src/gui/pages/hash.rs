@@ -0,0 +1,113 @@
+use iced::{
+    alignment,
+    widget::{
+        button, column, horizontal_space, pick_list, progress_bar, row, scrollable, text,
+        text_input,
+    },
+    Alignment, Element, Font, Length,
+};
+
+use crate::{
+    gui::state::messages::{HashPageMessage, Message},
+    State,
+};
+use zi_core::hash::{
+    sums::{hex, Check},
+    tth::base32,
+    HashAlgorithm,
+};
+
+pub fn hash_page(state: &State) -> Element<'_, Message> {
+    let hash = &state.hash;
+
+    let picked = match (&hash.dir, hash.files.as_slice()) {
+        (Some(dir), _) => format!("{} (every file below it)", dir.display()),
+        (None, []) => String::new(),
+        (None, [file]) => format!("{}", file.display()),
+        (None, files) => format!(
+            "{} files in {}",
+            files.len(),
+            hash.base().unwrap().display()
+        ),
+    };
+    let sum_file = hash
+        .sum_file
+        .as_ref()
+        .map(|path| format!("{}", path.display()))
+        .unwrap_or_default();
+
+    let idle = |message: HashPageMessage| (!hash.is_running).then_some(Message::Hash(message));
+
+    let digests = hash
+        .digests
+        .iter()
+        .fold(column![].spacing(5), |digests, file| {
+            digests.push(column![
+                text(&file.name),
+                text(hex(&file.digest)).font(Font::MONOSPACE).size(12),
+                text(base32(&file.digest)).font(Font::MONOSPACE).size(12),
+            ])
+        });
+
+    let results = hash.checks.iter().fold(digests, |results, file| {
+        let status = text(format!("{}", file.check))
+            .font(Font::MONOSPACE)
+            .width(80)
+            .style(match file.check {
+                Check::Ok => text::success,
+                Check::Mismatch | Check::Missing => text::danger,
+            });
+
+        results.push(row![status, text(&file.name)].align_y(Alignment::Center))
+    });
+
+    column![
+        text("Files to hash"),
+        row![
+            text_input("Click the \"Files\" or \"Directory\" button", &picked)
+                .width(Length::Fill)
+                .on_input(|_| Message::Empty),
+            button(text("Files").align_x(alignment::Horizontal::Center))
+                .width(Length::Shrink)
+                .on_press_maybe(idle(HashPageMessage::GetFiles)),
+            button(text("Directory").align_x(alignment::Horizontal::Center))
+                .width(Length::Shrink)
+                .on_press_maybe(idle(HashPageMessage::GetDir)),
+        ],
+        horizontal_space().height(10),
+        row![
+            text("Algorithm"),
+            horizontal_space().width(10),
+            pick_list(HashAlgorithm::ALL, Some(hash.algorithm), |algorithm| {
+                Message::Hash(HashPageMessage::AlgorithmPicked(algorithm))
+            }),
+            horizontal_space(),
+            button(text("Hash").align_x(alignment::Horizontal::Center))
+                .on_press_maybe(idle(HashPageMessage::Start).filter(|_| hash.base().is_some())),
+            horizontal_space().width(10),
+            button(text("Save sum file").align_x(alignment::Horizontal::Center)).on_press_maybe(
+                idle(HashPageMessage::SaveSumFile).filter(|_| !hash.digests.is_empty())
+            ),
+        ]
+        .align_y(Alignment::Center),
+        horizontal_space().height(10),
+        text("Sum file to verify against, checked against the directory above or its own"),
+        row![
+            text_input("Click the \"Choose\" button", &sum_file)
+                .width(Length::Fill)
+                .on_input(|_| Message::Empty),
+            button(text("Choose").align_x(alignment::Horizontal::Center))
+                .width(Length::Shrink)
+                .on_press_maybe(idle(HashPageMessage::GetSumFile)),
+            button(text("Verify").align_x(alignment::Horizontal::Center))
+                .width(Length::Shrink)
+                .on_press_maybe(idle(HashPageMessage::Verify).filter(|_| hash.sum_file.is_some())),
+        ],
+        horizontal_space().height(10),
+        progress_bar(0.0..=1.0, hash.progress).height(10),
+        horizontal_space().height(10),
+        scrollable(results).height(Length::Fill),
+    ]
+    .padding([20, 40])
+    .into()
+}
@@ -0,0 +1,65 @@
+use iced::{
+    alignment,
+    widget::{button, column, container, text, text_input},
+    Element, Length,
+};
+
+use crate::gui::state::messages::{KeystoreMessage, Message};
+use crate::State;
+
+/// Shown instead of every other page while the keystore is locked
+pub fn lock_page(state: &State) -> Element<'_, Message> {
+    let keystore = &state.keystore;
+    let can_submit = !keystore.is_busy && !keystore.password.is_empty();
+    let submit = can_submit.then_some(Message::Keystore(KeystoreMessage::Unlock));
+
+    let (title, hint, action) = if keystore.exists {
+        (
+            "The keystore is locked",
+            "Your keys and profiles are encrypted under the master password",
+            "Unlock",
+        )
+    } else {
+        (
+            "Choose a master password",
+            "It encrypts the keys and profiles ZI saves. It can't be recovered if you forget it.",
+            "Create",
+        )
+    };
+
+    let confirm = (!keystore.exists).then(|| {
+        text_input("Confirm the master password", &keystore.confirm)
+            .secure(true)
+            .on_input(|val| Message::Keystore(KeystoreMessage::ConfirmChanged(val)))
+            .on_submit_maybe(submit.clone())
+    });
+
+    let content = column![
+        text(title).size(24),
+        text(hint),
+        text_input("Master password", &keystore.password)
+            .secure(true)
+            .on_input(|val| Message::Keystore(KeystoreMessage::PasswordChanged(val)))
+            .on_submit_maybe(submit.clone()),
+    ]
+    .push_maybe(confirm)
+    .push(
+        button(
+            text(if keystore.is_busy {
+                "Working..."
+            } else {
+                action
+            })
+            .align_x(alignment::Horizontal::Center),
+        )
+        .on_press_maybe(submit),
+    )
+    .spacing(10)
+    .width(350)
+    .align_x(alignment::Horizontal::Center);
+
+    container(content)
+        .center_x(Length::Fill)
+        .center_y(Length::Fill)
+        .into()
+}
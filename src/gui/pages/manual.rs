@@ -4,10 +4,11 @@ use iced::{
     Element, Length,
 };
 
+use crate::gui::components::profile_picker;
 use crate::gui::state::messages::{ManualPageMessage, Message};
 use crate::State;
 
-pub fn manual_page(state: &State) -> Element<Message> {
+pub fn manual_page(state: &State) -> Element<'_, Message> {
     let can_run =
         state.manual.from.is_some() && state.manual.to.is_some() && !state.manual.is_doing_work;
     let from = state
@@ -51,6 +52,12 @@ pub fn manual_page(state: &State) -> Element<Message> {
                 })
         ],
         horizontal_space().height(10),
+        container(profile_picker(state, state.manual.profile, |pin| {
+            Message::Manual(ManualPageMessage::ProfilePinned(pin))
+        }))
+        .width(Length::Fill)
+        .align_x(alignment::Horizontal::Center),
+        horizontal_space().height(10),
         container(row![
             button(text("Encrypt").align_x(alignment::Horizontal::Center))
                 .width(Length::Shrink)
@@ -1,13 +1,20 @@
+pub mod enigma_analysis;
+pub mod enigma_simulator;
 pub mod fsw;
+pub mod hash;
+pub mod lock;
 pub mod manual;
 pub mod settings;
 pub mod tcp;
 
-#[derive(Default)]
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
 pub enum Page {
     Settings,
     #[default]
     Fsw,
     Manual,
     Tcp,
+    Hash,
+    EnigmaSimulator,
+    EnigmaAnalysis,
 }
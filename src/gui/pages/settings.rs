@@ -1,45 +1,136 @@
 use iced::{
     alignment,
-    widget::{button, column, container, pick_list, row, text, text_input, vertical_space},
+    widget::{
+        button, checkbox, column, container, horizontal_space, pick_list, row, text, text_input,
+        vertical_space,
+    },
     Alignment, Element, Length,
 };
 
 use crate::{
-    algorithms::AlgorithmOption,
     gui::state::{
-        args::{EnigmaArgs, XxteaArgs, XxteaCfbArgs},
+        args::{AesArgs, ChaCha20Poly1305Args, EnigmaArgs, XxteaArgs},
         messages::{
-            AlgorithmSettingsMessage, EnigmaSettingsMessage, Message, XxteaCfbSettingsMessage,
-            XxteaSettingsMessage,
+            AesSettingsMessage, AlgorithmSettingsMessage, ChaCha20Poly1305SettingsMessage,
+            EnigmaSettingsMessage, KeystoreMessage, Message, ProfileMessage, XxteaSettingsMessage,
         },
+        AutoLock,
     },
     State,
 };
+use zi_core::{
+    algorithms::{
+        aes::alg::AesKeySize,
+        enigma::{
+            alg::{EnigmaMachine, EnigmaTextMode},
+            catalogue,
+        },
+        kdf::DEFAULT_ITERATIONS,
+        modes::alg::BlockMode,
+        xxtea::alg::XxteaMode,
+        AlgorithmOption,
+    },
+    container::ContainerFormat,
+    hash::HashAlgorithm,
+};
 
-pub fn settings_page(state: &State) -> Element<Message> {
+pub fn settings_page(state: &State) -> Element<'_, Message> {
     let option = state.settings.algorithm_option;
 
     let args: Element<Message> = match option {
         AlgorithmOption::Enigma => enigma_settings(&state.settings.enigma_args),
         AlgorithmOption::Xxtea => xxtea_settings(&state.settings.xxtea_args),
-        AlgorithmOption::XxteaCfb => xxtea_cfb_settings(&state.settings.xxtea_cfb_args),
+        AlgorithmOption::Aes => aes_settings(&state.settings.aes_args),
+        AlgorithmOption::ChaCha20Poly1305 => {
+            chacha20poly1305_settings(&state.settings.chacha20poly1305_args)
+        }
     };
 
     column![
         vertical_space().height(30),
+        row![
+            text("Profile: "),
+            text_input("Name", &state.profile_name)
+                .on_input(|val| Message::Profile(ProfileMessage::NameChanged(val)))
+                .on_submit(Message::Profile(ProfileMessage::Rename))
+                .width(200),
+            horizontal_space().width(10),
+            button(text("Rename")).on_press(Message::Profile(ProfileMessage::Rename)),
+            horizontal_space().width(10),
+            button(text("New")).on_press(Message::Profile(ProfileMessage::New)),
+            horizontal_space().width(10),
+            button(text("Duplicate")).on_press(Message::Profile(ProfileMessage::Duplicate)),
+            horizontal_space().width(10),
+            button(text("Delete")).on_press_maybe(
+                if state.profiles.read().unwrap().list.len() > 1 {
+                    Some(Message::Profile(ProfileMessage::Delete))
+                } else {
+                    None
+                }
+            ),
+        ]
+        .align_y(Alignment::Center),
+        vertical_space().height(10),
         row![
             text("Algorithm: "),
             pick_list(
                 vec![
                     AlgorithmOption::Enigma,
                     AlgorithmOption::Xxtea,
-                    AlgorithmOption::XxteaCfb
+                    AlgorithmOption::Aes,
+                    AlgorithmOption::ChaCha20Poly1305
                 ],
                 Some(option),
                 Message::AlgorithmChanged
             ),
+            horizontal_space().width(20),
+            text("Format: "),
+            pick_list(
+                vec![ContainerFormat::Container, ContainerFormat::Raw],
+                Some(state.settings.container_format),
+                Message::ContainerFormatChanged
+            ),
+            horizontal_space().width(20),
+            text("TCP hash: "),
+            pick_list(
+                HashAlgorithm::ALL,
+                Some(state.settings.integrity_hash),
+                Message::IntegrityHashChanged
+            ),
+        ]
+        .align_y(Alignment::Center),
+        vertical_space().height(10),
+        row![
+            text("Shared secret: "),
+            text_input(
+                "Optional, authenticates TCP transfers",
+                state
+                    .settings
+                    .shared_secret
+                    .as_ref()
+                    .map(|s| s.as_str())
+                    .unwrap_or("")
+            )
+            .on_input(|val| {
+                let value = if val.is_empty() { None } else { Some(val) };
+                Message::SharedSecretChanged(value)
+            })
+            .width(300),
+            horizontal_space().width(20),
+            text("KDF iterations: "),
+            text_input(
+                &DEFAULT_ITERATIONS.to_string(),
+                state.settings.kdf_iterations.as_deref().unwrap_or("")
+            )
+            .on_input(|val| {
+                let value = if val.is_empty() { None } else { Some(val) };
+                Message::KdfIterationsChanged(value)
+            })
+            .width(100),
         ]
         .align_y(Alignment::Center),
+        vertical_space().height(10),
+        master_password(state),
         container(column![args])
             .center_y(Length::Fill)
             .padding([0, 50]),
@@ -53,102 +144,166 @@ pub fn settings_page(state: &State) -> Element<Message> {
     .into()
 }
 
-fn enigma_settings(state: &EnigmaArgs) -> Element<Message> {
-    column![
-        row![
-            column![
-                text("Reflector")
-                    .width(Length::Fill)
-                    .align_x(Alignment::Center),
-                text_input("Wiring", state.refl_wiring.as_deref().unwrap_or(""))
-                    .on_input(|val| {
-                        let value = if val.is_empty() { None } else { Some(val) };
-                        Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
-                            EnigmaSettingsMessage::ReflWiringChanged(value),
-                        ))
-                    })
-                    .width(Length::Fill)
+/// Changing the master password and locking the keystore
+fn master_password(state: &State) -> Element<'_, Message> {
+    let keystore = &state.keystore;
+    let can_change = !keystore.is_busy
+        && !keystore.current_password.is_empty()
+        && !keystore.new_password.is_empty();
+
+    let password = |placeholder, value, on_input: fn(String) -> KeystoreMessage| {
+        text_input(placeholder, value)
+            .secure(true)
+            .on_input(move |val| Message::Keystore(on_input(val)))
+            .width(110)
+    };
+
+    row![
+        text("Master password: "),
+        password(
+            "Current",
+            &keystore.current_password,
+            KeystoreMessage::CurrentPasswordChanged
+        ),
+        horizontal_space().width(5),
+        password(
+            "New",
+            &keystore.new_password,
+            KeystoreMessage::NewPasswordChanged
+        ),
+        horizontal_space().width(5),
+        password(
+            "Confirm",
+            &keystore.new_confirm,
+            KeystoreMessage::NewConfirmChanged
+        ),
+        horizontal_space().width(10),
+        button(text("Change")).on_press_maybe(
+            can_change.then_some(Message::Keystore(KeystoreMessage::ChangePassword))
+        ),
+        horizontal_space().width(20),
+        text("Auto-lock: "),
+        pick_list(AutoLock::ALL, Some(keystore.auto_lock), |value| {
+            Message::Keystore(KeystoreMessage::AutoLockPicked(value))
+        }),
+        horizontal_space().width(10),
+        button(text("Lock")).on_press_maybe(
+            (!keystore.is_busy).then_some(Message::Keystore(KeystoreMessage::Lock))
+        ),
+    ]
+    .align_y(Alignment::Center)
+    .into()
+}
+
+fn enigma_settings(state: &EnigmaArgs) -> Element<'_, Message> {
+    let machine_picker = pick_list(EnigmaMachine::ALL, Some(state.machine), |value| {
+        Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
+            EnigmaSettingsMessage::MachineChanged(value),
+        ))
+    });
+
+    let reflectors = match state.machine {
+        EnigmaMachine::M3 => &catalogue::REFLECTORS[..],
+        EnigmaMachine::M4 => &catalogue::THIN_REFLECTORS[..],
+        EnigmaMachine::Binary => {
+            return column![
+                row![text("Machine"), machine_picker]
+                    .spacing(10)
+                    .align_y(Alignment::Center),
+                vertical_space().height(10),
+                text("Passphrase").width(Length::Fill),
+                text_input(
+                    "Passphrase",
+                    state.passphrase.as_ref().map(|s| s.as_str()).unwrap_or("")
+                )
+                .on_input(|val| {
+                    let value = if val.is_empty() { None } else { Some(val) };
+                    Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
+                        EnigmaSettingsMessage::PassphraseChanged(value),
+                    ))
+                })
+                .width(Length::Fill),
             ]
-            .spacing(5),
+            .spacing(5)
+            .into();
+        }
+    };
+
+    let mut rotors = row![column![
+        text("Reflector")
+            .width(Length::Fill)
+            .align_x(Alignment::Center),
+        pick_list(
+            catalogue::with_custom(reflectors),
+            Some(catalogue::identify(
+                reflectors,
+                state.refl_wiring.as_deref(),
+                None
+            )),
+            |component| {
+                Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
+                    EnigmaSettingsMessage::ReflectorPicked(component),
+                ))
+            }
+        )
+        .width(Length::Fill),
+        text_input("Wiring", state.refl_wiring.as_deref().unwrap_or(""))
+            .on_input(|val| {
+                let value = if val.is_empty() { None } else { Some(val) };
+                Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
+                    EnigmaSettingsMessage::ReflWiringChanged(value),
+                ))
+            })
+            .width(Length::Fill)
+    ]
+    .spacing(5)]
+    .spacing(10);
+
+    // The Greek wheel sits between the reflector and the left rotor
+    if state.machine == EnigmaMachine::M4 {
+        rotors = rotors.push(
             column![
-                text("Rotor 1")
+                text("Greek wheel")
                     .width(Length::Fill)
                     .align_x(Alignment::Center),
-                text_input("Wiring", state.rot1_wiring.as_deref().unwrap_or(""))
-                    .on_input(|val| {
-                        let value = if val.is_empty() { None } else { Some(val) };
-                        Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
-                            EnigmaSettingsMessage::Rot1WiringChanged(value),
-                        ))
-                    })
-                    .width(Length::Fill),
-                row![
-                    text_input("Notch", state.rot1_notch.as_deref().unwrap_or("")).on_input(
-                        |val| {
-                            let value = if val.is_empty() { None } else { Some(val) };
-                            Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
-                                EnigmaSettingsMessage::Rot1NotchChanged(value),
-                            ))
-                        }
-                    ),
-                    text_input(
-                        "Ringstellung",
-                        state.rot1_ringstellung.as_deref().unwrap_or("")
-                    )
-                    .on_input(|val| {
-                        let value = if val.is_empty() { None } else { Some(val) };
+                pick_list(
+                    catalogue::with_custom(&catalogue::GREEK_WHEELS),
+                    Some(catalogue::identify(
+                        &catalogue::GREEK_WHEELS,
+                        state.greek_wiring.as_deref(),
+                        None
+                    )),
+                    |component| {
                         Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
-                            EnigmaSettingsMessage::Rot1RingstellungChanged(value),
+                            EnigmaSettingsMessage::GreekWheelPicked(component),
                         ))
-                    }),
-                    text_input("Start", state.rot1_position.as_deref().unwrap_or("")).on_input(
-                        |val| {
-                            let value = if val.is_empty() { None } else { Some(val) };
-                            Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
-                                EnigmaSettingsMessage::Rot1PositionChanged(value),
-                            ))
-                        }
-                    ),
-                ]
-                .spacing(5)
-            ]
-            .spacing(5),
-            column![
-                text("Rotor 2")
-                    .width(Length::Fill)
-                    .align_x(Alignment::Center),
-                text_input("Wiring", state.rot2_wiring.as_deref().unwrap_or(""))
+                    }
+                )
+                .width(Length::Fill),
+                text_input("Wiring", state.greek_wiring.as_deref().unwrap_or(""))
                     .on_input(|val| {
                         let value = if val.is_empty() { None } else { Some(val) };
                         Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
-                            EnigmaSettingsMessage::Rot2WiringChanged(value),
+                            EnigmaSettingsMessage::GreekWiringChanged(value),
                         ))
                     })
                     .width(Length::Fill),
                 row![
-                    text_input("Notch", state.rot2_notch.as_deref().unwrap_or("")).on_input(
-                        |val| {
-                            let value = if val.is_empty() { None } else { Some(val) };
-                            Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
-                                EnigmaSettingsMessage::Rot2NotchChanged(value),
-                            ))
-                        }
-                    ),
                     text_input(
                         "Ringstellung",
-                        state.rot2_ringstellung.as_deref().unwrap_or("")
+                        state.greek_ringstellung.as_deref().unwrap_or("")
                     )
                     .on_input(|val| {
                         let value = if val.is_empty() { None } else { Some(val) };
                         Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
-                            EnigmaSettingsMessage::Rot2RingstellungChanged(value),
+                            EnigmaSettingsMessage::GreekRingstellungChanged(value),
                         ))
                     }),
-                    text_input("Start", state.rot2_position.as_deref().unwrap_or("")).on_input(
+                    text_input("Start", state.greek_position.as_deref().unwrap_or("")).on_input(
                         |val| {
                             let value = if val.is_empty() { None } else { Some(val) };
                             Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
-                                EnigmaSettingsMessage::Rot2PositionChanged(value),
+                                EnigmaSettingsMessage::GreekPositionChanged(value),
                             ))
                         }
                     ),
@@ -156,51 +311,203 @@ fn enigma_settings(state: &EnigmaArgs) -> Element<Message> {
                 .spacing(5)
             ]
             .spacing(5),
-            column![
-                text("Rotor 3")
-                    .width(Length::Fill)
-                    .align_x(Alignment::Center),
-                text_input("Wiring", state.rot3_wiring.as_deref().unwrap_or(""))
-                    .on_input(|val| {
-                        let value = if val.is_empty() { None } else { Some(val) };
-                        Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
-                            EnigmaSettingsMessage::Rot3WiringChanged(value),
-                        ))
-                    })
-                    .width(Length::Fill),
-                row![
-                    text_input("Notch", state.rot3_notch.as_deref().unwrap_or("")).on_input(
-                        |val| {
-                            let value = if val.is_empty() { None } else { Some(val) };
-                            Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
-                                EnigmaSettingsMessage::Rot3NotchChanged(value),
-                            ))
-                        }
-                    ),
-                    text_input(
-                        "Ringstellung",
-                        state.rot3_ringstellung.as_deref().unwrap_or("")
-                    )
-                    .on_input(|val| {
-                        let value = if val.is_empty() { None } else { Some(val) };
-                        Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
-                            EnigmaSettingsMessage::Rot3RingstellungChanged(value),
-                        ))
-                    }),
-                    text_input("Start", state.rot3_position.as_deref().unwrap_or("")).on_input(
-                        |val| {
-                            let value = if val.is_empty() { None } else { Some(val) };
-                            Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
-                                EnigmaSettingsMessage::Rot3PositionChanged(value),
-                            ))
-                        }
-                    ),
-                ]
-                .spacing(5)
+        );
+    }
+
+    rotors = rotors.push(
+        column![
+            text("Rotor 1")
+                .width(Length::Fill)
+                .align_x(Alignment::Center),
+            pick_list(
+                catalogue::with_custom(&catalogue::ROTORS),
+                Some(catalogue::identify(
+                    &catalogue::ROTORS,
+                    state.rot1_wiring.as_deref(),
+                    state.rot1_notch.as_deref()
+                )),
+                |component| {
+                    Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
+                        EnigmaSettingsMessage::Rot1Picked(component),
+                    ))
+                }
+            )
+            .width(Length::Fill),
+            text_input("Wiring", state.rot1_wiring.as_deref().unwrap_or(""))
+                .on_input(|val| {
+                    let value = if val.is_empty() { None } else { Some(val) };
+                    Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
+                        EnigmaSettingsMessage::Rot1WiringChanged(value),
+                    ))
+                })
+                .width(Length::Fill),
+            row![
+                text_input("Notches", state.rot1_notch.as_deref().unwrap_or("")).on_input(|val| {
+                    let value = if val.is_empty() { None } else { Some(val) };
+                    Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
+                        EnigmaSettingsMessage::Rot1NotchChanged(value),
+                    ))
+                }),
+                text_input(
+                    "Ringstellung",
+                    state.rot1_ringstellung.as_deref().unwrap_or("")
+                )
+                .on_input(|val| {
+                    let value = if val.is_empty() { None } else { Some(val) };
+                    Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
+                        EnigmaSettingsMessage::Rot1RingstellungChanged(value),
+                    ))
+                }),
+                text_input("Start", state.rot1_position.as_deref().unwrap_or("")).on_input(|val| {
+                    let value = if val.is_empty() { None } else { Some(val) };
+                    Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
+                        EnigmaSettingsMessage::Rot1PositionChanged(value),
+                    ))
+                }),
             ]
-            .spacing(5),
+            .spacing(5)
+        ]
+        .spacing(5),
+    );
+    rotors = rotors.push(
+        column![
+            text("Rotor 2")
+                .width(Length::Fill)
+                .align_x(Alignment::Center),
+            pick_list(
+                catalogue::with_custom(&catalogue::ROTORS),
+                Some(catalogue::identify(
+                    &catalogue::ROTORS,
+                    state.rot2_wiring.as_deref(),
+                    state.rot2_notch.as_deref()
+                )),
+                |component| {
+                    Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
+                        EnigmaSettingsMessage::Rot2Picked(component),
+                    ))
+                }
+            )
+            .width(Length::Fill),
+            text_input("Wiring", state.rot2_wiring.as_deref().unwrap_or(""))
+                .on_input(|val| {
+                    let value = if val.is_empty() { None } else { Some(val) };
+                    Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
+                        EnigmaSettingsMessage::Rot2WiringChanged(value),
+                    ))
+                })
+                .width(Length::Fill),
+            row![
+                text_input("Notches", state.rot2_notch.as_deref().unwrap_or("")).on_input(|val| {
+                    let value = if val.is_empty() { None } else { Some(val) };
+                    Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
+                        EnigmaSettingsMessage::Rot2NotchChanged(value),
+                    ))
+                }),
+                text_input(
+                    "Ringstellung",
+                    state.rot2_ringstellung.as_deref().unwrap_or("")
+                )
+                .on_input(|val| {
+                    let value = if val.is_empty() { None } else { Some(val) };
+                    Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
+                        EnigmaSettingsMessage::Rot2RingstellungChanged(value),
+                    ))
+                }),
+                text_input("Start", state.rot2_position.as_deref().unwrap_or("")).on_input(|val| {
+                    let value = if val.is_empty() { None } else { Some(val) };
+                    Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
+                        EnigmaSettingsMessage::Rot2PositionChanged(value),
+                    ))
+                }),
+            ]
+            .spacing(5)
+        ]
+        .spacing(5),
+    );
+    rotors = rotors.push(
+        column![
+            text("Rotor 3")
+                .width(Length::Fill)
+                .align_x(Alignment::Center),
+            pick_list(
+                catalogue::with_custom(&catalogue::ROTORS),
+                Some(catalogue::identify(
+                    &catalogue::ROTORS,
+                    state.rot3_wiring.as_deref(),
+                    state.rot3_notch.as_deref()
+                )),
+                |component| {
+                    Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
+                        EnigmaSettingsMessage::Rot3Picked(component),
+                    ))
+                }
+            )
+            .width(Length::Fill),
+            text_input("Wiring", state.rot3_wiring.as_deref().unwrap_or(""))
+                .on_input(|val| {
+                    let value = if val.is_empty() { None } else { Some(val) };
+                    Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
+                        EnigmaSettingsMessage::Rot3WiringChanged(value),
+                    ))
+                })
+                .width(Length::Fill),
+            row![
+                text_input("Notches", state.rot3_notch.as_deref().unwrap_or("")).on_input(|val| {
+                    let value = if val.is_empty() { None } else { Some(val) };
+                    Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
+                        EnigmaSettingsMessage::Rot3NotchChanged(value),
+                    ))
+                }),
+                text_input(
+                    "Ringstellung",
+                    state.rot3_ringstellung.as_deref().unwrap_or("")
+                )
+                .on_input(|val| {
+                    let value = if val.is_empty() { None } else { Some(val) };
+                    Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
+                        EnigmaSettingsMessage::Rot3RingstellungChanged(value),
+                    ))
+                }),
+                text_input("Start", state.rot3_position.as_deref().unwrap_or("")).on_input(|val| {
+                    let value = if val.is_empty() { None } else { Some(val) };
+                    Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
+                        EnigmaSettingsMessage::Rot3PositionChanged(value),
+                    ))
+                }),
+            ]
+            .spacing(5)
         ]
-        .spacing(10),
+        .spacing(5),
+    );
+
+    let mut options = row![
+        text("Machine"),
+        machine_picker,
+        horizontal_space().width(20),
+        text("Text"),
+        pick_list(EnigmaTextMode::ALL, Some(state.text_mode), |value| {
+            Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
+                EnigmaSettingsMessage::TextModeChanged(value),
+            ))
+        }),
+    ]
+    .spacing(10)
+    .align_y(Alignment::Center);
+
+    if state.text_mode == EnigmaTextMode::LettersOnly {
+        options = options.push(horizontal_space().width(20)).push(
+            checkbox("5-letter groups", state.five_letter_groups).on_toggle(|value| {
+                Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Enigma(
+                    EnigmaSettingsMessage::FiveLetterGroupsToggled(value),
+                ))
+            }),
+        );
+    }
+
+    column![
+        options,
+        vertical_space().height(10),
+        rotors,
         vertical_space().height(30),
         column![
             text("Plugboard")
@@ -222,65 +529,140 @@ fn enigma_settings(state: &EnigmaArgs) -> Element<Message> {
     .into()
 }
 
-fn xxtea_settings(state: &XxteaArgs) -> Element<Message> {
-    column![
-        text("Key").width(Length::Fill),
-        text_input("Key", state.key.as_deref().unwrap_or(""))
-            .on_input(|val| {
-                let value = if val.is_empty() { None } else { Some(val) };
+fn xxtea_settings(state: &XxteaArgs) -> Element<'_, Message> {
+    let mut settings = column![
+        row![
+            text("Mode"),
+            pick_list(XxteaMode::all(), Some(state.mode), |value| {
                 Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Xxtea(
-                    XxteaSettingsMessage::KeyChanged(value),
+                    XxteaSettingsMessage::ModeChanged(value),
                 ))
-            })
-            .width(Length::Fill),
-    ]
-    .spacing(5)
-    .into()
-}
+            }),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center),
+        vertical_space().height(10),
+    ];
 
-fn xxtea_cfb_settings(state: &XxteaCfbArgs) -> Element<Message> {
-    column![
-        row![
-            column![
-                text("IV"),
-                text_input("IV", state.iv.as_deref().unwrap_or(""))
-                    .on_input(|val| {
-                        let value = if val.is_empty() { None } else { Some(val) };
-                        Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::XxteaCfb(
-                            XxteaCfbSettingsMessage::IVChanged(value),
-                        ))
-                    })
-                    .width(Length::Fill),
+    if let XxteaMode::Block(_) = state.mode {
+        settings = settings.push(
+            row![
+                column![
+                    text("IV"),
+                    text_input("IV", state.iv.as_deref().unwrap_or(""))
+                        .on_input(|val| {
+                            let value = if val.is_empty() { None } else { Some(val) };
+                            Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Xxtea(
+                                XxteaSettingsMessage::IVChanged(value),
+                            ))
+                        })
+                        .width(Length::Fill),
+                ]
+                .spacing(5)
+                .width(Length::Fill),
+                column![
+                    text("Block Size"),
+                    text_input("Block Size", state.block_size.as_deref().unwrap_or(""))
+                        .on_input(|val| {
+                            let value = if val.is_empty() { None } else { Some(val) };
+                            Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Xxtea(
+                                XxteaSettingsMessage::BlockSizeChanged(value),
+                            ))
+                        })
+                        .width(100)
+                ]
+                .spacing(5),
             ]
-            .spacing(5)
-            .width(Length::Fill),
+            .spacing(10),
+        );
+    }
+
+    settings
+        .push(
             column![
-                text("Block Size"),
-                text_input("Block Size", state.block_size.as_deref().unwrap_or(""))
+                text("Key").width(Length::Fill),
+                text_input("Key", state.key.as_ref().map(|s| s.as_str()).unwrap_or(""))
                     .on_input(|val| {
                         let value = if val.is_empty() { None } else { Some(val) };
-                        Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::XxteaCfb(
-                            XxteaCfbSettingsMessage::BlockSizeChanged(value),
+                        Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Xxtea(
+                            XxteaSettingsMessage::KeyChanged(value),
                         ))
                     })
-                    .width(100)
+                    .width(Length::Fill),
             ]
             .spacing(5),
+        )
+        .spacing(5)
+        .into()
+}
+
+fn aes_settings(state: &AesArgs) -> Element<'_, Message> {
+    let mut settings = column![
+        row![
+            text("Key Size"),
+            pick_list(
+                vec![AesKeySize::Aes128, AesKeySize::Aes192, AesKeySize::Aes256],
+                Some(state.key_size),
+                |value| {
+                    Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Aes(
+                        AesSettingsMessage::KeySizeChanged(value),
+                    ))
+                }
+            ),
+            horizontal_space().width(20),
+            text("Mode"),
+            pick_list(BlockMode::ALL, Some(state.mode), |value| {
+                Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Aes(
+                    AesSettingsMessage::ModeChanged(value),
+                ))
+            }),
         ]
-        .spacing(10),
+        .spacing(10)
+        .align_y(Alignment::Center),
         vertical_space().height(10),
-        column![
-            text("Key").width(Length::Fill),
-            text_input("Key", state.key.as_deref().unwrap_or(""))
+    ];
+
+    if state.mode.uses_iv() {
+        settings = settings.push(text("IV").width(Length::Fill)).push(
+            text_input("IV", state.iv.as_deref().unwrap_or(""))
                 .on_input(|val| {
                     let value = if val.is_empty() { None } else { Some(val) };
-                    Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::XxteaCfb(
-                        XxteaCfbSettingsMessage::KeyChanged(value),
+                    Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Aes(
+                        AesSettingsMessage::IVChanged(value),
                     ))
                 })
-                .width(Length::Fill)
-        ]
-        .spacing(5),
+                .width(Length::Fill),
+        );
+    }
+
+    settings
+        .push(text("Key").width(Length::Fill))
+        .push(
+            text_input("Key", state.key.as_ref().map(|s| s.as_str()).unwrap_or(""))
+                .on_input(|val| {
+                    let value = if val.is_empty() { None } else { Some(val) };
+                    Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::Aes(
+                        AesSettingsMessage::KeyChanged(value),
+                    ))
+                })
+                .width(Length::Fill),
+        )
+        .spacing(5)
+        .into()
+}
+
+fn chacha20poly1305_settings(state: &ChaCha20Poly1305Args) -> Element<'_, Message> {
+    column![
+        text("Key (up to 32 bytes)").width(Length::Fill),
+        text_input("Key", state.key.as_ref().map(|s| s.as_str()).unwrap_or(""))
+            .on_input(|val| {
+                let value = if val.is_empty() { None } else { Some(val) };
+                Message::AlgorithmSettingsChanged(AlgorithmSettingsMessage::ChaCha20Poly1305(
+                    ChaCha20Poly1305SettingsMessage::KeyChanged(value),
+                ))
+            })
+            .width(Length::Fill),
     ]
+    .spacing(5)
     .into()
 }
@@ -7,6 +7,7 @@ use iced::{
 };
 
 use crate::{
+    gui::components::profile_picker,
     gui::state::{
         messages::{Message, TcpPageMessage},
         TcpMode,
@@ -15,7 +16,7 @@ use crate::{
     State,
 };
 
-pub fn tcp_page(state: &State) -> Element<Message> {
+pub fn tcp_page(state: &State) -> Element<'_, Message> {
     column![
         row![
             text("Send"),
@@ -25,7 +26,11 @@ pub fn tcp_page(state: &State) -> Element<Message> {
                 .size(20)
                 .on_toggle_maybe(Some(|_| Message::Tcp(TcpPageMessage::ToggleMode))),
             horizontal_space().width(10),
-            text("Receive")
+            text("Receive"),
+            horizontal_space().width(30),
+            profile_picker(state, state.tcp.profile, |pin| {
+                Message::Tcp(TcpPageMessage::ProfilePinned(pin))
+            }),
         ]
         .align_y(alignment::Vertical::Center),
         vertical_space().height(Length::Fill),
@@ -42,7 +47,7 @@ pub fn tcp_page(state: &State) -> Element<Message> {
     .into()
 }
 
-fn tcp_send_widget(state: &State) -> Element<Message> {
+fn tcp_send_widget(state: &State) -> Element<'_, Message> {
     let file = state
         .tcp
         .file
@@ -116,7 +121,7 @@ fn tcp_send_widget(state: &State) -> Element<Message> {
     .into()
 }
 
-fn tcp_recieve_widget(state: &State) -> Element<Message> {
+fn tcp_recieve_widget(state: &State) -> Element<'_, Message> {
     let port = state
         .tcp
         .my_port
@@ -0,0 +1,42 @@
+use std::sync::{atomic::AtomicBool, Arc};
+
+use zi_core::algorithms::enigma::{
+    analysis::Candidate,
+    bombe::Stop,
+    catalogue::{self, Component},
+};
+
+pub struct AnalysisState {
+    pub ciphertext: Option<String>,
+    /// Which of [`catalogue::ROTORS`] the rotor order is picked from
+    pub rotors: [bool; catalogue::ROTORS.len()],
+    pub reflector: Component,
+    pub is_running: bool,
+    pub progress: f32,
+    /// Set to stop the running search, every search gets a fresh flag
+    pub cancel: Arc<AtomicBool>,
+    pub candidates: Vec<Candidate>,
+
+    pub crib: Option<String>,
+    /// Letter of the ciphertext the crib starts at
+    pub crib_offset: Option<String>,
+    pub stops: Vec<Stop>,
+}
+
+impl Default for AnalysisState {
+    fn default() -> Self {
+        Self {
+            ciphertext: None,
+            // The Wehrmacht's five rotors
+            rotors: std::array::from_fn(|i| i < 5),
+            reflector: catalogue::REFLECTORS[1],
+            is_running: false,
+            progress: 0.0,
+            cancel: Default::default(),
+            candidates: Vec::new(),
+            crib: None,
+            crib_offset: Some(String::from("0")),
+            stops: Vec::new(),
+        }
+    }
+}
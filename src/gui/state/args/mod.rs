@@ -1,7 +1,27 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use zi_core::algorithms::aes::alg::AesKeySize;
+use zi_core::algorithms::enigma::alg::{EnigmaMachine, EnigmaTextMode};
+use zi_core::algorithms::enigma::catalogue;
+use zi_core::algorithms::modes::alg::BlockMode;
+use zi_core::algorithms::xxtea::alg::XxteaMode;
+use zi_core::config::{
+    AesConfig, BinaryEnigmaConfig, ChaCha20Poly1305Config, EnigmaConfig, RotorConfig, Secret,
+    XxteaConfig,
+};
+
 #[derive(Clone)]
 pub struct EnigmaArgs {
+    pub machine: EnigmaMachine,
+
     pub refl_wiring: Option<String>,
 
+    /// Only used by the M4
+    pub greek_wiring: Option<String>,
+    pub greek_ringstellung: Option<String>,
+    pub greek_position: Option<String>,
+
     pub rot1_wiring: Option<String>,
     pub rot1_notch: Option<String>,
     pub rot1_ringstellung: Option<String>,
@@ -18,12 +38,22 @@ pub struct EnigmaArgs {
     pub rot3_position: Option<String>,
 
     pub plugboard: Option<String>,
+
+    pub text_mode: EnigmaTextMode,
+    pub five_letter_groups: bool,
+
+    /// Only used by the byte machine, replaces every other field
+    pub passphrase: Option<Secret>,
 }
 
 impl Default for EnigmaArgs {
     fn default() -> Self {
         EnigmaArgs {
-            refl_wiring: Some("yruhqsldpxngokmiebfzcwvjat".to_owned()),
+            machine: EnigmaMachine::M3,
+            refl_wiring: Some(catalogue::UKW_B.to_owned()),
+            greek_wiring: Some(catalogue::GREEK_BETA.to_owned()),
+            greek_ringstellung: Some("0".to_owned()),
+            greek_position: Some("0".to_owned()),
             rot1_wiring: Some("ekmflgdqvzntowyhxuspaibrcj".to_owned()),
             rot1_notch: Some("8".to_owned()),
             rot1_ringstellung: Some("0".to_owned()),
@@ -37,36 +67,248 @@ impl Default for EnigmaArgs {
             rot3_ringstellung: Some("0".to_owned()),
             rot3_position: Some("0".to_owned()),
             plugboard: Some("po ml iu kj nh yt gb vf re dc".to_owned()),
+            text_mode: EnigmaTextMode::LettersOnly,
+            five_letter_groups: false,
+            passphrase: None,
+        }
+    }
+}
+
+impl EnigmaArgs {
+    /// Settings of the M3 and M4. The Greek wheel is only required by the
+    /// M4, an M3 keeps it if it is valid.
+    pub fn to_config(&self) -> Result<EnigmaConfig> {
+        let greek = rotor(
+            "Greek wheel",
+            &self.greek_wiring,
+            None,
+            &self.greek_ringstellung,
+            &self.greek_position,
+        );
+
+        Ok(EnigmaConfig {
+            reflector: required(&self.refl_wiring, "Reflector wiring")?.to_owned(),
+            greek: match self.machine {
+                EnigmaMachine::M4 => Some(greek?),
+                EnigmaMachine::M3 | EnigmaMachine::Binary => greek.ok(),
+            },
+            rotors: [
+                rotor(
+                    "Rotor 1",
+                    &self.rot1_wiring,
+                    Some(&self.rot1_notch),
+                    &self.rot1_ringstellung,
+                    &self.rot1_position,
+                )?,
+                rotor(
+                    "Rotor 2",
+                    &self.rot2_wiring,
+                    Some(&self.rot2_notch),
+                    &self.rot2_ringstellung,
+                    &self.rot2_position,
+                )?,
+                rotor(
+                    "Rotor 3",
+                    &self.rot3_wiring,
+                    Some(&self.rot3_notch),
+                    &self.rot3_ringstellung,
+                    &self.rot3_position,
+                )?,
+            ],
+            plugboard: self.plugboard.clone().unwrap_or_default(),
+            text_mode: self.text_mode,
+            five_letter_groups: self.five_letter_groups,
+        })
+    }
+
+    pub fn to_binary_config(&self) -> Result<BinaryEnigmaConfig> {
+        Ok(BinaryEnigmaConfig {
+            passphrase: secret(&self.passphrase, "Passphrase")?,
+        })
+    }
+}
+
+/// An M3 key as form fields, like the ones the analysis finds. A Greek wheel
+/// in `config` is kept for switching to the M4.
+impl From<&EnigmaConfig> for EnigmaArgs {
+    fn from(config: &EnigmaConfig) -> Self {
+        let [rot1, rot2, rot3] = config.rotors.clone().map(|rotor| {
+            let notches: Vec<_> = rotor.notches.iter().map(u8::to_string).collect();
+            (
+                Some(rotor.wiring),
+                Some(notches.join(" ")),
+                Some(rotor.ringstellung.to_string()),
+                Some(rotor.position.to_string()),
+            )
+        });
+        let defaults = EnigmaArgs::default();
+
+        EnigmaArgs {
+            machine: EnigmaMachine::M3,
+            refl_wiring: Some(config.reflector.clone()),
+            greek_wiring: config
+                .greek
+                .as_ref()
+                .map(|greek| greek.wiring.clone())
+                .or(defaults.greek_wiring),
+            greek_ringstellung: config
+                .greek
+                .as_ref()
+                .map(|greek| greek.ringstellung.to_string())
+                .or(defaults.greek_ringstellung),
+            greek_position: config
+                .greek
+                .as_ref()
+                .map(|greek| greek.position.to_string())
+                .or(defaults.greek_position),
+            rot1_wiring: rot1.0,
+            rot1_notch: rot1.1,
+            rot1_ringstellung: rot1.2,
+            rot1_position: rot1.3,
+            rot2_wiring: rot2.0,
+            rot2_notch: rot2.1,
+            rot2_ringstellung: rot2.2,
+            rot2_position: rot2.3,
+            rot3_wiring: rot3.0,
+            rot3_notch: rot3.1,
+            rot3_ringstellung: rot3.2,
+            rot3_position: rot3.3,
+            plugboard: (!config.plugboard.is_empty()).then(|| config.plugboard.clone()),
+            text_mode: config.text_mode,
+            five_letter_groups: config.five_letter_groups,
+            passphrase: defaults.passphrase,
         }
     }
 }
 
+/// A Greek wheel has no `notch` field
+fn rotor(
+    name: &str,
+    wiring: &Option<String>,
+    notch: Option<&Option<String>>,
+    ringstellung: &Option<String>,
+    position: &Option<String>,
+) -> Result<RotorConfig> {
+    let notches = match notch {
+        Some(notch) => required(notch, &format!("{} notch", name))?
+            .split_whitespace()
+            .map(|notch| {
+                notch
+                    .parse()
+                    .map_err(|_| anyhow!("{} notches must be numbers", name))
+            })
+            .collect::<Result<_>>()?,
+        None => Vec::new(),
+    };
+
+    Ok(RotorConfig {
+        wiring: required(wiring, &format!("{} wiring", name))?.to_owned(),
+        notches,
+        ringstellung: number(ringstellung, &format!("{} ring setting", name))?,
+        position: number(position, &format!("{} position", name))?,
+    })
+}
+
+fn required<'a>(field: &'a Option<String>, name: &str) -> Result<&'a str> {
+    field
+        .as_deref()
+        .ok_or_else(|| anyhow!("{} is missing", name))
+}
+
+fn secret(field: &Option<Secret>, name: &str) -> Result<Secret> {
+    field.clone().ok_or_else(|| anyhow!("{} is missing", name))
+}
+
+fn number<T: FromStr>(field: &Option<String>, name: &str) -> Result<T> {
+    required(field, name)?
+        .trim()
+        .parse()
+        .map_err(|_| anyhow!("{} must be a number", name))
+}
+
 #[derive(Clone)]
 pub struct XxteaArgs {
-    pub key: Option<String>,
+    pub key: Option<Secret>,
+    pub mode: XxteaMode,
+    pub iv: Option<String>,
+    pub block_size: Option<String>,
 }
 
 impl Default for XxteaArgs {
     fn default() -> Self {
         Self {
-            key: Some("SecureKey".to_owned()),
+            key: Some(Secret::from("SecureKey".to_owned())),
+            mode: Default::default(),
+            iv: Some("asdjgasdjgasdjfasdjkhasdf".to_owned()),
+            block_size: Some("8".to_owned()),
+        }
+    }
+}
+
+impl XxteaArgs {
+    /// Block size and IV are only required by the block modes
+    pub fn to_config(&self) -> Result<XxteaConfig> {
+        let block_size = number(&self.block_size, "Block Size");
+
+        Ok(XxteaConfig {
+            key: secret(&self.key, "Key")?,
+            mode: self.mode,
+            iv: self.iv.clone().unwrap_or_default().into_bytes(),
+            block_size: match self.mode {
+                XxteaMode::WholeMessage => block_size.unwrap_or_default(),
+                XxteaMode::Block(_) => block_size?,
+            },
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct ChaCha20Poly1305Args {
+    pub key: Option<Secret>,
+}
+
+impl Default for ChaCha20Poly1305Args {
+    fn default() -> Self {
+        Self {
+            key: Some(Secret::from("SecureKey".to_owned())),
         }
     }
 }
 
+impl ChaCha20Poly1305Args {
+    pub fn to_config(&self) -> Result<ChaCha20Poly1305Config> {
+        Ok(ChaCha20Poly1305Config {
+            key: secret(&self.key, "Key")?,
+        })
+    }
+}
+
 #[derive(Clone)]
-pub struct XxteaCfbArgs {
-    pub key: Option<String>,
+pub struct AesArgs {
+    pub key: Option<Secret>,
+    pub key_size: AesKeySize,
+    pub mode: BlockMode,
     pub iv: Option<String>,
-    pub block_size: Option<String>,
 }
 
-impl Default for XxteaCfbArgs {
+impl Default for AesArgs {
     fn default() -> Self {
         Self {
-            key: Some("SecureKey".to_owned()),
+            key: Some(Secret::from("SecureKey".to_owned())),
+            key_size: Default::default(),
+            mode: Default::default(),
             iv: Some("asdjgasdjgasdjfasdjkhasdf".to_owned()),
-            block_size: Some("8".to_owned()),
         }
     }
 }
+
+impl AesArgs {
+    pub fn to_config(&self) -> Result<AesConfig> {
+        Ok(AesConfig {
+            key: secret(&self.key, "Key")?,
+            key_size: self.key_size,
+            mode: self.mode,
+            iv: self.iv.clone().unwrap_or_default().into_bytes(),
+        })
+    }
+}
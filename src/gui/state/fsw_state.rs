@@ -1,12 +1,15 @@
 use notify::Watcher;
 use std::path::PathBuf;
 
-use crate::algorithms::Operation;
+use crate::utils::documents_dir;
+use zi_core::algorithms::Operation;
 
 pub struct FSWState {
     pub from: Option<PathBuf>,
     pub to: Option<PathBuf>,
     pub mode: Operation,
+    /// Profile to use instead of the active one, read when watching starts
+    pub profile: Option<u32>,
     pub is_on: bool,
 
     pub watcher: Option<Box<dyn Watcher + Send>>,
@@ -14,24 +17,15 @@ pub struct FSWState {
 
 impl Default for FSWState {
     fn default() -> Self {
-        let mut base = PathBuf::new();
-        base.push(r"C:\");
-        base.push("Users");
-        base.push("Aleksandar");
-        base.push("Documents");
-
-        let mut from = base.clone();
-        from.push("fsw_1source");
-
-        let mut to = base.clone();
-        to.push("fsw_2dest");
+        let base = documents_dir();
 
         Self {
-            from: Some(from),
-            to: Some(to),
+            from: Some(base.join("fsw_1source")),
+            to: Some(base.join("fsw_2dest")),
             is_on: Default::default(),
             watcher: Default::default(),
             mode: Default::default(),
+            profile: Default::default(),
         }
     }
 }
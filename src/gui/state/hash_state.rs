@@ -0,0 +1,32 @@
+use std::path::PathBuf;
+
+use zi_core::hash::{
+    sums::{FileCheck, FileDigest},
+    HashAlgorithm,
+};
+
+#[derive(Default)]
+pub struct HashState {
+    /// Every file below it is hashed, set instead of `files`
+    pub dir: Option<PathBuf>,
+    pub files: Vec<PathBuf>,
+    pub algorithm: HashAlgorithm,
+    pub is_running: bool,
+    pub progress: f32,
+    pub digests: Vec<FileDigest>,
+    /// Sum file the base directory is verified against
+    pub sum_file: Option<PathBuf>,
+    pub checks: Vec<FileCheck>,
+}
+
+impl HashState {
+    /// The picked directory, or the one the picked files are in
+    pub fn base(&self) -> Option<PathBuf> {
+        self.dir.clone().or_else(|| {
+            self.files
+                .first()
+                .and_then(|file| file.parent())
+                .map(|dir| dir.to_path_buf())
+        })
+    }
+}
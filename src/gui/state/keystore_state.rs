@@ -0,0 +1,115 @@
+use std::fmt::Display;
+use std::time::{Duration, Instant};
+
+use zi_core::config::Secret;
+use zi_core::keystore::Keystore;
+
+/// The master password and what it unlocks. The keys and profiles are only
+/// loaded while `keystore` is set.
+pub struct KeystoreState {
+    /// None while locked
+    pub keystore: Option<Keystore>,
+    /// Unlocking opens the file instead of choosing a master password
+    pub exists: bool,
+    /// The KDF is running
+    pub is_busy: bool,
+    pub password: Secret,
+    pub confirm: Secret,
+    //------------------------------------------
+    pub current_password: Secret,
+    pub new_password: Secret,
+    pub new_confirm: Secret,
+    //------------------------------------------
+    pub auto_lock: AutoLock,
+    /// Last key or mouse button press
+    pub last_activity: Instant,
+    /// What was last sealed into the keystore file
+    pub saved: Secret,
+}
+
+impl Default for KeystoreState {
+    fn default() -> Self {
+        Self {
+            keystore: None,
+            exists: Default::default(),
+            is_busy: Default::default(),
+            password: Default::default(),
+            confirm: Default::default(),
+            current_password: Default::default(),
+            new_password: Default::default(),
+            new_confirm: Default::default(),
+            auto_lock: Default::default(),
+            last_activity: Instant::now(),
+            saved: Default::default(),
+        }
+    }
+}
+
+impl KeystoreState {
+    pub fn is_locked(&self) -> bool {
+        self.keystore.is_none()
+    }
+
+    /// Whether it has been idle for longer than the auto-lock allows
+    pub fn is_idle(&self) -> bool {
+        match self.auto_lock.duration() {
+            Some(timeout) => !self.is_locked() && self.last_activity.elapsed() >= timeout,
+            None => false,
+        }
+    }
+
+    /// Forgets every typed password
+    pub fn clear_passwords(&mut self) {
+        for password in [
+            &mut self.password,
+            &mut self.confirm,
+            &mut self.current_password,
+            &mut self.new_password,
+            &mut self.new_confirm,
+        ] {
+            *password = Secret::default();
+        }
+    }
+}
+
+/// Idle time after which the keystore locks itself, held off while the FSW
+/// or the TCP listener is running
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AutoLock {
+    Never,
+    Minutes(u64),
+}
+
+impl Default for AutoLock {
+    fn default() -> Self {
+        AutoLock::Minutes(15)
+    }
+}
+
+impl AutoLock {
+    pub const ALL: [AutoLock; 6] = [
+        AutoLock::Never,
+        AutoLock::Minutes(1),
+        AutoLock::Minutes(5),
+        AutoLock::Minutes(15),
+        AutoLock::Minutes(30),
+        AutoLock::Minutes(60),
+    ];
+
+    pub fn duration(self) -> Option<Duration> {
+        match self {
+            AutoLock::Never => None,
+            AutoLock::Minutes(minutes) => Some(Duration::from_secs(minutes * 60)),
+        }
+    }
+}
+
+impl Display for AutoLock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AutoLock::Never => write!(f, "Never"),
+            AutoLock::Minutes(1) => write!(f, "After 1 minute"),
+            AutoLock::Minutes(minutes) => write!(f, "After {} minutes", minutes),
+        }
+    }
+}
@@ -4,5 +4,7 @@ use std::path::PathBuf;
 pub struct ManualState {
     pub from: Option<PathBuf>,
     pub to: Option<PathBuf>,
+    /// Profile to use instead of the active one
+    pub profile: Option<u32>,
     pub is_doing_work: bool,
 }
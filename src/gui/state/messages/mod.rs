@@ -1,15 +1,38 @@
 use std::path::PathBuf;
 
-use crate::algorithms::AlgorithmOption;
+use super::keystore_state::AutoLock;
+use super::profiles_state::ProfilePin;
+use zi_core::algorithms::aes::alg::AesKeySize;
+use zi_core::algorithms::enigma::alg::{EnigmaMachine, EnigmaTextMode};
+use zi_core::algorithms::enigma::analysis::Candidate;
+use zi_core::algorithms::enigma::bombe::Stop;
+use zi_core::algorithms::enigma::catalogue::Component;
+use zi_core::algorithms::modes::alg::BlockMode;
+use zi_core::algorithms::xxtea::alg::XxteaMode;
+use zi_core::algorithms::AlgorithmOption;
+use zi_core::config::Secret;
+use zi_core::container::ContainerFormat;
+use zi_core::hash::sums::{FileCheck, FileDigest};
+use zi_core::hash::HashAlgorithm;
+use zi_core::keystore::Keystore;
 
 #[derive(Debug, Clone)]
 pub enum Message {
     Navigation(NavigationMessage),
+    Keystore(KeystoreMessage),
+    Profile(ProfileMessage),
     FSW(FSWPageMessage),
     Manual(ManualPageMessage),
     Tcp(TcpPageMessage),
+    Hash(HashPageMessage),
+    EnigmaSimulator(EnigmaSimulatorMessage),
+    EnigmaAnalysis(EnigmaAnalysisMessage),
     AlgorithmChanged(AlgorithmOption),
     AlgorithmSettingsChanged(AlgorithmSettingsMessage),
+    ContainerFormatChanged(ContainerFormat),
+    IntegrityHashChanged(HashAlgorithm),
+    SharedSecretChanged(Option<String>),
+    KdfIterationsChanged(Option<String>),
     CommitSettings,
     DeleteToast(usize),
     Tick,
@@ -21,9 +44,43 @@ pub enum NavigationMessage {
     GoToFSWPage,
     GoToManualPage,
     GoToTcpPage,
+    GoToHashPage,
+    GoToEnigmaSimulatorPage,
+    GoToEnigmaAnalysisPage,
     GoToSettingsPage,
 }
 
+#[derive(Debug, Clone)]
+pub enum KeystoreMessage {
+    PasswordChanged(String),
+    ConfirmChanged(String),
+    /// Opens the keystore, or creates it on the first run
+    Unlock,
+    /// The contents are None for a new keystore
+    Unlocked(Result<(Keystore, Option<Secret>), String>),
+    Lock,
+    /// A key or mouse button was pressed
+    Activity,
+    AutoLockPicked(AutoLock),
+    CurrentPasswordChanged(String),
+    NewPasswordChanged(String),
+    NewConfirmChanged(String),
+    ChangePassword,
+    PasswordReplaced(Result<Keystore, String>),
+}
+
+#[derive(Debug, Clone)]
+pub enum ProfileMessage {
+    /// Makes the profile with this name the active one
+    Picked(String),
+    NameChanged(String),
+    New,
+    Duplicate,
+    /// Gives the active profile the typed name
+    Rename,
+    Delete,
+}
+
 #[derive(Debug, Clone)]
 pub enum FSWPageMessage {
     GetDirToWatch,
@@ -31,6 +88,7 @@ pub enum FSWPageMessage {
     GetDirToSaveTo,
     DirToSaveToResult(Option<PathBuf>),
     ToggleMode,
+    ProfilePinned(ProfilePin),
     TurnOn,
     TurnOff,
     WatchingStarted,
@@ -43,15 +101,44 @@ pub enum ManualPageMessage {
     FileResult(Option<PathBuf>),
     GetDirToSaveTo,
     DirToSaveToResult(Option<PathBuf>),
+    ProfilePinned(ProfilePin),
     StartEncryption,
     EncryptionDone,
     StartDecryption,
     DecryptionDone,
 }
 
+#[derive(Debug, Clone)]
+pub enum EnigmaSimulatorMessage {
+    KeyPressed(char),
+    /// Rebuild the machine from the committed settings and clear the tape
+    Reset,
+}
+
+#[derive(Debug, Clone)]
+pub enum EnigmaAnalysisMessage {
+    CiphertextChanged(Option<String>),
+    /// Index into `catalogue::ROTORS`
+    RotorToggled(usize, bool),
+    ReflectorPicked(Component),
+    Start,
+    Cancel,
+    Progress(f32),
+    Finished(Result<Vec<Candidate>, String>),
+    /// Index into the candidates of the last search
+    ApplyCandidate(usize),
+    CribChanged(Option<String>),
+    CribOffsetChanged(Option<String>),
+    StartBombe,
+    BombeFinished(Result<Vec<Stop>, String>),
+    /// Index into the stops of the last bombe run
+    ApplyStop(usize),
+}
+
 #[derive(Debug, Clone)]
 pub enum TcpPageMessage {
     ToggleMode,
+    ProfilePinned(ProfilePin),
     SelectFileToSend,
     FileToSendResult(Option<PathBuf>),
     RecieverAddressChanged(String),
@@ -66,15 +153,41 @@ pub enum TcpPageMessage {
     StopListening,
 }
 
+#[derive(Debug, Clone)]
+pub enum HashPageMessage {
+    GetFiles,
+    FilesResult(Option<Vec<PathBuf>>),
+    GetDir,
+    DirResult(Option<PathBuf>),
+    AlgorithmPicked(HashAlgorithm),
+    Start,
+    Progress(f32),
+    Finished(Result<Vec<FileDigest>, String>),
+    SaveSumFile,
+    SumFileToSaveResult(Option<PathBuf>),
+    GetSumFile,
+    SumFileResult(Option<PathBuf>),
+    Verify,
+    VerifyFinished(Result<Vec<FileCheck>, String>),
+}
+
 #[derive(Debug, Clone)]
 pub enum AlgorithmSettingsMessage {
     Enigma(EnigmaSettingsMessage),
     Xxtea(XxteaSettingsMessage),
-    XxteaCfb(XxteaCfbSettingsMessage),
+    Aes(AesSettingsMessage),
+    ChaCha20Poly1305(ChaCha20Poly1305SettingsMessage),
 }
 
 #[derive(Debug, Clone)]
 pub enum EnigmaSettingsMessage {
+    MachineChanged(EnigmaMachine),
+    /// Catalogue picks fill in the wiring (and notches) of a component
+    ReflectorPicked(Component),
+    Rot1Picked(Component),
+    Rot2Picked(Component),
+    Rot3Picked(Component),
+    GreekWheelPicked(Component),
     ReflWiringChanged(Option<String>),
     Rot1WiringChanged(Option<String>),
     Rot1NotchChanged(Option<String>),
@@ -88,17 +201,32 @@ pub enum EnigmaSettingsMessage {
     Rot3NotchChanged(Option<String>),
     Rot3RingstellungChanged(Option<String>),
     Rot3PositionChanged(Option<String>),
+    GreekWiringChanged(Option<String>),
+    GreekRingstellungChanged(Option<String>),
+    GreekPositionChanged(Option<String>),
     PlugboardChanged(Option<String>),
+    PassphraseChanged(Option<String>),
+    TextModeChanged(EnigmaTextMode),
+    FiveLetterGroupsToggled(bool),
 }
 
 #[derive(Debug, Clone)]
 pub enum XxteaSettingsMessage {
     KeyChanged(Option<String>),
+    ModeChanged(XxteaMode),
+    IVChanged(Option<String>),
+    BlockSizeChanged(Option<String>),
 }
 
 #[derive(Debug, Clone)]
-pub enum XxteaCfbSettingsMessage {
+pub enum AesSettingsMessage {
     KeyChanged(Option<String>),
+    KeySizeChanged(AesKeySize),
+    ModeChanged(BlockMode),
     IVChanged(Option<String>),
-    BlockSizeChanged(Option<String>),
+}
+
+#[derive(Debug, Clone)]
+pub enum ChaCha20Poly1305SettingsMessage {
+    KeyChanged(Option<String>),
 }
@@ -1,11 +1,19 @@
+mod analysis_state;
 pub mod args;
 mod fsw_state;
+mod hash_state;
+mod keystore_state;
 mod manual_state;
 pub mod messages;
+mod profiles_state;
+mod saved_state;
 mod settings_state;
+mod simulator_state;
 mod state;
 mod tcp_state;
 
+pub use keystore_state::AutoLock;
+pub use profiles_state::{ProfilePin, Profiles};
 pub use settings_state::SettingsState;
 pub use tcp_state::TcpMode;
 
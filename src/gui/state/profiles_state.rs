@@ -0,0 +1,233 @@
+use std::fmt::Display;
+
+use anyhow::{anyhow, Result};
+
+use super::SettingsState;
+
+#[derive(Clone)]
+pub struct Profile {
+    /// Stays the same across renames, pins refer to it
+    pub id: u32,
+    pub name: String,
+    pub settings: SettingsState,
+}
+
+/// Committed settings under a name each. Pages use the active profile
+/// unless they pin one.
+#[derive(Clone)]
+pub struct Profiles {
+    pub list: Vec<Profile>,
+    pub active: usize,
+    next_id: u32,
+}
+
+impl Default for Profiles {
+    fn default() -> Self {
+        Profiles::new("Default", Default::default())
+    }
+}
+
+impl Profiles {
+    pub fn new(name: &str, settings: SettingsState) -> Profiles {
+        Profiles {
+            list: vec![Profile {
+                id: 0,
+                name: name.to_owned(),
+                settings,
+            }],
+            active: 0,
+            next_id: 1,
+        }
+    }
+
+    pub fn active(&self) -> &Profile {
+        &self.list[self.active]
+    }
+
+    pub fn active_mut(&mut self) -> &mut Profile {
+        &mut self.list[self.active]
+    }
+
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.list.iter().position(|profile| profile.name == name)
+    }
+
+    pub fn get(&self, id: u32) -> Option<&Profile> {
+        self.list.iter().find(|profile| profile.id == id)
+    }
+
+    /// Settings of the pinned profile, the active one when nothing is
+    /// pinned or the pinned profile was deleted
+    pub fn settings(&self, pin: Option<u32>) -> &SettingsState {
+        match pin.and_then(|id| self.get(id)) {
+            Some(profile) => &profile.settings,
+            None => &self.active().settings,
+        }
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.list
+            .iter()
+            .map(|profile| profile.name.clone())
+            .collect()
+    }
+
+    /// `name`, or `name 2`, `name 3`... when it's taken
+    pub fn unique_name(&self, name: &str) -> String {
+        (1..)
+            .map(|number| match number {
+                1 => name.to_owned(),
+                _ => format!("{} {}", name, number),
+            })
+            .find(|candidate| self.find(candidate).is_none())
+            .expect("There are only so many profiles")
+    }
+
+    /// Adds a profile under a unique name and returns its index
+    pub fn add(&mut self, name: &str, settings: SettingsState) -> usize {
+        let name = self.unique_name(name);
+        self.list.push(Profile {
+            id: self.next_id,
+            name,
+            settings,
+        });
+        self.next_id += 1;
+
+        self.list.len() - 1
+    }
+
+    pub fn rename(&mut self, index: usize, name: &str) -> Result<()> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(anyhow!("A profile needs a name"));
+        }
+        if self.find(name).is_some_and(|other| other != index) {
+            return Err(anyhow!("There already is a profile named {}", name));
+        }
+
+        self.list[index].name = name.to_owned();
+
+        Ok(())
+    }
+
+    /// The next profile becomes active when the active one is removed
+    pub fn remove(&mut self, index: usize) -> Result<Profile> {
+        if self.list.len() == 1 {
+            return Err(anyhow!("The last profile can't be deleted"));
+        }
+
+        let profile = self.list.remove(index);
+        if self.active > index || self.active == self.list.len() {
+            self.active -= 1;
+        }
+
+        Ok(profile)
+    }
+}
+
+/// Choice of a page between following the active profile and pinning one,
+/// for pick lists
+#[derive(Clone, PartialEq, Debug)]
+pub enum ProfilePin {
+    Active,
+    Profile(u32, String),
+}
+
+impl ProfilePin {
+    pub fn options(profiles: &Profiles) -> Vec<ProfilePin> {
+        std::iter::once(ProfilePin::Active)
+            .chain(
+                profiles
+                    .list
+                    .iter()
+                    .map(|profile| ProfilePin::Profile(profile.id, profile.name.clone())),
+            )
+            .collect()
+    }
+
+    pub fn new(profiles: &Profiles, pin: Option<u32>) -> ProfilePin {
+        match pin.and_then(|id| profiles.get(id)) {
+            Some(profile) => ProfilePin::Profile(profile.id, profile.name.clone()),
+            None => ProfilePin::Active,
+        }
+    }
+
+    pub fn id(&self) -> Option<u32> {
+        match self {
+            ProfilePin::Active => None,
+            ProfilePin::Profile(id, _) => Some(*id),
+        }
+    }
+}
+
+impl Display for ProfilePin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ProfilePin::Active => "Active profile",
+                ProfilePin::Profile(_, name) => name,
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Profiles;
+    use crate::gui::state::SettingsState;
+    use zi_core::algorithms::AlgorithmOption;
+
+    #[test]
+    fn names_stay_unique() {
+        let mut profiles = Profiles::default();
+
+        assert_eq!(profiles.add("Default", Default::default()), 1);
+        assert_eq!(profiles.add("Default", Default::default()), 2);
+        assert_eq!(profiles.names(), ["Default", "Default 2", "Default 3"]);
+
+        assert!(profiles.rename(1, "Default 3").is_err());
+        assert!(profiles.rename(1, "  ").is_err());
+        profiles.rename(1, " Work ").unwrap();
+        profiles.rename(1, "Work").unwrap();
+        assert_eq!(profiles.list[1].name, "Work");
+    }
+
+    #[test]
+    fn pins_fall_back_to_the_active_profile() {
+        let mut profiles = Profiles::default();
+        let settings = SettingsState {
+            algorithm_option: AlgorithmOption::Aes,
+            ..Default::default()
+        };
+        let work = profiles.add("Work", settings);
+        let work = profiles.list[work].id;
+        profiles.rename(1, "Project").unwrap();
+
+        let pinned = |profiles: &Profiles| profiles.settings(Some(work)).algorithm_option;
+        assert_eq!(pinned(&profiles), AlgorithmOption::Aes);
+        assert_eq!(
+            profiles.settings(None).algorithm_option,
+            AlgorithmOption::Enigma
+        );
+
+        profiles.remove(1).unwrap();
+        assert_eq!(pinned(&profiles), AlgorithmOption::Enigma);
+    }
+
+    #[test]
+    fn removing_keeps_an_active_profile() {
+        let mut profiles = Profiles::default();
+        profiles.add("Work", Default::default());
+        profiles.add("Home", Default::default());
+
+        profiles.active = 2;
+        profiles.remove(2).unwrap();
+        assert_eq!(profiles.active().name, "Work");
+
+        profiles.remove(0).unwrap();
+        assert_eq!(profiles.active().name, "Work");
+        assert!(profiles.remove(0).is_err());
+    }
+}
@@ -0,0 +1,417 @@
+//! What the app remembers between launches. The directories, ports and
+//! pinned profiles of the FSW, Manual and TCP pages, the last page and the
+//! auto-lock go to a plain file. The profiles hold keys, so they are sealed
+//! into the keystore under the master password. Both are `key = value`
+//! lines, each `profile = <name>` line starts the settings of a profile,
+//! using the keys of `--config`.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use zi_core::config::Secret;
+
+use crate::cli::config;
+use crate::gui::pages::Page;
+
+use super::{AutoLock, Profiles, SettingsState, State};
+
+const PAGES: [(&str, Page); 7] = [
+    ("settings", Page::Settings),
+    ("fsw", Page::Fsw),
+    ("manual", Page::Manual),
+    ("tcp", Page::Tcp),
+    ("hash", Page::Hash),
+    ("enigma-simulator", Page::EnigmaSimulator),
+    ("enigma-analysis", Page::EnigmaAnalysis),
+];
+
+/// `zi/settings.conf` in the platform config dir
+pub fn path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("zi").join("settings.conf"))
+}
+
+/// `zi/keystore.zi` in the platform config dir
+pub fn keystore_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("zi").join("keystore.zi"))
+}
+
+/// The part of `state` without secrets, as the text of the plain file
+pub fn format(state: &State) -> String {
+    fn path(path: &Option<PathBuf>) -> String {
+        path.as_ref()
+            .map(|path| path.display().to_string())
+            .unwrap_or_default()
+    }
+
+    fn port(port: Option<u16>) -> String {
+        port.map(|port| port.to_string()).unwrap_or_default()
+    }
+
+    let page = PAGES
+        .iter()
+        .find(|(_, page)| *page == state.page)
+        .map(|(name, _)| name.to_string())
+        .expect("Every page has a name");
+
+    let auto_lock = match state.keystore.auto_lock {
+        AutoLock::Never => String::from("never"),
+        AutoLock::Minutes(minutes) => minutes.to_string(),
+    };
+
+    let entries = vec![
+        ("page", page),
+        ("fsw-from", path(&state.fsw.from)),
+        ("fsw-to", path(&state.fsw.to)),
+        ("manual-from", path(&state.manual.from)),
+        ("manual-to", path(&state.manual.to)),
+        ("tcp-file", path(&state.tcp.file)),
+        (
+            "tcp-address",
+            state.tcp.reciever_adress.clone().unwrap_or_default(),
+        ),
+        ("tcp-port", port(state.tcp.reciever_port)),
+        ("tcp-out", path(&state.tcp.dir_to_store_files)),
+        ("tcp-listen-port", port(state.tcp.my_port)),
+        ("auto-lock", auto_lock),
+    ];
+
+    return config::format(&entries);
+}
+
+/// The profiles and the pins to them, as the text sealed into the keystore
+pub fn format_keystore(state: &State) -> Secret {
+    let profiles = state.profiles.read().unwrap();
+    let pin = |pin: Option<u32>| {
+        pin.and_then(|id| profiles.get(id))
+            .map(|profile| profile.name.clone())
+            .unwrap_or_default()
+    };
+
+    let entries = vec![
+        ("fsw-profile", pin(state.fsw.profile)),
+        ("manual-profile", pin(state.manual.profile)),
+        ("tcp-profile", pin(state.tcp.profile)),
+        ("active-profile", profiles.active().name.clone()),
+    ];
+
+    let mut text = Secret::new(config::format(&entries));
+    for profile in &profiles.list {
+        let mut entries = vec![("profile", profile.name.clone())];
+        entries.extend(profile.settings.entries());
+
+        text.push('\n');
+        text.push_str(&Secret::new(config::format(&entries)));
+    }
+
+    return text;
+}
+
+/// Where the text given to [`restore`] comes from
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Source {
+    /// The plain file from before there was a keystore, its profiles are
+    /// moved into the keystore once it is created
+    LegacyFile,
+    /// The plain file next to a keystore, profiles and keys in it are
+    /// skipped, so the next save leaves them out
+    PlainFile,
+    Keystore,
+}
+
+/// Applies the plain file or the keystore contents to `state`, the active
+/// profile is also loaded into the settings form. Entries that don't apply
+/// are skipped and returned as errors, so one bad value doesn't throw away
+/// the rest. Settings before the first `profile` line go to a profile named
+/// "Default", so profiles in a plain file from before the keystore are
+/// picked up and moved into it.
+pub fn restore(state: &mut State, text: &str, source: Source) -> Result<Vec<anyhow::Error>> {
+    let mut errors = Vec::new();
+    let mut profiles: Vec<(String, SettingsState)> = Vec::new();
+    // Profile names, looked up once every profile is read
+    let mut pins = Vec::new();
+    // Keys that belong in the keystore, found in the plain file
+    let mut misplaced = Vec::new();
+
+    for (key, value) in config::parse(text)? {
+        let belongs_in_keystore = matches!(
+            key.as_str(),
+            "profile" | "active-profile" | "fsw-profile" | "manual-profile" | "tcp-profile"
+        ) || config::KEYS.contains(&key.as_str());
+        if source == Source::PlainFile && belongs_in_keystore {
+            if !misplaced.contains(&key) {
+                misplaced.push(key);
+            }
+            continue;
+        }
+
+        let result = match key.as_str() {
+            "profile" => {
+                profiles.push((value, Default::default()));
+                Ok(())
+            }
+            "active-profile" | "fsw-profile" | "manual-profile" | "tcp-profile" => {
+                pins.push((key, value));
+                Ok(())
+            }
+            key if config::KEYS.contains(&key) => {
+                if profiles.is_empty() {
+                    profiles.push((String::from("Default"), Default::default()));
+                }
+                let (_, settings) = profiles.last_mut().expect("There is a profile");
+                settings.apply(key, &value)
+            }
+            key => apply(state, key, &value),
+        };
+
+        if let Err(err) = result {
+            errors.push(err);
+        }
+    }
+
+    if !misplaced.is_empty() {
+        errors.push(anyhow!(
+            "The plain settings file holds profile settings ({}), they were ignored and are removed on the next save, the keystore holds the profiles",
+            misplaced.join(", ")
+        ));
+    }
+
+    let mut profiles = profiles.into_iter();
+    if let Some((name, settings)) = profiles.next() {
+        let mut restored = Profiles::new(&name, settings);
+        for (name, settings) in profiles {
+            restored.add(&name, settings);
+        }
+        state.profiles = std::sync::Arc::new(std::sync::RwLock::new(restored));
+    }
+
+    let mut profiles = state.profiles.write().unwrap();
+    for (key, name) in pins.into_iter().filter(|(_, name)| !name.is_empty()) {
+        let Some(index) = profiles.find(&name) else {
+            errors.push(anyhow!("There is no profile named {} for {}", name, key));
+            continue;
+        };
+
+        let id = Some(profiles.list[index].id);
+        match key.as_str() {
+            "active-profile" => profiles.active = index,
+            "fsw-profile" => state.fsw.profile = id,
+            "manual-profile" => state.manual.profile = id,
+            _ => state.tcp.profile = id,
+        }
+    }
+    state.settings = profiles.active().settings.clone();
+    drop(profiles);
+
+    Ok(errors)
+}
+
+fn apply(state: &mut State, key: &str, value: &str) -> Result<()> {
+    let path = || {
+        if value.is_empty() {
+            None
+        } else {
+            Some(PathBuf::from(value))
+        }
+    };
+    let port = || -> Result<Option<u16>> {
+        if value.is_empty() {
+            return Ok(None);
+        }
+        value
+            .parse()
+            .map(Some)
+            .map_err(|_| anyhow!("{} must be a port number", key))
+    };
+
+    match key {
+        "page" => {
+            state.page = PAGES
+                .iter()
+                .find(|(name, _)| *name == value)
+                .map(|(_, page)| *page)
+                .ok_or(anyhow!("Unknown page {}", value))?
+        }
+        "fsw-from" => state.fsw.from = path(),
+        "fsw-to" => state.fsw.to = path(),
+        "manual-from" => state.manual.from = path(),
+        "manual-to" => state.manual.to = path(),
+        "tcp-file" => state.tcp.file = path(),
+        "tcp-address" => state.tcp.reciever_adress = Some(value.to_owned()),
+        "tcp-port" => state.tcp.reciever_port = port()?,
+        "tcp-out" => state.tcp.dir_to_store_files = path(),
+        "tcp-listen-port" => state.tcp.my_port = port()?,
+        "auto-lock" => {
+            state.keystore.auto_lock = match value.parse() {
+                _ if value == "never" => AutoLock::Never,
+                Ok(0) | Err(_) => return Err(anyhow!("auto-lock must be never or minutes")),
+                Ok(minutes) => AutoLock::Minutes(minutes),
+            }
+        }
+        _ => state.settings.apply(key, value)?,
+    }
+
+    Ok(())
+}
+
+/// Only readable by the user on Unix
+pub fn write(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    std::io::Write::write_all(&mut options.open(path)?, contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{format, format_keystore, restore, write, Source};
+    use crate::gui::pages::Page;
+    use crate::gui::state::{AutoLock, SettingsState, State};
+    use zi_core::algorithms::AlgorithmOption;
+    use zi_core::utils::TempDir;
+
+    #[test]
+    fn restoring_gives_the_saved_state_back() {
+        let mut state = State::default();
+        state.page = Page::Tcp;
+        state.fsw.from = Some(PathBuf::from("watched dir"));
+        state.manual.to = None;
+        state.tcp.my_port = Some(4000);
+        state.keystore.auto_lock = AutoLock::Never;
+        {
+            let mut profiles = state.profiles.write().unwrap();
+            let aes = SettingsState {
+                algorithm_option: AlgorithmOption::Aes,
+                ..Default::default()
+            };
+            let work = profiles.add("Work", aes);
+            state.fsw.profile = Some(profiles.list[work].id);
+            profiles.active = work;
+        }
+
+        let text = format(&state);
+        let secrets = format_keystore(&state);
+        let mut restored = State::default();
+        let mut errors = restore(&mut restored, &text, Source::PlainFile).unwrap();
+        errors.extend(restore(&mut restored, &secrets, Source::Keystore).unwrap());
+
+        assert!(errors.is_empty());
+        assert_eq!(format(&restored), text);
+        assert_eq!(format_keystore(&restored), secrets);
+        assert_eq!(restored.page, Page::Tcp);
+        assert_eq!(restored.keystore.auto_lock, AutoLock::Never);
+        assert_eq!(restored.settings.algorithm_option, AlgorithmOption::Aes);
+
+        let profiles = restored.profiles.read().unwrap();
+        assert_eq!(profiles.names(), ["Default", "Work"]);
+        assert_eq!(profiles.active().name, "Work");
+        assert_eq!(restored.fsw.profile, Some(profiles.active().id));
+        assert_eq!(restored.tcp.profile, None);
+    }
+
+    #[test]
+    fn keys_stay_out_of_the_plain_file() {
+        let state = State::default();
+        state
+            .profiles
+            .write()
+            .unwrap()
+            .active_mut()
+            .settings
+            .aes_args
+            .key = Some(String::from("hunter2").into());
+
+        assert!(!format(&state).contains("hunter2"));
+        assert!(!format(&state).contains("profile"));
+        assert!(format_keystore(&state).contains("aes-key = hunter2"));
+    }
+
+    #[test]
+    fn settings_without_a_profile_go_to_the_default_one() {
+        let mut state = State::default();
+        let errors = restore(
+            &mut state,
+            "algorithm = xxtea\ntcp-profile = Gone",
+            Source::LegacyFile,
+        )
+        .unwrap();
+
+        assert_eq!(errors.len(), 1);
+        let profiles = state.profiles.read().unwrap();
+        assert_eq!(profiles.names(), ["Default"]);
+        assert_eq!(
+            profiles.active().settings.algorithm_option,
+            AlgorithmOption::Xxtea
+        );
+    }
+
+    #[test]
+    fn bad_entries_are_skipped() {
+        let mut state = State::default();
+        let errors = restore(
+            &mut state,
+            "tcp-port = http\npage = hash\ncolour = blue\nauto-lock = 0",
+            Source::PlainFile,
+        )
+        .unwrap();
+
+        assert_eq!(errors.len(), 3);
+        assert_eq!(state.page, Page::Hash);
+        assert!(restore(&mut state, "not a setting", Source::PlainFile).is_err());
+    }
+
+    #[test]
+    fn keys_in_the_plain_file_are_only_read_before_the_keystore() {
+        let text = "page = hash\naes-key = k\nalgorithm = aes\nprofile = Work\naes-key = k2";
+        let key = |state: &State| {
+            state
+                .profiles
+                .read()
+                .unwrap()
+                .active()
+                .settings
+                .aes_args
+                .key
+                .as_ref()
+                .map(|key| key.to_string())
+        };
+
+        let mut legacy = State::default();
+        assert!(restore(&mut legacy, text, Source::LegacyFile)
+            .unwrap()
+            .is_empty());
+        assert_eq!(key(&legacy).as_deref(), Some("k"));
+
+        let mut state = State::default();
+        let errors = restore(&mut state, text, Source::PlainFile).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0]
+            .to_string()
+            .contains("aes-key, algorithm, profile"));
+        assert!(!errors[0].to_string().contains("k2"));
+        assert_eq!(key(&state), key(&State::default()));
+        assert_eq!(state.page, Page::Hash);
+
+        // So the next save writes the file without them
+        assert!(!format(&state).contains("aes-key"));
+        assert!(!format(&state).contains("profile"));
+    }
+
+    #[test]
+    fn writing_creates_the_dir() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("zi").join("settings.conf");
+
+        write(&path, b"page = fsw\n").unwrap();
+        write(&path, b"page = tcp\n").unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "page = tcp\n");
+    }
+}
@@ -1,11 +1,354 @@
-use crate::algorithms::AlgorithmOption;
+use anyhow::{anyhow, Result};
+use zi_core::algorithms::enigma::alg::EnigmaMachine;
+use zi_core::algorithms::enigma::catalogue;
+use zi_core::algorithms::kdf::DEFAULT_ITERATIONS;
+use zi_core::algorithms::xxtea::alg::XxteaMode;
+use zi_core::algorithms::AlgorithmOption;
+use zi_core::config::{Config, Secret};
+use zi_core::container::ContainerFormat;
+use zi_core::hash::HashAlgorithm;
 
-use super::args::{EnigmaArgs, XxteaArgs, XxteaCfbArgs};
+use crate::cli::config::{
+    self, block_mode, block_mode_name, choice, component, hash_algorithm, name, wiring, ALGORITHMS,
+    FORMATS, KEY_SIZES, MACHINES, SWITCH, TEXT_MODES,
+};
+
+use super::args::{AesArgs, ChaCha20Poly1305Args, EnigmaArgs, XxteaArgs};
 
 #[derive(Default, Clone)]
 pub struct SettingsState {
     pub algorithm_option: AlgorithmOption,
     pub enigma_args: EnigmaArgs,
     pub xxtea_args: XxteaArgs,
-    pub xxtea_cfb_args: XxteaCfbArgs,
+    pub aes_args: AesArgs,
+    pub chacha20poly1305_args: ChaCha20Poly1305Args,
+    pub container_format: ContainerFormat,
+    /// Hash sent along with files over TCP, both sides have to agree on it
+    pub integrity_hash: HashAlgorithm,
+    /// Turns the TCP hash into an HMAC, so it also proves who sent the file
+    pub shared_secret: Option<Secret>,
+    /// PBKDF2 iterations for new containers, empty means the default
+    pub kdf_iterations: Option<String>,
+}
+
+impl SettingsState {
+    /// Parses the typed fields. Only the selected algorithm has to be valid,
+    /// the others are kept when they are, since a container header can
+    /// switch to them.
+    pub fn to_config(&self) -> anyhow::Result<Config> {
+        fn pick<T>(selected: bool, config: anyhow::Result<T>) -> anyhow::Result<Option<T>> {
+            if selected {
+                config.map(Some)
+            } else {
+                Ok(config.ok())
+            }
+        }
+
+        let selected = |algorithm| self.algorithm_option == algorithm;
+        let machine = self.enigma_args.machine;
+        let binary = machine == EnigmaMachine::Binary;
+
+        Ok(Config {
+            algorithm: self.algorithm_option,
+            enigma_machine: machine,
+            enigma: pick(
+                selected(AlgorithmOption::Enigma) && !binary,
+                self.enigma_args.to_config(),
+            )?,
+            binary_enigma: pick(
+                selected(AlgorithmOption::Enigma) && binary,
+                self.enigma_args.to_binary_config(),
+            )?,
+            xxtea: pick(
+                selected(AlgorithmOption::Xxtea),
+                self.xxtea_args.to_config(),
+            )?,
+            aes: pick(selected(AlgorithmOption::Aes), self.aes_args.to_config())?,
+            chacha20poly1305: pick(
+                selected(AlgorithmOption::ChaCha20Poly1305),
+                self.chacha20poly1305_args.to_config(),
+            )?,
+            container_format: self.container_format,
+            integrity_hash: self.integrity_hash,
+            shared_secret: self.shared_secret.clone(),
+            kdf_iterations: match &self.kdf_iterations {
+                None => DEFAULT_ITERATIONS,
+                Some(iterations) => match iterations.parse() {
+                    Ok(0) | Err(_) => {
+                        return Err(anyhow!("KDF iterations must be a positive number"))
+                    }
+                    Ok(iterations) => iterations,
+                },
+            },
+        })
+    }
+
+    /// Sets the field `key` of a `--config` file names. Fields are validated
+    /// like the ones typed into the settings page, when the algorithm is
+    /// built.
+    pub fn apply(&mut self, key: &str, value: &str) -> Result<()> {
+        let text = || {
+            if value.is_empty() {
+                None
+            } else {
+                Some(value.to_owned())
+            }
+        };
+        let secret = || text().map(Secret::from);
+
+        let enigma = &mut self.enigma_args;
+
+        match key {
+            "algorithm" => self.algorithm_option = choice(key, value, &ALGORITHMS)?,
+            "format" => self.container_format = choice(key, value, &FORMATS)?,
+            "tcp-hash" => self.integrity_hash = hash_algorithm(key, value)?,
+            "shared-secret" => self.shared_secret = secret(),
+            "kdf-iterations" => self.kdf_iterations = text(),
+
+            "enigma-machine" => enigma.machine = choice(key, value, &MACHINES)?,
+            "enigma-reflector" => {
+                let reflectors = [&catalogue::REFLECTORS[..], &catalogue::THIN_REFLECTORS].concat();
+                enigma.refl_wiring = text().map(|value| wiring(&reflectors, value));
+            }
+            "enigma-greek" => {
+                enigma.greek_wiring = text().map(|value| wiring(&catalogue::GREEK_WHEELS, value));
+            }
+            "enigma-greek-ring" => enigma.greek_ringstellung = text(),
+            "enigma-greek-position" => enigma.greek_position = text(),
+            "enigma-rotor1" => rotor(&mut enigma.rot1_wiring, &mut enigma.rot1_notch, value),
+            "enigma-notch1" => enigma.rot1_notch = text(),
+            "enigma-ring1" => enigma.rot1_ringstellung = text(),
+            "enigma-position1" => enigma.rot1_position = text(),
+            "enigma-rotor2" => rotor(&mut enigma.rot2_wiring, &mut enigma.rot2_notch, value),
+            "enigma-notch2" => enigma.rot2_notch = text(),
+            "enigma-ring2" => enigma.rot2_ringstellung = text(),
+            "enigma-position2" => enigma.rot2_position = text(),
+            "enigma-rotor3" => rotor(&mut enigma.rot3_wiring, &mut enigma.rot3_notch, value),
+            "enigma-notch3" => enigma.rot3_notch = text(),
+            "enigma-ring3" => enigma.rot3_ringstellung = text(),
+            "enigma-position3" => enigma.rot3_position = text(),
+            "enigma-plugboard" => enigma.plugboard = text(),
+            "enigma-passphrase" => enigma.passphrase = secret(),
+            "enigma-text-mode" => enigma.text_mode = choice(key, value, &TEXT_MODES)?,
+            "enigma-groups" => enigma.five_letter_groups = choice(key, value, &SWITCH)?,
+
+            "xxtea-key" => self.xxtea_args.key = secret(),
+            "xxtea-mode" => {
+                self.xxtea_args.mode = if value.eq_ignore_ascii_case("whole") {
+                    XxteaMode::WholeMessage
+                } else {
+                    XxteaMode::Block(block_mode(key, value)?)
+                }
+            }
+            "xxtea-iv" => self.xxtea_args.iv = text(),
+            "xxtea-block-size" => self.xxtea_args.block_size = text(),
+
+            "aes-key" => self.aes_args.key = secret(),
+            "aes-key-size" => self.aes_args.key_size = choice(key, value, &KEY_SIZES)?,
+            "aes-mode" => self.aes_args.mode = block_mode(key, value)?,
+            "aes-iv" => self.aes_args.iv = text(),
+
+            "chacha-key" => self.chacha20poly1305_args.key = secret(),
+
+            _ => return Err(anyhow!("Unknown setting {}", key)),
+        }
+
+        Ok(())
+    }
+
+    /// The value of every key in [`config::KEYS`], in order. Applying them to any
+    /// settings gives these back.
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        let enigma = &self.enigma_args;
+        let xxtea = &self.xxtea_args;
+        let aes = &self.aes_args;
+
+        let values = [
+            name(&ALGORITHMS, self.algorithm_option),
+            name(&FORMATS, self.container_format),
+            self.integrity_hash.sum_extension().to_owned(),
+            secret(&self.shared_secret),
+            text(&self.kdf_iterations),
+            name(&MACHINES, enigma.machine),
+            text(&enigma.refl_wiring),
+            text(&enigma.greek_wiring),
+            text(&enigma.greek_ringstellung),
+            text(&enigma.greek_position),
+            text(&enigma.rot1_wiring),
+            text(&enigma.rot1_notch),
+            text(&enigma.rot1_ringstellung),
+            text(&enigma.rot1_position),
+            text(&enigma.rot2_wiring),
+            text(&enigma.rot2_notch),
+            text(&enigma.rot2_ringstellung),
+            text(&enigma.rot2_position),
+            text(&enigma.rot3_wiring),
+            text(&enigma.rot3_notch),
+            text(&enigma.rot3_ringstellung),
+            text(&enigma.rot3_position),
+            text(&enigma.plugboard),
+            secret(&enigma.passphrase),
+            name(&TEXT_MODES, enigma.text_mode),
+            name(&SWITCH, enigma.five_letter_groups),
+            secret(&xxtea.key),
+            match xxtea.mode {
+                XxteaMode::WholeMessage => String::from("whole"),
+                XxteaMode::Block(mode) => block_mode_name(mode),
+            },
+            text(&xxtea.iv),
+            text(&xxtea.block_size),
+            secret(&aes.key),
+            name(&KEY_SIZES, aes.key_size),
+            block_mode_name(aes.mode),
+            text(&aes.iv),
+            secret(&self.chacha20poly1305_args.key),
+        ];
+
+        config::KEYS.into_iter().zip(values).collect()
+    }
+}
+
+fn text(value: &Option<String>) -> String {
+    value.clone().unwrap_or_default()
+}
+
+fn secret(value: &Option<Secret>) -> String {
+    value
+        .as_ref()
+        .map(|value| value.to_string())
+        .unwrap_or_default()
+}
+
+/// A rotor named from the catalogue brings its notches along, a hand typed
+/// wiring keeps the current ones
+fn rotor(wiring: &mut Option<String>, notch: &mut Option<String>, value: &str) {
+    match component(&catalogue::ROTORS, value) {
+        Some(rotor) => {
+            *wiring = Some(rotor.wiring.to_owned());
+            *notch = Some(rotor.notches());
+        }
+        None if value.is_empty() => *wiring = None,
+        None => *wiring = Some(value.to_owned()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SettingsState;
+    use crate::cli::config::{format, parse, KEYS};
+    use zi_core::algorithms::enigma::alg::EnigmaMachine;
+    use zi_core::algorithms::enigma::catalogue;
+    use zi_core::algorithms::modes::alg::BlockMode;
+    use zi_core::algorithms::xxtea::alg::XxteaMode;
+    use zi_core::algorithms::AlgorithmOption;
+    use zi_core::hash::HashAlgorithm;
+
+    #[test]
+    fn keys_set_their_fields() {
+        let mut settings = SettingsState::default();
+
+        for (key, value) in [
+            ("algorithm", "XXTEA"),
+            ("xxtea-mode", "ctr"),
+            ("xxtea-key", "k"),
+            ("tcp-hash", "sha256"),
+            ("shared-secret", ""),
+            ("enigma-machine", "m4"),
+        ] {
+            settings.apply(key, value).unwrap();
+        }
+
+        assert_eq!(settings.algorithm_option, AlgorithmOption::Xxtea);
+        assert_eq!(settings.xxtea_args.mode, XxteaMode::Block(BlockMode::Ctr));
+        assert_eq!(
+            settings.xxtea_args.key.as_ref().map(|key| key.as_str()),
+            Some("k")
+        );
+        assert_eq!(settings.integrity_hash, HashAlgorithm::Sha256);
+        assert_eq!(settings.shared_secret, None);
+        assert_eq!(settings.enigma_args.machine, EnigmaMachine::M4);
+    }
+
+    #[test]
+    fn rotors_can_be_named() {
+        let mut settings = SettingsState::default();
+
+        settings.apply("enigma-rotor2", "VI").unwrap();
+        settings.apply("enigma-reflector", "UKW-C").unwrap();
+
+        let args = &settings.enigma_args;
+        assert_eq!(
+            args.rot2_wiring.as_deref(),
+            Some(catalogue::ROTORS[5].wiring)
+        );
+        assert_eq!(args.rot2_notch, Some(catalogue::ROTORS[5].notches()));
+        assert_eq!(args.refl_wiring.as_deref(), Some(catalogue::UKW_C));
+
+        settings
+            .apply("enigma-rotor2", "abcdefghijklmnopqrstuvwxyz")
+            .unwrap();
+        assert_eq!(
+            settings.enigma_args.rot2_notch,
+            Some(catalogue::ROTORS[5].notches())
+        );
+    }
+
+    #[test]
+    fn bad_keys_and_values_are_errors() {
+        let mut settings = SettingsState::default();
+
+        assert!(settings.apply("colour", "blue").is_err());
+        assert!(settings.apply("algorithm", "rot13").is_err());
+        assert!(settings.apply("aes-key-size", "512").is_err());
+        assert!(settings.apply("aes-mode", "gcm").is_err());
+    }
+
+    #[test]
+    fn entries_give_the_settings_back() {
+        let mut settings = SettingsState::default();
+        for (key, value) in [
+            ("algorithm", "chacha20-poly1305"),
+            ("xxtea-mode", "whole"),
+            ("aes-mode", "ofb"),
+            ("aes-key-size", "192"),
+            ("enigma-machine", "binary"),
+            ("enigma-greek", "Beta"),
+            ("enigma-plugboard", ""),
+            ("tcp-hash", "crc32"),
+        ] {
+            settings.apply(key, value).unwrap();
+        }
+
+        let written = format(&settings.entries());
+        let mut read = SettingsState::default();
+        read.apply("xxtea-key", "other").unwrap();
+        for (key, value) in parse(&written).unwrap() {
+            read.apply(&key, &value).unwrap();
+        }
+
+        assert_eq!(format(&read.entries()), written);
+        assert_eq!(read.enigma_args.plugboard, None);
+        assert_eq!(read.xxtea_args.key, settings.xxtea_args.key);
+    }
+
+    #[test]
+    fn every_listed_key_is_understood() {
+        for key in KEYS {
+            let mut settings = SettingsState::default();
+            let value = match key {
+                "algorithm" => "aes",
+                "format" => "raw",
+                "tcp-hash" => "md5",
+                "enigma-machine" => "m3",
+                "enigma-text-mode" => "keep",
+                "enigma-groups" => "true",
+                "xxtea-mode" => "whole",
+                "aes-key-size" => "256",
+                "aes-mode" => "cbc",
+                _ => "1",
+            };
+
+            assert!(settings.apply(key, value).is_ok(), "{}", key);
+        }
+    }
 }
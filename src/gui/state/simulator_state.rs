@@ -0,0 +1,11 @@
+use zi_core::algorithms::enigma::simulator::{EnigmaSimulator, SignalPath};
+
+#[derive(Default)]
+pub struct SimulatorState {
+    /// Built from the committed Enigma settings, `None` if they are invalid
+    pub machine: Option<EnigmaSimulator>,
+    pub error: Option<String>,
+    pub input_tape: String,
+    pub output_tape: String,
+    pub last_path: Option<SignalPath>,
+}
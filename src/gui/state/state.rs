@@ -1,35 +1,56 @@
-use crate::algorithms::Operation;
 use crate::gui::components::navigation;
 use crate::gui::toasts::{push_toast, toasts_widget, Severity, Toast};
-use crate::hash;
-use crate::utils::{get_algorithm, get_dir_path, get_file_path, get_new_file_path2, process_file};
-
+use crate::utils::{get_dir_path, get_file_path, get_file_paths, get_save_path};
+use crate::watcher;
+use zi_core::algorithms::enigma::alg::{Enigma, EnigmaMachine};
+use zi_core::algorithms::enigma::analysis::{self, SearchSpace};
+use zi_core::algorithms::enigma::bombe;
+use zi_core::algorithms::enigma::catalogue::{self, Component};
+use zi_core::algorithms::kdf::DEFAULT_ITERATIONS;
+use zi_core::algorithms::{AlgorithmOption, Operation};
+use zi_core::config::{Config, EnigmaConfig, Secret};
+use zi_core::hash::sums::{self, Check};
+use zi_core::hash::HashAlgorithm;
+use zi_core::keystore::Keystore;
+use zi_core::transfer;
+use zi_core::utils::{get_algorithm, process_file};
+
+use super::analysis_state::AnalysisState;
+use super::args::EnigmaArgs;
 use super::fsw_state::FSWState;
+use super::hash_state::HashState;
+use super::keystore_state::KeystoreState;
 use super::manual_state::ManualState;
 use super::messages::{
-    AlgorithmSettingsMessage, EnigmaSettingsMessage, FSWPageMessage, ManualPageMessage, Message,
-    NavigationMessage, TcpPageMessage, XxteaCfbSettingsMessage, XxteaSettingsMessage,
+    AesSettingsMessage, AlgorithmSettingsMessage, ChaCha20Poly1305SettingsMessage,
+    EnigmaAnalysisMessage, EnigmaSettingsMessage, EnigmaSimulatorMessage, FSWPageMessage,
+    HashPageMessage, KeystoreMessage, ManualPageMessage, Message, NavigationMessage,
+    ProfileMessage, TcpPageMessage, XxteaSettingsMessage,
 };
+use super::profiles_state::Profiles;
+use super::saved_state::{self, Source};
 use super::settings_state::SettingsState;
+use super::simulator_state::SimulatorState;
 use super::tcp_state::{TcpMode, TcpState};
 
 use super::super::pages::{
-    fsw::fsw_page, manual::manual_page, settings::settings_page, tcp::tcp_page, Page,
+    enigma_analysis::enigma_analysis_page, enigma_simulator::enigma_simulator_page, fsw::fsw_page,
+    hash::hash_page, lock::lock_page, manual::manual_page, settings::settings_page, tcp::tcp_page,
+    Page,
 };
 
-use std::ops::Deref;
+use std::io::ErrorKind;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use iced::{
+    event, keyboard, mouse,
     widget::{column, container, horizontal_rule, horizontal_space, row, stack},
-    Alignment, Element, Length, Subscription, Task,
+    Alignment, Element, Event, Length, Subscription, Task,
 };
 
-use notify::{recommended_watcher, RecursiveMode, Watcher};
-
-use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
-use tokio::net::{TcpListener, TcpStream};
+use tokio::net::TcpListener;
 
 #[derive(Default)]
 pub struct State {
@@ -37,23 +58,68 @@ pub struct State {
     pub fsw: FSWState,
     pub manual: ManualState,
     pub tcp: TcpState,
+    pub simulator: SimulatorState,
+    pub analysis: AnalysisState,
+    pub hash: HashState,
     pub settings: SettingsState,
-    pub commited_settings: Arc<RwLock<SettingsState>>,
+    /// Committed settings, shared with the FSW and TCP tasks
+    pub profiles: Arc<RwLock<Profiles>>,
+    /// The name field of the profile controls on the settings page
+    pub profile_name: String,
+    pub keystore: KeystoreState,
 
     pub toasts: Arc<RwLock<Vec<Toast>>>,
+
+    /// What was last written to the plain saved state file
+    saved: String,
 }
 
 impl State {
-    pub fn view(&self) -> Element<Message> {
-        let navigation = navigation(self);
-
-        let page: Element<Message> = match self.page {
-            Page::Fsw => fsw_page(self),
-            Page::Manual => manual_page(self),
-            Page::Tcp => tcp_page(self),
-            Page::Settings => settings_page(self),
+    /// The state the last run left behind, or the defaults on the first run.
+    /// It starts locked, the profiles are loaded once the keystore is.
+    pub fn load() -> State {
+        let mut state = State::default();
+        state.keystore.exists = saved_state::keystore_path().is_some_and(|path| path.exists());
+        let source = if state.keystore.exists {
+            Source::PlainFile
+        } else {
+            Source::LegacyFile
         };
 
+        if let Some(path) = saved_state::path() {
+            match std::fs::read_to_string(&path) {
+                Ok(text) => match saved_state::restore(&mut state, &text, source) {
+                    Ok(errors) => {
+                        state.saved = text;
+                        for err in errors {
+                            push_toast(
+                                &state.toasts,
+                                &format!("Skipped a saved setting: {}", err),
+                                Severity::Error,
+                            );
+                        }
+                    }
+                    Err(err) => push_toast(
+                        &state.toasts,
+                        &format!("Could not read {}: {}", path.display(), err),
+                        Severity::Error,
+                    ),
+                },
+                Err(err) if err.kind() == ErrorKind::NotFound => {}
+                Err(err) => push_toast(
+                    &state.toasts,
+                    &format!("Could not read {}: {}", path.display(), err),
+                    Severity::Error,
+                ),
+            }
+        }
+
+        state.profile_name = state.profiles.read().unwrap().active().name.clone();
+
+        return state;
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
         let toasts_overlay = container(row![
             horizontal_space().width(Length::FillPortion(1)),
             container(toasts_widget(self)).width(Length::FillPortion(2))
@@ -63,6 +129,22 @@ impl State {
         .align_x(Alignment::End)
         .align_y(Alignment::End);
 
+        if self.keystore.is_locked() {
+            return stack![lock_page(self), toasts_overlay].into();
+        }
+
+        let navigation = navigation(self);
+
+        let page: Element<Message> = match self.page {
+            Page::Fsw => fsw_page(self),
+            Page::Manual => manual_page(self),
+            Page::Tcp => tcp_page(self),
+            Page::Hash => hash_page(self),
+            Page::EnigmaSimulator => enigma_simulator_page(self),
+            Page::EnigmaAnalysis => enigma_analysis_page(self),
+            Page::Settings => settings_page(self),
+        };
+
         let main_view = column![
             navigation,
             horizontal_rule(0),
@@ -75,6 +157,13 @@ impl State {
     }
 
     pub fn update(&mut self, message: Message) -> Task<Message> {
+        let task = self.handle(message);
+        self.save();
+
+        return task;
+    }
+
+    fn handle(&mut self, message: Message) -> Task<Message> {
         match message {
             Message::Navigation(navigation_message) => match navigation_message {
                 NavigationMessage::GoToFSWPage => {
@@ -89,11 +178,163 @@ impl State {
                     self.page = Page::Tcp;
                     Task::none()
                 }
+                NavigationMessage::GoToHashPage => {
+                    self.page = Page::Hash;
+                    Task::none()
+                }
+                NavigationMessage::GoToEnigmaSimulatorPage => {
+                    self.page = Page::EnigmaSimulator;
+                    if self.simulator.machine.is_none() {
+                        self.load_simulator();
+                    }
+                    Task::none()
+                }
+                NavigationMessage::GoToEnigmaAnalysisPage => {
+                    self.page = Page::EnigmaAnalysis;
+                    Task::none()
+                }
                 NavigationMessage::GoToSettingsPage => {
                     self.page = Page::Settings;
                     Task::none()
                 }
             },
+            Message::Keystore(keystore_message) => match keystore_message {
+                KeystoreMessage::PasswordChanged(value) => {
+                    self.keystore.password = Secret::new(value);
+                    Task::none()
+                }
+                KeystoreMessage::ConfirmChanged(value) => {
+                    self.keystore.confirm = Secret::new(value);
+                    Task::none()
+                }
+                KeystoreMessage::Unlock => self.unlock(),
+                KeystoreMessage::Unlocked(result) => {
+                    self.keystore.is_busy = false;
+                    self.keystore.clear_passwords();
+                    match result {
+                        Ok((keystore, contents)) => self.unlocked(keystore, contents),
+                        Err(err) => push_toast(&self.toasts, &err, Severity::Error),
+                    }
+                    Task::none()
+                }
+                KeystoreMessage::Lock => self.lock(),
+                KeystoreMessage::Activity => {
+                    self.keystore.last_activity = Instant::now();
+                    Task::none()
+                }
+                KeystoreMessage::AutoLockPicked(auto_lock) => {
+                    self.keystore.auto_lock = auto_lock;
+                    Task::none()
+                }
+                KeystoreMessage::CurrentPasswordChanged(value) => {
+                    self.keystore.current_password = Secret::new(value);
+                    Task::none()
+                }
+                KeystoreMessage::NewPasswordChanged(value) => {
+                    self.keystore.new_password = Secret::new(value);
+                    Task::none()
+                }
+                KeystoreMessage::NewConfirmChanged(value) => {
+                    self.keystore.new_confirm = Secret::new(value);
+                    Task::none()
+                }
+                KeystoreMessage::ChangePassword => self.change_master_password(),
+                KeystoreMessage::PasswordReplaced(result) => {
+                    self.keystore.is_busy = false;
+                    self.keystore.clear_passwords();
+                    match result {
+                        // Locked while the KDF ran, which cleared the profiles,
+                        // so sealing them now would overwrite the saved ones
+                        Ok(_) if self.keystore.is_locked() => push_toast(
+                            &self.toasts,
+                            "The keystore was locked before the new password was set, it wasn't changed",
+                            Severity::Error,
+                        ),
+                        Ok(keystore) => {
+                            self.keystore.keystore = Some(keystore);
+                            // Sealed again under the new key when this update is saved
+                            self.keystore.saved = Secret::default();
+                            push_toast(
+                                &self.toasts,
+                                "The master password was changed",
+                                Severity::Success,
+                            );
+                        }
+                        Err(err) => push_toast(&self.toasts, &err, Severity::Error),
+                    }
+                    Task::none()
+                }
+            },
+            Message::Profile(profile_message) => match profile_message {
+                ProfileMessage::Picked(name) => {
+                    let index = self.profiles.read().unwrap().find(&name);
+                    if let Some(index) = index {
+                        self.activate_profile(index);
+                    }
+                    Task::none()
+                }
+                ProfileMessage::NameChanged(name) => {
+                    self.profile_name = name;
+                    Task::none()
+                }
+                ProfileMessage::New => {
+                    let index = self
+                        .profiles
+                        .write()
+                        .unwrap()
+                        .add("New profile", Default::default());
+                    self.activate_profile(index);
+                    Task::none()
+                }
+                ProfileMessage::Duplicate => {
+                    let mut profiles = self.profiles.write().unwrap();
+                    let active = profiles.active().clone();
+                    let index = profiles.add(&active.name, active.settings);
+                    drop(profiles);
+
+                    self.activate_profile(index);
+                    Task::none()
+                }
+                ProfileMessage::Rename => {
+                    let mut profiles = self.profiles.write().unwrap();
+                    let active = profiles.active;
+                    match profiles.rename(active, &self.profile_name) {
+                        Ok(_) => self.profile_name = profiles.active().name.clone(),
+                        Err(err) => push_toast(&self.toasts, &format!("{}", err), Severity::Error),
+                    }
+                    Task::none()
+                }
+                ProfileMessage::Delete => {
+                    let mut profiles = self.profiles.write().unwrap();
+                    let active = profiles.active;
+                    let removed = profiles.remove(active);
+                    let index = profiles.active;
+                    drop(profiles);
+
+                    match removed {
+                        Ok(profile) => {
+                            // Pins to it would quietly fall back to the active profile
+                            for pin in [
+                                &mut self.fsw.profile,
+                                &mut self.manual.profile,
+                                &mut self.tcp.profile,
+                            ] {
+                                if *pin == Some(profile.id) {
+                                    *pin = None;
+                                }
+                            }
+                            push_toast(
+                                &self.toasts,
+                                &format!("Deleted the profile {}", profile.name),
+                                Severity::Info,
+                            );
+                            self.activate_profile(index);
+                        }
+                        Err(err) => push_toast(&self.toasts, &format!("{}", err), Severity::Error),
+                    }
+                    Task::none()
+                }
+            },
             Message::FSW(fsw_message) => match fsw_message {
                 FSWPageMessage::GetDirToWatch => Task::perform(get_dir_path(), |path| {
                     Message::FSW(FSWPageMessage::DirToWatchResult(path))
@@ -121,6 +362,10 @@ impl State {
                     };
                     Task::none()
                 }
+                FSWPageMessage::ProfilePinned(pin) => {
+                    self.fsw.profile = pin.id();
+                    Task::none()
+                }
                 FSWPageMessage::TurnOn => self.turn_on_fsw(),
                 FSWPageMessage::TurnOff => self.turn_off_fsw(),
                 FSWPageMessage::WatchingStarted => {
@@ -132,6 +377,92 @@ impl State {
                     Task::none()
                 }
             },
+            Message::EnigmaSimulator(simulator_message) => match simulator_message {
+                EnigmaSimulatorMessage::KeyPressed(key) => {
+                    self.press_simulator_key(key);
+                    Task::none()
+                }
+                EnigmaSimulatorMessage::Reset => {
+                    self.load_simulator();
+                    Task::none()
+                }
+            },
+            Message::EnigmaAnalysis(analysis_message) => match analysis_message {
+                EnigmaAnalysisMessage::CiphertextChanged(value) => {
+                    self.analysis.ciphertext = value;
+                    Task::none()
+                }
+                EnigmaAnalysisMessage::RotorToggled(index, checked) => {
+                    self.analysis.rotors[index] = checked;
+                    Task::none()
+                }
+                EnigmaAnalysisMessage::ReflectorPicked(reflector) => {
+                    self.analysis.reflector = reflector;
+                    Task::none()
+                }
+                EnigmaAnalysisMessage::Start => self.start_analysis(),
+                EnigmaAnalysisMessage::Cancel => {
+                    self.analysis.cancel.store(true, Ordering::Relaxed);
+                    Task::none()
+                }
+                EnigmaAnalysisMessage::Progress(done) => {
+                    self.analysis.progress = done;
+                    Task::none()
+                }
+                EnigmaAnalysisMessage::Finished(result) => {
+                    self.analysis.is_running = false;
+                    match result {
+                        Ok(candidates) => {
+                            push_toast(
+                                &self.toasts,
+                                "The search is done, the best candidates are listed",
+                                Severity::Success,
+                            );
+                            self.analysis.candidates = candidates;
+                        }
+                        Err(err) => {
+                            self.analysis.progress = 0.0;
+                            push_toast(&self.toasts, &err, Severity::Error);
+                        }
+                    }
+                    Task::none()
+                }
+                EnigmaAnalysisMessage::ApplyCandidate(index) => {
+                    self.apply_enigma_key(&self.analysis.candidates[index].config.clone());
+                    Task::none()
+                }
+                EnigmaAnalysisMessage::CribChanged(value) => {
+                    self.analysis.crib = value;
+                    Task::none()
+                }
+                EnigmaAnalysisMessage::CribOffsetChanged(value) => {
+                    self.analysis.crib_offset = value;
+                    Task::none()
+                }
+                EnigmaAnalysisMessage::StartBombe => self.start_bombe(),
+                EnigmaAnalysisMessage::BombeFinished(result) => {
+                    self.analysis.is_running = false;
+                    match result {
+                        Ok(stops) => {
+                            push_toast(
+                                &self.toasts,
+                                &format!("The bombe stopped {} times", stops.len()),
+                                Severity::Success,
+                            );
+                            self.analysis.stops = stops;
+                        }
+                        Err(err) => {
+                            self.analysis.progress = 0.0;
+                            push_toast(&self.toasts, &err, Severity::Error);
+                        }
+                    }
+                    Task::none()
+                }
+                EnigmaAnalysisMessage::ApplyStop(index) => {
+                    self.apply_enigma_key(&self.analysis.stops[index].config.clone());
+                    Task::none()
+                }
+            },
             Message::Manual(manual_message) => match manual_message {
                 ManualPageMessage::GetFile => Task::perform(get_file_path(), |path| {
                     Message::Manual(ManualPageMessage::FileResult(path))
@@ -151,6 +482,10 @@ impl State {
                     }
                     Task::none()
                 }
+                ManualPageMessage::ProfilePinned(pin) => {
+                    self.manual.profile = pin.id();
+                    Task::none()
+                }
                 ManualPageMessage::StartEncryption => self.manual_encrypt(),
                 ManualPageMessage::EncryptionDone => {
                     self.manual.is_doing_work = false;
@@ -162,6 +497,133 @@ impl State {
                     Task::none()
                 }
             },
+            Message::Hash(hash_message) => match hash_message {
+                HashPageMessage::GetFiles => Task::perform(get_file_paths(), |paths| {
+                    Message::Hash(HashPageMessage::FilesResult(paths))
+                }),
+                HashPageMessage::FilesResult(paths) => {
+                    if let Some(paths) = paths.filter(|paths| !paths.is_empty()) {
+                        self.hash.dir = None;
+                        self.hash.files = paths;
+                        self.hash.digests.clear();
+                    }
+                    Task::none()
+                }
+                HashPageMessage::GetDir => Task::perform(get_dir_path(), |path| {
+                    Message::Hash(HashPageMessage::DirResult(path))
+                }),
+                HashPageMessage::DirResult(path_buf) => {
+                    if let Some(path) = path_buf {
+                        self.hash.files.clear();
+                        self.hash.dir = Some(path);
+                        self.hash.digests.clear();
+                    }
+                    Task::none()
+                }
+                HashPageMessage::AlgorithmPicked(algorithm) => {
+                    self.hash.algorithm = algorithm;
+                    // A sum file holds digests of one algorithm only
+                    self.hash.digests.clear();
+                    Task::none()
+                }
+                HashPageMessage::Start => self.start_hashing(),
+                HashPageMessage::Progress(done) => {
+                    self.hash.progress = done;
+                    Task::none()
+                }
+                HashPageMessage::Finished(result) => {
+                    self.hash.is_running = false;
+                    match result {
+                        Ok(digests) => {
+                            push_toast(
+                                &self.toasts,
+                                &format!("Hashed {} files", digests.len()),
+                                Severity::Success,
+                            );
+                            self.hash.digests = digests;
+                        }
+                        Err(err) => {
+                            self.hash.progress = 0.0;
+                            push_toast(&self.toasts, &err, Severity::Error);
+                        }
+                    }
+                    Task::none()
+                }
+                HashPageMessage::SaveSumFile => {
+                    let base = self.hash.base().expect("UI should not allow this");
+                    let name = format!(
+                        "{}.{}",
+                        base.file_name()
+                            .map(|name| name.to_string_lossy().into_owned())
+                            .unwrap_or(String::from("checksums")),
+                        self.hash.algorithm.sum_extension()
+                    );
+
+                    Task::perform(get_save_path(base, name), |path| {
+                        Message::Hash(HashPageMessage::SumFileToSaveResult(path))
+                    })
+                }
+                HashPageMessage::SumFileToSaveResult(path_buf) => {
+                    if let Some(path) = path_buf {
+                        match std::fs::write(&path, sums::format(&self.hash.digests)) {
+                            Ok(_) => push_toast(
+                                &self.toasts,
+                                &format!("Saved {}", path.display()),
+                                Severity::Success,
+                            ),
+                            Err(err) => push_toast(
+                                &self.toasts,
+                                &format!("Could not save the sum file: {}", err),
+                                Severity::Error,
+                            ),
+                        }
+                    }
+                    Task::none()
+                }
+                HashPageMessage::GetSumFile => Task::perform(get_file_path(), |path| {
+                    Message::Hash(HashPageMessage::SumFileResult(path))
+                }),
+                HashPageMessage::SumFileResult(path_buf) => {
+                    if let Some(path) = path_buf {
+                        if let Some(algorithm) = HashAlgorithm::from_sum_file(&path) {
+                            self.hash.algorithm = algorithm;
+                            self.hash.digests.clear();
+                        }
+                        self.hash.sum_file = Some(path);
+                        self.hash.checks.clear();
+                    }
+                    Task::none()
+                }
+                HashPageMessage::Verify => self.verify_sums(),
+                HashPageMessage::VerifyFinished(result) => {
+                    self.hash.is_running = false;
+                    match result {
+                        Ok(checks) => {
+                            let failed =
+                                checks.iter().filter(|file| file.check != Check::Ok).count();
+                            if failed == 0 {
+                                push_toast(
+                                    &self.toasts,
+                                    &format!("All {} files are OK", checks.len()),
+                                    Severity::Success,
+                                );
+                            } else {
+                                push_toast(
+                                    &self.toasts,
+                                    &format!("{} of {} files failed", failed, checks.len()),
+                                    Severity::Error,
+                                );
+                            }
+                            self.hash.checks = checks;
+                        }
+                        Err(err) => {
+                            self.hash.progress = 0.0;
+                            push_toast(&self.toasts, &err, Severity::Error);
+                        }
+                    }
+                    Task::none()
+                }
+            },
             Message::Tcp(tcp_page_message) => match tcp_page_message {
                 TcpPageMessage::ToggleMode => {
                     if let TcpMode::Sending = self.tcp.mode {
@@ -171,6 +633,10 @@ impl State {
                     };
                     Task::none()
                 }
+                TcpPageMessage::ProfilePinned(pin) => {
+                    self.tcp.profile = pin.id();
+                    Task::none()
+                }
                 TcpPageMessage::SelectFileToSend => Task::perform(get_file_path(), |path| {
                     Message::Tcp(TcpPageMessage::FileToSendResult(path))
                 }),
@@ -286,38 +752,155 @@ impl State {
                                 self.settings.enigma_args.rot3_position = value;
                                 Task::none()
                             }
+                            EnigmaSettingsMessage::MachineChanged(machine) => {
+                                let args = &mut self.settings.enigma_args;
+                                // Wide and thin reflectors don't fit the other
+                                // machine, swap in its usual one
+                                if let (Some(current), Some(new)) = (
+                                    args.machine.default_reflector(),
+                                    machine.default_reflector(),
+                                ) {
+                                    if args.refl_wiring.as_deref() == Some(current) {
+                                        args.refl_wiring = Some(new.to_owned());
+                                    }
+                                }
+                                args.machine = machine;
+                                Task::none()
+                            }
+                            EnigmaSettingsMessage::ReflectorPicked(component) => {
+                                if component != Component::CUSTOM {
+                                    let args = &mut self.settings.enigma_args;
+                                    args.refl_wiring = Some(component.wiring.to_owned());
+                                }
+                                Task::none()
+                            }
+                            EnigmaSettingsMessage::Rot1Picked(component) => {
+                                if component != Component::CUSTOM {
+                                    let args = &mut self.settings.enigma_args;
+                                    args.rot1_wiring = Some(component.wiring.to_owned());
+                                    args.rot1_notch = Some(component.notches());
+                                }
+                                Task::none()
+                            }
+                            EnigmaSettingsMessage::Rot2Picked(component) => {
+                                if component != Component::CUSTOM {
+                                    let args = &mut self.settings.enigma_args;
+                                    args.rot2_wiring = Some(component.wiring.to_owned());
+                                    args.rot2_notch = Some(component.notches());
+                                }
+                                Task::none()
+                            }
+                            EnigmaSettingsMessage::Rot3Picked(component) => {
+                                if component != Component::CUSTOM {
+                                    let args = &mut self.settings.enigma_args;
+                                    args.rot3_wiring = Some(component.wiring.to_owned());
+                                    args.rot3_notch = Some(component.notches());
+                                }
+                                Task::none()
+                            }
+                            EnigmaSettingsMessage::GreekWheelPicked(component) => {
+                                if component != Component::CUSTOM {
+                                    let args = &mut self.settings.enigma_args;
+                                    args.greek_wiring = Some(component.wiring.to_owned());
+                                }
+                                Task::none()
+                            }
+                            EnigmaSettingsMessage::GreekWiringChanged(value) => {
+                                self.settings.enigma_args.greek_wiring = value;
+                                Task::none()
+                            }
+                            EnigmaSettingsMessage::GreekRingstellungChanged(value) => {
+                                self.settings.enigma_args.greek_ringstellung = value;
+                                Task::none()
+                            }
+                            EnigmaSettingsMessage::GreekPositionChanged(value) => {
+                                self.settings.enigma_args.greek_position = value;
+                                Task::none()
+                            }
                             EnigmaSettingsMessage::PlugboardChanged(value) => {
                                 self.settings.enigma_args.plugboard = value;
                                 Task::none()
                             }
+                            EnigmaSettingsMessage::PassphraseChanged(value) => {
+                                self.settings.enigma_args.passphrase = value.map(Secret::from);
+                                Task::none()
+                            }
+                            EnigmaSettingsMessage::TextModeChanged(value) => {
+                                self.settings.enigma_args.text_mode = value;
+                                Task::none()
+                            }
+                            EnigmaSettingsMessage::FiveLetterGroupsToggled(value) => {
+                                self.settings.enigma_args.five_letter_groups = value;
+                                Task::none()
+                            }
                         }
                     }
                     AlgorithmSettingsMessage::Xxtea(xxteasettings_mesasge) => {
                         match xxteasettings_mesasge {
                             XxteaSettingsMessage::KeyChanged(value) => {
-                                self.settings.xxtea_args.key = value;
+                                self.settings.xxtea_args.key = value.map(Secret::from);
+                                Task::none()
+                            }
+                            XxteaSettingsMessage::ModeChanged(value) => {
+                                self.settings.xxtea_args.mode = value;
+                                Task::none()
+                            }
+                            XxteaSettingsMessage::IVChanged(value) => {
+                                self.settings.xxtea_args.iv = value;
+                                Task::none()
+                            }
+                            XxteaSettingsMessage::BlockSizeChanged(value) => {
+                                self.settings.xxtea_args.block_size = value;
                                 Task::none()
                             }
                         }
                     }
-                    AlgorithmSettingsMessage::XxteaCfb(xxtea_cfbsettings_mesasge) => {
-                        match xxtea_cfbsettings_mesasge {
-                            XxteaCfbSettingsMessage::KeyChanged(value) => {
-                                self.settings.xxtea_cfb_args.key = value;
+                    AlgorithmSettingsMessage::Aes(aes_settings_message) => {
+                        match aes_settings_message {
+                            AesSettingsMessage::KeyChanged(value) => {
+                                self.settings.aes_args.key = value.map(Secret::from);
+                                Task::none()
+                            }
+                            AesSettingsMessage::KeySizeChanged(value) => {
+                                self.settings.aes_args.key_size = value;
+                                Task::none()
+                            }
+                            AesSettingsMessage::ModeChanged(value) => {
+                                self.settings.aes_args.mode = value;
                                 Task::none()
                             }
-                            XxteaCfbSettingsMessage::IVChanged(value) => {
-                                self.settings.xxtea_cfb_args.iv = value;
+                            AesSettingsMessage::IVChanged(value) => {
+                                self.settings.aes_args.iv = value;
                                 Task::none()
                             }
-                            XxteaCfbSettingsMessage::BlockSizeChanged(value) => {
-                                self.settings.xxtea_cfb_args.block_size = value;
+                        }
+                    }
+                    AlgorithmSettingsMessage::ChaCha20Poly1305(chacha_settings_message) => {
+                        match chacha_settings_message {
+                            ChaCha20Poly1305SettingsMessage::KeyChanged(value) => {
+                                self.settings.chacha20poly1305_args.key = value.map(Secret::from);
                                 Task::none()
                             }
                         }
                     }
                 }
             }
+            Message::ContainerFormatChanged(format) => {
+                self.settings.container_format = format;
+                Task::none()
+            }
+            Message::IntegrityHashChanged(variant) => {
+                self.settings.integrity_hash = variant;
+                Task::none()
+            }
+            Message::SharedSecretChanged(secret) => {
+                self.settings.shared_secret = secret.map(Secret::from);
+                Task::none()
+            }
+            Message::KdfIterationsChanged(iterations) => {
+                self.settings.kdf_iterations = iterations;
+                Task::none()
+            }
             Message::CommitSettings => {
                 self.commit_settings();
                 Task::none()
@@ -354,6 +937,18 @@ impl State {
                     ts.extend(filtered);
                 }
 
+                // Locking stops the FSW and the TCP listener, which run
+                // unattended, so they keep the keystore open
+                let in_background = self.fsw.watcher.is_some() || self.tcp.is_listening;
+                if self.keystore.is_idle() && !in_background {
+                    push_toast(
+                        &self.toasts,
+                        "The keystore was locked after being idle",
+                        Severity::Info,
+                    );
+                    return self.lock();
+                }
+
                 Task::none()
             }
             Message::Empty => Task::none(),
@@ -361,7 +956,35 @@ impl State {
     }
 
     pub fn subscription(&self) -> Subscription<Message> {
-        iced::time::every(Duration::from_secs(1)).map(|_| Message::Tick)
+        let tick = iced::time::every(Duration::from_secs(1)).map(|_| Message::Tick);
+
+        if self.keystore.is_locked() {
+            return tick;
+        }
+
+        // Mouse moves don't count, they happen without anyone at the desk
+        let activity = event::listen_with(|event, _, _| match event {
+            Event::Keyboard(keyboard::Event::KeyPressed { .. })
+            | Event::Mouse(mouse::Event::ButtonPressed(_)) => {
+                Some(Message::Keystore(KeystoreMessage::Activity))
+            }
+            _ => None,
+        });
+
+        if let Page::EnigmaSimulator = self.page {
+            let typing = keyboard::on_key_press(|key, modifiers| match key {
+                keyboard::Key::Character(c) if !modifiers.command() && !modifiers.alt() => c
+                    .chars()
+                    .next()
+                    .filter(char::is_ascii_alphabetic)
+                    .map(|c| Message::EnigmaSimulator(EnigmaSimulatorMessage::KeyPressed(c))),
+                _ => None,
+            });
+
+            return Subscription::batch([tick, activity, typing]);
+        }
+
+        Subscription::batch([tick, activity])
     }
 
     // pub fn subscription(&self) -> Subscription<Message> {
@@ -377,90 +1000,300 @@ impl State {
     //     .map(|_| Message::Tick)
     // }
 
-    fn turn_on_fsw(&mut self) -> Task<Message> {
-        let dir_to_watch = self
-            .fsw
-            .from
-            .as_ref()
-            .expect("This should not allow UI")
-            .to_owned();
+    fn analysis_space(&self) -> SearchSpace {
+        SearchSpace {
+            rotors: catalogue::ROTORS
+                .iter()
+                .zip(self.analysis.rotors)
+                .filter_map(|(rotor, picked)| picked.then_some(*rotor))
+                .collect(),
+            reflector: self.analysis.reflector,
+        }
+    }
 
-        let dest_dir = self
-            .fsw
-            .to
-            .as_ref()
-            .expect("This should not allow UI")
-            .to_owned();
+    fn start_analysis(&mut self) -> Task<Message> {
+        let ciphertext = self.analysis.ciphertext.clone().unwrap_or_default();
+        let space = self.analysis_space();
 
-        if match (dir_to_watch.canonicalize(), dest_dir.canonicalize()) {
-            (Ok(canonical_path1), Ok(canonical_path2)) => canonical_path1 == canonical_path2,
-            _ => false,
-        } {
-            eprintln!("Source and destination directory are the same. This would create an infinite loop.");
+        self.analysis.candidates.clear();
+
+        self.run_analysis_job(move |cancel, progress| {
+            let result = analysis::search(&ciphertext, &space, &cancel, progress);
+
+            EnigmaAnalysisMessage::Finished(result.map_err(|err| format!("{}", err)))
+        })
+    }
+
+    fn start_bombe(&mut self) -> Task<Message> {
+        let ciphertext = self.analysis.ciphertext.clone().unwrap_or_default();
+        let crib = self.analysis.crib.clone().unwrap_or_default();
+        let space = self.analysis_space();
+
+        let Some(offset) = self
+            .analysis
+            .crib_offset
+            .as_ref()
+            .and_then(|offset| offset.parse().ok())
+        else {
             push_toast(
-                            &self.toasts,
-                            "Source and destination directory are the same. This would create an infinite loop.",
-                            Severity::Error,
-                        );
+                &self.toasts,
+                "The crib offset is not a number",
+                Severity::Error,
+            );
             return Task::none();
         };
 
-        let operation = self.fsw.mode.to_owned();
+        self.analysis.stops.clear();
 
-        let (event_tx, mut event_rx) = tokio::sync::mpsc::channel(10);
+        self.run_analysis_job(move |cancel, progress| {
+            let result = bombe::run(&ciphertext, &crib, offset, &space, &cancel, progress);
 
-        let mut watcher = recommended_watcher(move |res| {
-                                            match res {
-                                                Ok(event) => {
-                                                    event_tx.blocking_send(event).expect("Send error. A send operation can only fail if the receiving end of a channel is disconnected, implying that the data could never be received. The error contains the data being sent as a payload so it can be recovered.")
-                                                },
-                                                Err(e) => println!("Event handler recieved error: {:?}", e),
-                                            }
-                                        }).expect("Couldn't create watcher");
+            EnigmaAnalysisMessage::BombeFinished(result.map_err(|err| format!("{}", err)))
+        })
+    }
 
-        watcher
-            .watch(&dir_to_watch, RecursiveMode::NonRecursive)
-            .expect("Couldn't start watcher");
+    /// Runs a CPU bound search on a blocking thread, which reports progress
+    /// and its result back through a channel
+    fn run_analysis_job<F>(&mut self, job: F) -> Task<Message>
+    where
+        F: FnOnce(Arc<AtomicBool>, &mut dyn FnMut(f32)) -> EnigmaAnalysisMessage + Send + 'static,
+    {
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.analysis.cancel = cancel.clone();
+        self.analysis.is_running = true;
+        self.analysis.progress = 0.0;
+
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+
+        tokio::task::spawn_blocking(move || {
+            let finished = job(cancel, &mut |done| {
+                let _ = sender.send(EnigmaAnalysisMessage::Progress(done));
+            });
+
+            let _ = sender.send(finished);
+        });
 
-        self.fsw.watcher = Some(Box::new(watcher));
+        Task::run(
+            async_stream::stream! {
+                while let Some(message) = receiver.recv().await {
+                    yield message;
+                }
+            },
+            Message::EnigmaAnalysis,
+        )
+    }
 
-        let toasts = self.toasts.clone();
+    fn start_hashing(&mut self) -> Task<Message> {
+        let algorithm = self.hash.algorithm;
+        let base = self.hash.base().expect("UI should not allow this");
+        let dir = self.hash.dir.clone();
+        let files = self.hash.files.clone();
 
-        let settings_pointer = Arc::clone(&self.commited_settings);
+        self.hash.digests.clear();
 
-        tokio::spawn(async move {
-            while let Some(event) = event_rx.recv().await {
-                if !matches!(event.kind, notify::EventKind::Create(_)) {
-                    continue;
-                }
+        self.run_hash_job(move |progress| {
+            let result = match dir {
+                Some(dir) => sums::collect_files(&dir),
+                None => Ok(files),
+            }
+            .and_then(|files| sums::hash_files(algorithm, &base, &files, progress));
 
-                let file_path = event.paths.first().unwrap().to_owned();
-                // println!("New File: {:?}", file_path);
+            HashPageMessage::Finished(result.map_err(|err| format!("{:#}", err)))
+        })
+    }
 
-                // "Waiting" for file to become ready
-                for _ in 0..5 {
-                    match tokio::fs::File::open(&file_path).await {
-                        Ok(_) => break,
-                        Err(_) => {
-                            tokio::time::sleep(Duration::from_millis(100)).await;
-                        }
-                    }
+    fn verify_sums(&mut self) -> Task<Message> {
+        let sum_file = self
+            .hash
+            .sum_file
+            .clone()
+            .expect("UI should not allow this");
+        let algorithm = HashAlgorithm::from_sum_file(&sum_file).unwrap_or(self.hash.algorithm);
+        // Sum files usually sit in the directory they describe
+        let dir = self
+            .hash
+            .dir
+            .clone()
+            .or_else(|| sum_file.parent().map(|dir| dir.to_path_buf()))
+            .unwrap_or_default();
+
+        self.hash.checks.clear();
+
+        self.run_hash_job(move |progress| {
+            let result = std::fs::read_to_string(&sum_file)
+                .map_err(anyhow::Error::from)
+                .and_then(|sums| sums::verify(algorithm, &dir, &sums, progress));
+
+            HashPageMessage::VerifyFinished(result.map_err(|err| format!("{:#}", err)))
+        })
+    }
+
+    /// Hashes files on a blocking thread, like [`State::run_analysis_job`]
+    fn run_hash_job<F>(&mut self, job: F) -> Task<Message>
+    where
+        F: FnOnce(&mut dyn FnMut(f32)) -> HashPageMessage + Send + 'static,
+    {
+        self.hash.is_running = true;
+        self.hash.progress = 0.0;
+
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+
+        tokio::task::spawn_blocking(move || {
+            let finished = job(&mut |done| {
+                let _ = sender.send(HashPageMessage::Progress(done));
+            });
+
+            let _ = sender.send(finished);
+        });
+
+        Task::run(
+            async_stream::stream! {
+                while let Some(message) = receiver.recv().await {
+                    yield message;
                 }
+            },
+            Message::Hash,
+        )
+    }
+
+    /// Make a key found by the analysis the committed Enigma setting
+    fn apply_enigma_key(&mut self, key: &EnigmaConfig) {
+        let current = &self.settings.enigma_args;
+
+        // Presentation and byte mode fields are not part of the key
+        self.settings.enigma_args = EnigmaArgs {
+            text_mode: current.text_mode,
+            five_letter_groups: current.five_letter_groups,
+            passphrase: current.passphrase.clone(),
+            ..EnigmaArgs::from(key)
+        };
+        self.settings.algorithm_option = AlgorithmOption::Enigma;
+        self.commit_settings();
+        self.simulator.machine = None;
+
+        push_toast(
+            &self.toasts,
+            "The key is now the Enigma setting",
+            Severity::Success,
+        );
+    }
+
+    fn load_simulator(&mut self) {
+        let settings = self.profiles.read().unwrap().active().settings.clone();
+
+        self.simulator = SimulatorState::default();
+
+        if settings.enigma_args.machine == EnigmaMachine::Binary {
+            self.simulator.error = Some(String::from(
+                "The simulator needs an M3 or M4, the byte machine has no keyboard",
+            ));
+            return;
+        }
+
+        let args = &settings.enigma_args;
+        match args
+            .to_config()
+            .and_then(|config| Enigma::try_new(&config, args.machine))
+        {
+            Ok(enigma) => self.simulator.machine = Some(enigma.simulator()),
+            Err(err) => {
+                self.simulator.error = Some(format!("Enigma settings are invalid: {}", err))
+            }
+        }
+    }
+
+    fn press_simulator_key(&mut self, key: char) {
+        let Some(machine) = self.simulator.machine.as_mut() else {
+            return;
+        };
+
+        let path = machine.press(key.to_ascii_lowercase() as u8);
+
+        let letters =
+            self.simulator.input_tape.len() - self.simulator.input_tape.matches(' ').count();
+        if letters > 0 && letters.is_multiple_of(5) {
+            self.simulator.input_tape.push(' ');
+            self.simulator.output_tape.push(' ');
+        }
+        self.simulator
+            .input_tape
+            .push(path.key.to_ascii_uppercase() as char);
+        self.simulator
+            .output_tape
+            .push(path.lamp.to_ascii_uppercase() as char);
+
+        self.simulator.last_path = Some(path);
+    }
+
+    fn turn_on_fsw(&mut self) -> Task<Message> {
+        let dir_to_watch = self
+            .fsw
+            .from
+            .as_ref()
+            .expect("This should not allow UI")
+            .to_owned();
+
+        let dest_dir = self
+            .fsw
+            .to
+            .as_ref()
+            .expect("This should not allow UI")
+            .to_owned();
+
+        if let Err(err) = watcher::check_dirs(&dir_to_watch, &dest_dir) {
+            eprintln!("{}", err);
+            push_toast(&self.toasts, &format!("{}", err), Severity::Error);
+            return Task::none();
+        };
+
+        let operation = self.fsw.mode.to_owned();
+
+        let (watcher, mut created_files) = match watcher::watch(&dir_to_watch) {
+            Ok(val) => val,
+            Err(err) => {
+                push_toast(&self.toasts, &format!("{:#}", err), Severity::Error);
+                return Task::none();
+            }
+        };
+
+        self.fsw.watcher = Some(Box::new(watcher));
 
+        let toasts = self.toasts.clone();
+
+        let profiles = Arc::clone(&self.profiles);
+        let pin = self.fsw.profile;
+
+        tokio::spawn(async move {
+            while let Some(file_path) = created_files.next().await {
                 let dest_dir = dest_dir.clone();
 
                 let toasts = toasts.clone();
 
-                let alg = match get_algorithm(&settings_pointer.read().unwrap()) {
-                    Ok(a) => a,
+                // A bad profile only skips this file, the profile can be fixed
+                // while the folder stays watched
+                let skipped = |err: anyhow::Error| {
+                    let message = format!("Skipped {}: {}", file_path.display(), err);
+                    push_toast(&toasts, &message, Severity::Error);
+                };
+
+                let config = match profiles.read().unwrap().settings(pin).to_config() {
+                    Ok(config) => config,
                     Err(err) => {
-                        push_toast(&toasts, &format!("{}", err), Severity::Error);
-                        return;
+                        skipped(err);
+                        continue;
                     }
                 };
 
+                // When decrypting, the container header may select a different algorithm
+                if let Operation::Encrypt = operation {
+                    if let Err(err) = get_algorithm(&config) {
+                        skipped(err);
+                        continue;
+                    }
+                }
+
                 tokio::spawn(async move {
-                    return match process_file(&file_path, &alg, operation, &dest_dir).await {
+                    return match process_file(&file_path, &config, operation, &dest_dir).await {
                         Ok(_) => {
                             push_toast(&toasts, "Successfully processed file", Severity::Success);
                         }
@@ -468,7 +1301,7 @@ impl State {
                             eprintln!("There was an error processing the file: {:?}", err);
                             push_toast(
                                 &toasts,
-                                "There was an error processing the file",
+                                &format!("There was an error processing the file: {}", err),
                                 Severity::Error,
                             );
                         }
@@ -514,8 +1347,8 @@ impl State {
 
         let toasts = self.toasts.clone();
 
-        let alg = match get_algorithm(&self.commited_settings.read().unwrap()) {
-            Ok(a) => Arc::new(a),
+        let config = match self.committed_config(self.manual.profile) {
+            Ok(config) => config,
             Err(err) => {
                 push_toast(&toasts, &format!("{}", err), Severity::Error);
                 self.manual.is_doing_work = false;
@@ -525,7 +1358,7 @@ impl State {
 
         Task::perform(
             async move {
-                match process_file(&file_path, alg.deref(), Operation::Encrypt, &dest_dir).await {
+                match process_file(&file_path, &config, Operation::Encrypt, &dest_dir).await {
                     Ok(_) => {
                         push_toast(&toasts, "Successfully processed file", Severity::Success);
                     }
@@ -533,7 +1366,7 @@ impl State {
                         eprintln!("There was an error processing the file: {:?}", err);
                         push_toast(
                             &toasts,
-                            "There was an error processing the file",
+                            &format!("There was an error processing the file: {}", err),
                             Severity::Error,
                         );
                     }
@@ -548,8 +1381,16 @@ impl State {
 
         let toasts = self.toasts.clone();
 
-        let alg = match get_algorithm(&self.commited_settings.read().unwrap()) {
-            Ok(a) => Arc::new(a),
+        // Not built here, the container header may select a different
+        // algorithm than the one in settings
+        let config = match self
+            .profiles
+            .read()
+            .unwrap()
+            .settings(self.manual.profile)
+            .to_config()
+        {
+            Ok(config) => config,
             Err(err) => {
                 push_toast(&toasts, &format!("{}", err), Severity::Error);
                 self.manual.is_doing_work = false;
@@ -567,7 +1408,7 @@ impl State {
 
         Task::perform(
             async move {
-                match process_file(&file_path, alg.deref(), Operation::Decrypt, &dest_dir).await {
+                match process_file(&file_path, &config, Operation::Decrypt, &dest_dir).await {
                     Ok(_) => {
                         push_toast(&toasts, "Successfully processed file", Severity::Success);
                     }
@@ -575,7 +1416,7 @@ impl State {
                         eprintln!("There was an error processing the file: {:?}", err);
                         push_toast(
                             &toasts,
-                            "There was an error processing the file",
+                            &format!("There was an error processing the file: {}", err),
                             Severity::Error,
                         );
                     }
@@ -606,18 +1447,10 @@ impl State {
             .expect("UI logic should not allow this")
             .to_owned();
 
-        let file_name = match file_path.file_name() {
-            Some(str) => str.to_string_lossy().to_string(),
-            None => {
-                push_toast(&toasts, "Coulnd't extract file name", Severity::Error);
-                return Task::none();
-            }
-        };
-
-        let alg = match get_algorithm(&self.commited_settings.read().unwrap()) {
-            Ok(a) => Arc::new(a),
+        let config = match self.committed_config(self.tcp.profile) {
+            Ok(config) => config,
             Err(err) => {
-                push_toast(&toasts, &format!("{}", err), Severity::Success);
+                push_toast(&toasts, &format!("{}", err), Severity::Error);
                 return Task::none();
             }
         };
@@ -625,191 +1458,17 @@ impl State {
         self.tcp.is_sending = true;
         Task::perform(
             async move {
-                let mut file = match tokio::fs::File::open(file_path).await {
-                    Ok(s) => s,
-                    Err(err) => {
-                        eprintln!(
-                            "Error opening the file when trying to send it over tcp: {:?}",
-                            err
-                        );
-                        push_toast(&toasts, "Couldn't open the file", Severity::Error);
-                        return;
-                    }
-                };
-
-                let mut file_name_leb128_buf: Vec<u8> = Vec::new();
-                let file_name_leb128_bytes = match leb128::write::unsigned(
-                    &mut file_name_leb128_buf,
-                    file_name.len().try_into().unwrap(),
-                ) {
-                    Ok(b) => b,
-                    Err(err) => {
-                        eprintln!("Error encoding file name len-prefix {:?}", err);
-                        push_toast(&toasts, "Error", Severity::Error);
-                        return;
-                    }
-                };
-                let file_name_prefix = &file_name_leb128_buf[..file_name_leb128_bytes];
-
-                let file_size = match file.metadata().await {
-                    Ok(metadata) => metadata.len(),
-                    Err(err) => {
-                        eprintln!("Error trying to read file metadata: {:?}", err);
-                        push_toast(&toasts, "Error", Severity::Error);
-                        return;
-                    }
-                };
-
-                let mut file_content = Vec::with_capacity(file_size.try_into().unwrap());
-                match file.read_to_end(&mut file_content).await {
-                    Ok(_) => (),
-                    Err(err) => {
-                        eprintln!(
-                            "Error reading the file when trying to send it over tcp: {:?}",
-                            err
-                        );
-                        push_toast(&toasts, "Couldn't read the file", Severity::Error);
-                        return;
-                    }
-                };
-
-                let encrypted_file_content = match alg.encrypt(&file_content) {
-                    Ok(v) => v,
-                    Err(err) => {
-                        eprintln!("Error encrypting file content: {:?}", err);
-                        push_toast(&toasts, "Error", Severity::Error);
-                        return;
-                    }
-                };
-
-                let hash = hash::hash_data(&encrypted_file_content);
-
-                match TcpStream::connect(address).await {
-                    Ok(mut stream) => {
-                        println!("Successfully connected to the server");
-                        push_toast(&toasts, "Established connection", Severity::Info);
-                        match stream.write_all(file_name_prefix).await {
-                            Ok(_) => {
-                                println!("Successfully sent len-prefix of the file name")
-                            }
-                            Err(err) => {
-                                eprintln!("Error sending len-prefix of the file name: {:?}", err);
-                                push_toast(
-                                    &toasts,
-                                    "An error occurred while sending data",
-                                    Severity::Error,
-                                );
-                                return;
-                            }
-                        };
-
-                        match stream.write_all(file_name.as_bytes()).await {
-                            Ok(_) => {
-                                println!("Successfully sent file name: {}", file_name);
-                            }
-                            Err(err) => {
-                                eprintln!("Error sending file name : {:?}", err);
-                                push_toast(
-                                    &toasts,
-                                    "An error occurred while sending data",
-                                    Severity::Error,
-                                );
-                                return;
-                            }
-                        };
-
-                        match stream.write_i64_le(file_size.try_into().unwrap()).await {
-                            Ok(_) => {
-                                println!("Successfully sent file size: {}", file_size);
-                            }
-                            Err(err) => {
-                                eprintln!("Error sending file size : {:?}", err);
-                                push_toast(
-                                    &toasts,
-                                    "An error occurred while sending data",
-                                    Severity::Error,
-                                );
-                                return;
-                            }
-                        };
-
-                        match stream.write_i32_le(hash.len().try_into().unwrap()).await {
-                            Ok(_) => {
-                                println!("Succesffully sent hash size {}", hash.len());
-                            }
-                            Err(err) => {
-                                eprintln!("Error sending hash size : {:?}", err);
-                                push_toast(
-                                    &toasts,
-                                    "An error occurred while sending data",
-                                    Severity::Error,
-                                );
-                                return;
-                            }
-                        }
-
-                        match stream.write_all(&hash).await {
-                            Ok(_) => {
-                                println!(
-                                    "Successfully sent hash:\n {:?}",
-                                    String::from_utf8_lossy(&hash)
-                                );
-                            }
-                            Err(err) => {
-                                eprintln!("Error sending hash: {:?}", err);
-                                push_toast(
-                                    &toasts,
-                                    "An error occurred while sending data",
-                                    Severity::Error,
-                                );
-                                return;
-                            }
-                        }
-
-                        match stream.write_all(&encrypted_file_content).await {
-                            Ok(_) => {
-                                println!("Successfully sent encrypted file content")
-                                // println!(
-                                //     "Successfully sent encrypted file content:\n {:?}",
-                                //     String::from_utf8_lossy(&encrypted_file_content)
-                                // )
-                            }
-                            Err(err) => {
-                                eprintln!("Error sending file content: {:?}", err);
-                                push_toast(
-                                    &toasts,
-                                    "An error occurred while sending data",
-                                    Severity::Error,
-                                );
-                                return;
-                            }
-                        };
+                let report = |message: &str| push_toast(&toasts, message, Severity::Info);
 
+                match transfer::send_file(&config, &file_path, &address, report).await {
+                    Ok(_) => {
                         push_toast(&toasts, "The file was successfully sent", Severity::Success);
-
-                        match stream.shutdown().await {
-                            Ok(_) => {
-                                println!("Successfully closed connection");
-                            }
-                            Err(err) => {
-                                eprintln!(
-                                    "An error occurred while closing the connection: {:?}",
-                                    err
-                                );
-                                push_toast(
-                                    &toasts,
-                                    "An error occurred while closing the connection",
-                                    Severity::Error,
-                                );
-                            }
-                        };
                     }
                     Err(err) => {
-                        println!("Error opening connection to the server {:?}", err);
-                        push_toast(&toasts, "Failed to establish a connection", Severity::Error);
-                        return;
+                        eprintln!("Error sending the file over tcp: {:?}", err);
+                        push_toast(&toasts, &format!("{:#}", err), Severity::Error);
                     }
-                };
+                }
             },
             |_| Message::Tcp(TcpPageMessage::Sent),
         )
@@ -829,7 +1488,8 @@ impl State {
             .clone()
             .expect("Dest dir is none when trying to start tcp server");
 
-        let settings_pointer = self.commited_settings.clone();
+        let profiles = self.profiles.clone();
+        let pin = self.tcp.profile;
 
         let (task, handle) = Task::perform(
             async move {
@@ -861,288 +1521,267 @@ impl State {
 
                     let toasts = toasts.clone();
 
-                    let alg = match get_algorithm(&settings_pointer.read().unwrap()) {
-                        Ok(a) => Arc::new(a),
+                    let config = match profiles.read().unwrap().settings(pin).to_config() {
+                        Ok(config) => config,
                         Err(err) => {
-                            push_toast(&toasts, &format!("{}", err), Severity::Success);
-                            return;
+                            push_toast(&toasts, &format!("{}", err), Severity::Error);
+                            continue;
                         }
                     };
 
                     let dest_dir = dest_dir.clone();
 
                     tokio::spawn(async move {
-                        let mut socket = BufReader::new(socket);
-                        match socket.fill_buf().await {
-                            Ok(v) => v,
+                        let report = |message: &str| push_toast(&toasts, message, Severity::Info);
+
+                        match transfer::receive_file(socket, &config, &dest_dir, report).await {
+                            Ok(_) => push_toast(
+                                &toasts,
+                                "Successfully processed a file sent over tcp",
+                                Severity::Success,
+                            ),
                             Err(err) => {
-                                println!("Error filling buffer from tcp stream: {:?}", err);
-                                push_toast(
-                                    &toasts,
-                                    "An error occurred while fetching data",
-                                    Severity::Error,
-                                );
-                                return;
+                                eprintln!("Error receiving a file over tcp: {:?}", err);
+                                push_toast(&toasts, &format!("{:#}", err), Severity::Error);
                             }
-                        };
-
-                        let message = "Succesffully received data";
-                        println!("{}", message);
-                        push_toast(&toasts, &message, Severity::Success);
-
-                        let file_name_len = {
-                            let mut buf = socket.buffer();
-                            let file_name_len = match leb128::read::unsigned(&mut buf) {
-                                Ok(v) => v,
-                                Err(err) => {
-                                    eprintln!(
-                                    "Error occured while extracting len-prefix of file name: {:?}",
-                                    err
-                                );
-                                    push_toast(
-                                        &toasts,
-                                        "An error occurred while extracting data",
-                                        Severity::Error,
-                                    );
-                                    return;
-                                }
-                            };
-
-                            let bytes_to_consume = socket.buffer().len() - buf.len();
-                            socket.consume(bytes_to_consume);
+                        }
+                    });
+                }
+            },
+            |_| Message::Tick,
+        )
+        .abortable();
 
-                            file_name_len
-                        };
-                        // println!("Length-prefix: {:?}", file_name_len);
+        self.tcp.join_handle = Some(handle);
+        self.tcp.is_listening = true;
 
-                        let mut file_name_buf = vec![0u8; file_name_len.try_into().unwrap()];
-                        match socket.read_exact(&mut file_name_buf).await {
-                            Ok(v) => v,
-                            Err(err) => {
-                                eprintln!("Error occured while extracting file name {:?}", err);
-                                push_toast(
-                                    &toasts,
-                                    "An error occurred while extracting data",
-                                    Severity::Error,
-                                );
-                                return;
-                            }
-                        };
+        task
+    }
 
-                        let file_name = match std::str::from_utf8(&file_name_buf) {
-                            Ok(v) => v,
-                            Err(err) => {
-                                eprintln!("Error occured while extracting file name {:?}", err);
-                                push_toast(
-                                    &toasts,
-                                    "An error occurred while extracting data",
-                                    Severity::Error,
-                                );
-                                return;
-                            }
-                        };
-                        // println!("File name: {:}", file_name);
+    fn tcp_stop_listening(&mut self) -> Task<Message> {
+        if let Some(handle) = self.tcp.join_handle.take() {
+            handle.abort();
+            println!("Successfully stoped listening for tcp connections");
+        } else {
+            println!("There is no join handle in state")
+        }
 
-                        let _file_len = match socket.read_i64_le().await {
-                            Ok(v) => v,
-                            Err(err) => {
-                                eprintln!("Error occured while extracting file length {:?}", err);
-                                push_toast(
-                                    &toasts,
-                                    "An error occurred while extracting data",
-                                    Severity::Error,
-                                );
-                                return;
-                            }
-                        };
-                        // println!("Content lenght: {:}", file_len);
+        self.tcp.is_listening = false;
+        Task::none()
+    }
 
-                        let hash_len = match socket.read_i32_le().await {
-                            Ok(v) => v,
-                            Err(err) => {
-                                eprintln!("Error occured while extracting hash length {:?}", err);
-                                push_toast(
-                                    &toasts,
-                                    "An error occurred while extracting data",
-                                    Severity::Error,
-                                );
-                                return;
-                            }
-                        };
-                        // println!("Hash lenght: {:}", hash_len);
+    /// The committed settings of the pinned profile, checked to build the
+    /// selected algorithm
+    fn committed_config(&self, pin: Option<u32>) -> anyhow::Result<Config> {
+        let config = self.profiles.read().unwrap().settings(pin).to_config()?;
+        get_algorithm(&config)?;
 
-                        let mut hash_buffer = vec![0_u8; hash_len.try_into().unwrap()];
-                        match socket.read_exact(&mut hash_buffer).await {
-                            Ok(v) => v,
-                            Err(err) => {
-                                eprintln!("Error occured while extracting hash {:?}", err);
-                                push_toast(
-                                    &toasts,
-                                    "An error occurred while extracting data",
-                                    Severity::Error,
-                                );
-                                return;
-                            }
-                        };
+        Ok(config)
+    }
 
-                        let mut encrypted_content = Vec::new();
-                        match socket.read_to_end(&mut encrypted_content).await {
-                            Ok(v) => v,
-                            Err(err) => {
-                                eprintln!(
-                                    "Error occured while extracting encrypted content {:?}",
-                                    err
-                                );
-                                push_toast(
-                                    &toasts,
-                                    "An error occurred while extracting data",
-                                    Severity::Error,
-                                );
-                                return;
-                            }
-                        };
+    /// Writes the plain file and reseals the keystore when their part of the
+    /// state changed. Nothing is saved while locked, so profiles in a plain
+    /// file from before the keystore aren't dropped before it holds them.
+    fn save(&mut self) {
+        let Some(keystore) = &self.keystore.keystore else {
+            return;
+        };
 
-                        match socket.shutdown().await {
-                            Ok(v) => v,
-                            Err(err) => {
-                                eprintln!(
-                                    "An error occurred while closing the connection: {:?}",
-                                    err
-                                );
-                                push_toast(
-                                    &toasts,
-                                    "An error occurred while closing the connection",
-                                    Severity::Error,
-                                );
-                            }
-                        };
+        let secrets = saved_state::format_keystore(self);
+        let sealed = (secrets != self.keystore.saved).then(|| keystore.seal(&secrets));
+        if let (Some(sealed), Some(path)) = (sealed, saved_state::keystore_path()) {
+            match saved_state::write(&path, &sealed) {
+                Ok(_) => self.keystore.saved = secrets,
+                Err(err) => push_toast(
+                    &self.toasts,
+                    &format!("Could not save the keystore: {}", err),
+                    Severity::Error,
+                ),
+            }
+        }
 
-                        drop(socket);
+        let text = saved_state::format(self);
+        if text == self.saved {
+            return;
+        }
+        self.saved = text;
+
+        if let Some(path) = saved_state::path() {
+            if let Err(err) = saved_state::write(&path, self.saved.as_bytes()) {
+                push_toast(
+                    &self.toasts,
+                    &format!("Could not save the settings: {}", err),
+                    Severity::Error,
+                );
+            }
+        }
+    }
 
-                        let recalculated_hash = hash::hash_data(&encrypted_content);
+    /// Runs the KDF on a blocking thread. Opens the keystore file, or
+    /// creates a keystore under the typed password on the first run.
+    fn unlock(&mut self) -> Task<Message> {
+        let password = self.keystore.password.clone();
+
+        let data = if self.keystore.exists {
+            let path = saved_state::keystore_path().expect("The keystore was found there");
+            match std::fs::read(&path) {
+                Ok(data) => Some(data),
+                Err(err) => {
+                    push_toast(
+                        &self.toasts,
+                        &format!("Could not read {}: {}", path.display(), err),
+                        Severity::Error,
+                    );
+                    return Task::none();
+                }
+            }
+        } else if password.is_empty() {
+            push_toast(
+                &self.toasts,
+                "The master password is empty",
+                Severity::Error,
+            );
+            return Task::none();
+        } else if password != self.keystore.confirm {
+            push_toast(&self.toasts, "The passwords don't match", Severity::Error);
+            return Task::none();
+        } else {
+            None
+        };
 
-                        // let hash = String::from_utf8_lossy(&hash_buffer);
-                        // println!("Recieved hash: --{:?}--", hash);
-                        //
-                        // let hash = String::from_utf8_lossy(&recalculated_hash);
-                        // println!("Recieved hash: --{:?}--", hash);
+        self.keystore.is_busy = true;
+        Task::perform(
+            tokio::task::spawn_blocking(move || match data {
+                Some(data) => Keystore::open(&password, &data)
+                    .map(|(keystore, contents)| (keystore, Some(contents)))
+                    .map_err(|err| format!("{}", err)),
+                None => Ok((Keystore::create(&password, DEFAULT_ITERATIONS), None)),
+            }),
+            |result| {
+                let result = result.map_err(|err| format!("{}", err)).and_then(|r| r);
+                Message::Keystore(KeystoreMessage::Unlocked(result))
+            },
+        )
+    }
 
-                        if hash_buffer.ne(&recalculated_hash) {
-                            eprintln!("Hash missmatch");
-                            push_toast(&toasts, "Hash missmatch", Severity::Error);
-                            return;
+    /// Loads the profiles from the keystore contents. A new keystore keeps
+    /// the profiles already loaded and is written on this update's save.
+    fn unlocked(&mut self, keystore: Keystore, contents: Option<Secret>) {
+        self.keystore.keystore = Some(keystore);
+        self.keystore.exists = true;
+        self.keystore.last_activity = Instant::now();
+
+        match contents {
+            Some(contents) => {
+                match saved_state::restore(self, &contents, Source::Keystore) {
+                    Ok(errors) => {
+                        for err in errors {
+                            push_toast(
+                                &self.toasts,
+                                &format!("Skipped a saved setting: {}", err),
+                                Severity::Error,
+                            );
                         }
+                    }
+                    Err(err) => push_toast(
+                        &self.toasts,
+                        &format!("Could not read the keystore: {}", err),
+                        Severity::Error,
+                    ),
+                }
+                self.keystore.saved = contents;
+            }
+            None => push_toast(&self.toasts, "Created the keystore", Severity::Success),
+        }
 
-                        let message = "Decrypting...";
-                        println!("{}", message);
-                        push_toast(&toasts, &message, Severity::Info);
+        self.profile_name = self.profiles.read().unwrap().active().name.clone();
+    }
 
-                        // println!(
-                        //     "Recived encrypted data:\n {:?}",
-                        //     String::from_utf8_lossy(&encrypted_content)
-                        // );
+    /// Saves, then drops the key and everything it unlocked. The FSW and TCP
+    /// tasks are stopped first, they read the profiles.
+    fn lock(&mut self) -> Task<Message> {
+        self.save();
 
-                        let decrypted_file_content = match alg.decrypt(&encrypted_content) {
-                            Ok(v) => v,
-                            Err(err) => {
-                                eprintln!("Error decrypting file content: {:?}", err);
-                                push_toast(&toasts, "Error", Severity::Error);
-                                return;
-                            }
-                        };
+        let mut tasks = Vec::new();
+        if self.fsw.watcher.is_some() {
+            tasks.push(self.turn_off_fsw());
+            self.fsw.watcher = None;
+        }
+        if self.tcp.is_listening {
+            tasks.push(self.tcp_stop_listening());
+        }
 
-                        println!(
-                            "Recieved: {}",
-                            String::from_utf8_lossy(&decrypted_file_content)
-                        );
+        self.keystore.keystore = None;
+        self.keystore.saved = Secret::default();
+        self.keystore.clear_passwords();
 
-                        push_toast(
-                            &toasts,
-                            "Successfully recieved a file over the tcp",
-                            Severity::Success,
-                        );
+        self.profiles = Arc::default();
+        self.settings = SettingsState::default();
+        self.profile_name = String::new();
+        self.simulator = SimulatorState::default();
+        self.fsw.profile = None;
+        self.manual.profile = None;
+        self.tcp.profile = None;
 
-                        // println!(
-                        //     "Decrypted data:\n {:?}",
-                        //     String::from_utf8_lossy(&decrypted_file_content)
-                        // );
-
-                        let new_file_path = match get_new_file_path2(
-                            file_name,
-                            &dest_dir,
-                            Operation::Decrypt,
-                        )
-                        .await
-                        {
-                            Ok(val) => val,
-                            Err(_) => {
-                                push_toast(
-                                    &toasts,
-                                    "Couldn't find available name for the file",
-                                    Severity::Error,
-                                );
-                                return;
-                            }
-                        };
+        Task::batch(tasks)
+    }
 
-                        let mut new_file = match tokio::fs::File::create(new_file_path).await {
-                            Ok(val) => val,
-                            Err(_) => {
-                                push_toast(
-                                    &toasts,
-                                    "Couldn't create file to store result into",
-                                    Severity::Error,
-                                );
-                                return;
-                            }
-                        };
+    /// Checks the current password and derives the new key on a blocking
+    /// thread. The keystore is sealed under the new key once it's done.
+    fn change_master_password(&mut self) -> Task<Message> {
+        let Some(keystore) = self.keystore.keystore.clone() else {
+            return Task::none();
+        };
+        let current = self.keystore.current_password.clone();
+        let new = self.keystore.new_password.clone();
 
-                        match new_file.write_all(&decrypted_file_content).await {
-                            Ok(_) => (),
-                            Err(_) => {
-                                push_toast(
-                                    &toasts,
-                                    "Error while writing result to the file",
-                                    Severity::Error,
-                                );
-                                return;
-                            }
-                        };
+        if new.is_empty() {
+            push_toast(
+                &self.toasts,
+                "The new master password is empty",
+                Severity::Error,
+            );
+            return Task::none();
+        }
+        if new != self.keystore.new_confirm {
+            push_toast(
+                &self.toasts,
+                "The new passwords don't match",
+                Severity::Error,
+            );
+            return Task::none();
+        }
 
-                        push_toast(
-                            &toasts,
-                            "Successfully processed a file sent over tcp",
-                            Severity::Success,
-                        );
-                    });
+        self.keystore.is_busy = true;
+        Task::perform(
+            tokio::task::spawn_blocking(move || {
+                if !keystore.matches(&current) {
+                    return Err(String::from("The current master password is wrong"));
                 }
+
+                Ok(Keystore::create(&new, DEFAULT_ITERATIONS))
+            }),
+            |result| {
+                let result = result.map_err(|err| format!("{}", err)).and_then(|r| r);
+                Message::Keystore(KeystoreMessage::PasswordReplaced(result))
             },
-            |_| Message::Tick,
         )
-        .abortable();
-
-        self.tcp.join_handle = Some(handle);
-        self.tcp.is_listening = true;
-
-        task
     }
 
-    fn tcp_stop_listening(&mut self) -> Task<Message> {
-        if let Some(handle) = self.tcp.join_handle.take() {
-            handle.abort();
-            println!("Successfully stoped listening for tcp connections");
-        } else {
-            println!("There is no join handle in state")
-        }
+    /// Makes the profile at `index` active and loads it into the settings
+    /// form, dropping uncommitted changes
+    fn activate_profile(&mut self, index: usize) {
+        let mut profiles = self.profiles.write().unwrap();
+        profiles.active = index;
 
-        self.tcp.is_listening = false;
-        Task::none()
+        self.settings = profiles.active().settings.clone();
+        self.profile_name = profiles.active().name.clone();
     }
 
     fn commit_settings(&self) {
-        match self.commited_settings.write() {
+        match self.profiles.write() {
             Ok(mut write_handle) => {
-                *write_handle = self.settings.clone();
+                write_handle.active_mut().settings = self.settings.clone();
             }
             Err(err) => {
                 push_toast(&self.toasts, &format!("{}", err), Severity::Error);
@@ -1,8 +1,12 @@
+use crate::utils::documents_dir;
 use iced::task::Handle;
 use std::path::PathBuf;
 
 pub struct TcpState {
     pub mode: TcpMode,
+    /// Profile to use instead of the active one, read when sending or
+    /// listening starts
+    pub profile: Option<u32>,
     //------------------------------------------
     pub file: Option<PathBuf>,
     pub reciever_adress: Option<String>,
@@ -25,27 +29,16 @@ pub enum TcpMode {
 
 impl Default for TcpState {
     fn default() -> Self {
-        let mut base = PathBuf::new();
-        base.push(r"C:\");
-        base.push("Users");
-        base.push("Aleksandar");
-        base.push("Documents");
-
-        let mut file = base.clone();
-        file.push("fsw_1source");
-        file.push("New Text Document.txt");
-
-        let mut dir = base.clone();
-        dir.push("fsw_2dest");
-
+        // Ports below 1024 need root on Unix
         Self {
             mode: Default::default(),
-            file: Some(file),
+            profile: Default::default(),
+            file: None,
             reciever_adress: Some("127.0.0.1".to_owned()),
-            reciever_port: Some(80),
+            reciever_port: Some(8080),
             is_sending: Default::default(),
-            dir_to_store_files: Some(dir),
-            my_port: Some(80),
+            dir_to_store_files: Some(dirs::download_dir().unwrap_or_else(documents_dir)),
+            my_port: Some(8080),
             is_listening: Default::default(),
             join_handle: Default::default(),
         }
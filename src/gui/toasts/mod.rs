@@ -57,7 +57,7 @@ pub fn push_toast(toasts: &Arc<RwLock<Vec<Toast>>>, message: &str, severity: Sev
     });
 }
 
-pub fn toasts_widget(state: &State) -> Element<Message> {
+pub fn toasts_widget(state: &State) -> Element<'_, Message> {
     let toasts: Vec<Element<Message>> = state
         .toasts
         .read()
@@ -1,4 +0,0 @@
-mod s_boxes;
-mod tiger;
-
-pub use tiger::hash_data;
@@ -1,15 +1,36 @@
-#![allow(clippy::needless_return)]
+#![allow(
+    clippy::needless_return,
+    clippy::upper_case_acronyms,
+    clippy::module_inception,
+    clippy::enum_variant_names
+)]
 
-mod algorithms;
+mod cli;
 mod gui;
-mod hash;
 mod utils;
+mod watcher;
 
 use gui::state::State;
 use iced::{window, Size};
+use std::process::ExitCode;
 
 #[tokio::main]
-async fn main() -> std::result::Result<(), iced::Error> {
+async fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        return cli::run(args).await;
+    }
+
+    match run_gui() {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run_gui() -> std::result::Result<(), iced::Error> {
     let icon = window::icon::from_file_data(include_bytes!("../assets/icon.png"), None).ok();
 
     let app = iced::application("ZI", State::update, State::view)
@@ -23,7 +44,7 @@ async fn main() -> std::result::Result<(), iced::Error> {
         .centered()
         .subscription(State::subscription);
 
-    app.run()
+    app.run_with(|| (State::load(), iced::Task::none()))
 }
 
 // Theme::TokyoNightStorm
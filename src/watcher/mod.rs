@@ -0,0 +1,77 @@
+//! Watching a directory for new files, shared by the FS Watcher page and
+//! the CLI.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use notify::{recommended_watcher, Event, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc::Receiver;
+
+/// Writing results into the watched directory would process them again
+pub fn check_dirs(dir_to_watch: &Path, dest_dir: &Path) -> anyhow::Result<()> {
+    if match (dir_to_watch.canonicalize(), dest_dir.canonicalize()) {
+        (Ok(canonical_path1), Ok(canonical_path2)) => canonical_path1 == canonical_path2,
+        _ => false,
+    } {
+        return Err(anyhow!(
+            "Source and destination directory are the same. This would create an infinite loop."
+        ));
+    }
+
+    Ok(())
+}
+
+/// Files created directly in a watched directory. Ends once the watcher it
+/// came with is dropped.
+pub struct CreatedFiles {
+    events: Receiver<Event>,
+}
+
+impl CreatedFiles {
+    /// Waits for the next new file, giving whoever creates it a moment to
+    /// let go of it
+    pub async fn next(&mut self) -> Option<PathBuf> {
+        loop {
+            let event = self.events.recv().await?;
+            if !matches!(event.kind, notify::EventKind::Create(_)) {
+                continue;
+            }
+
+            let Some(file_path) = event.paths.first().cloned() else {
+                continue;
+            };
+
+            // "Waiting" for file to become ready
+            for _ in 0..5 {
+                match tokio::fs::File::open(&file_path).await {
+                    Ok(_) => break,
+                    Err(_) => {
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                    }
+                }
+            }
+
+            return Some(file_path);
+        }
+    }
+}
+
+pub fn watch(dir_to_watch: &Path) -> anyhow::Result<(RecommendedWatcher, CreatedFiles)> {
+    let (event_tx, event_rx) = tokio::sync::mpsc::channel(10);
+
+    let mut watcher = recommended_watcher(move |res| match res {
+        // Fails only once nobody listens anymore
+        Ok(event) => {
+            let _ = event_tx.blocking_send(event);
+        }
+        Err(e) => eprintln!("Event handler recieved error: {:?}", e),
+    })
+    .context("Couldn't create watcher")?;
+
+    watcher
+        .watch(dir_to_watch, RecursiveMode::NonRecursive)
+        .context("Couldn't start watcher")?;
+
+    Ok((watcher, CreatedFiles { events: event_rx }))
+}
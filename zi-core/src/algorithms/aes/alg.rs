@@ -0,0 +1,403 @@
+use std::fmt::Display;
+
+use zeroize::Zeroize;
+
+use crate::algorithms::BlockCipher;
+use crate::config::AesConfig;
+
+pub const BLOCK_SIZE: usize = 16;
+
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum AesKeySize {
+    #[default]
+    Aes128,
+    Aes192,
+    Aes256,
+}
+
+impl AesKeySize {
+    pub fn bytes(&self) -> usize {
+        match self {
+            AesKeySize::Aes128 => 16,
+            AesKeySize::Aes192 => 24,
+            AesKeySize::Aes256 => 32,
+        }
+    }
+
+    pub fn from_bytes(bytes: usize) -> Option<AesKeySize> {
+        match bytes {
+            16 => Some(AesKeySize::Aes128),
+            24 => Some(AesKeySize::Aes192),
+            32 => Some(AesKeySize::Aes256),
+            _ => None,
+        }
+    }
+
+    fn rounds(&self) -> usize {
+        match self {
+            AesKeySize::Aes128 => 10,
+            AesKeySize::Aes192 => 12,
+            AesKeySize::Aes256 => 14,
+        }
+    }
+}
+
+impl Display for AesKeySize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                AesKeySize::Aes128 => "AES-128",
+                AesKeySize::Aes192 => "AES-192",
+                AesKeySize::Aes256 => "AES-256",
+            }
+        )
+    }
+}
+
+/// AES block primitive (FIPS-197), the mode of operation comes from
+/// [`crate::algorithms::modes`].
+pub struct Aes {
+    round_keys: Vec<[u8; BLOCK_SIZE]>,
+}
+
+impl Aes {
+    /// Pads or cuts the typed key to the size in `config`
+    pub fn from_config(config: &AesConfig) -> Aes {
+        Aes::new(&fix_key(config.key.as_bytes(), config.key_size))
+    }
+
+    /// `key` has to be 16, 24 or 32 bytes long
+    pub fn new(key: &[u8]) -> Aes {
+        let key_size = AesKeySize::from_bytes(key.len()).expect("Invalid AES key length");
+
+        Aes {
+            round_keys: expand_key(key, key_size),
+        }
+    }
+
+    pub fn encrypt_block(&self, block: &mut [u8; BLOCK_SIZE]) {
+        let rounds = self.round_keys.len() - 1;
+
+        add_round_key(block, &self.round_keys[0]);
+
+        for round in 1..rounds {
+            sub_bytes(block);
+            shift_rows(block);
+            mix_columns(block);
+            add_round_key(block, &self.round_keys[round]);
+        }
+
+        sub_bytes(block);
+        shift_rows(block);
+        add_round_key(block, &self.round_keys[rounds]);
+    }
+
+    pub fn decrypt_block(&self, block: &mut [u8; BLOCK_SIZE]) {
+        let rounds = self.round_keys.len() - 1;
+
+        add_round_key(block, &self.round_keys[rounds]);
+
+        for round in (1..rounds).rev() {
+            inv_shift_rows(block);
+            inv_sub_bytes(block);
+            add_round_key(block, &self.round_keys[round]);
+            inv_mix_columns(block);
+        }
+
+        inv_shift_rows(block);
+        inv_sub_bytes(block);
+        add_round_key(block, &self.round_keys[0]);
+    }
+}
+
+impl Drop for Aes {
+    fn drop(&mut self) {
+        self.round_keys.zeroize();
+    }
+}
+
+impl BlockCipher for Aes {
+    fn block_size(&self) -> usize {
+        BLOCK_SIZE
+    }
+
+    fn encrypt_block(&self, block: &mut [u8]) {
+        let block: &mut [u8; BLOCK_SIZE] = block.try_into().expect("AES block must be 16 bytes");
+        Aes::encrypt_block(self, block);
+    }
+
+    fn decrypt_block(&self, block: &mut [u8]) {
+        let block: &mut [u8; BLOCK_SIZE] = block.try_into().expect("AES block must be 16 bytes");
+        Aes::decrypt_block(self, block);
+    }
+}
+
+fn fix_key(key: &[u8], key_size: AesKeySize) -> Vec<u8> {
+    let mut out = vec![0u8; key_size.bytes()];
+
+    for (i, &val) in key.iter().take(key_size.bytes()).enumerate() {
+        out[i] = val;
+    }
+
+    return out;
+}
+
+fn expand_key(key: &[u8], key_size: AesKeySize) -> Vec<[u8; BLOCK_SIZE]> {
+    let nk = key_size.bytes() / 4;
+    let rounds = key_size.rounds();
+    let total_words = 4 * (rounds + 1);
+
+    let mut words: Vec<[u8; 4]> = key
+        .chunks_exact(4)
+        .map(|word| word.try_into().unwrap())
+        .collect();
+
+    let mut rcon: u8 = 1;
+
+    for i in nk..total_words {
+        let mut temp = words[i - 1];
+
+        if i % nk == 0 {
+            temp.rotate_left(1);
+            temp = temp.map(|b| SBOX[b as usize]);
+            temp[0] ^= rcon;
+            rcon = xtime(rcon);
+        } else if nk > 6 && i % nk == 4 {
+            temp = temp.map(|b| SBOX[b as usize]);
+        }
+
+        let prev = words[i - nk];
+        words.push([
+            prev[0] ^ temp[0],
+            prev[1] ^ temp[1],
+            prev[2] ^ temp[2],
+            prev[3] ^ temp[3],
+        ]);
+    }
+
+    words
+        .chunks_exact(4)
+        .map(|round| {
+            let mut key = [0u8; BLOCK_SIZE];
+            for (i, word) in round.iter().enumerate() {
+                key[4 * i..4 * i + 4].copy_from_slice(word);
+            }
+            key
+        })
+        .collect()
+}
+
+// The state is kept column-major, as in FIPS-197: byte `4 * c + r` is row r of column c
+
+fn add_round_key(state: &mut [u8; BLOCK_SIZE], round_key: &[u8; BLOCK_SIZE]) {
+    for (s, k) in state.iter_mut().zip(round_key) {
+        *s ^= k;
+    }
+}
+
+fn sub_bytes(state: &mut [u8; BLOCK_SIZE]) {
+    for b in state.iter_mut() {
+        *b = SBOX[*b as usize];
+    }
+}
+
+fn inv_sub_bytes(state: &mut [u8; BLOCK_SIZE]) {
+    for b in state.iter_mut() {
+        *b = INV_SBOX[*b as usize];
+    }
+}
+
+fn shift_rows(state: &mut [u8; BLOCK_SIZE]) {
+    let copy = *state;
+    for c in 0..4 {
+        for r in 1..4 {
+            state[4 * c + r] = copy[4 * ((c + r) % 4) + r];
+        }
+    }
+}
+
+fn inv_shift_rows(state: &mut [u8; BLOCK_SIZE]) {
+    let copy = *state;
+    for c in 0..4 {
+        for r in 1..4 {
+            state[4 * ((c + r) % 4) + r] = copy[4 * c + r];
+        }
+    }
+}
+
+fn mix_columns(state: &mut [u8; BLOCK_SIZE]) {
+    for column in state.chunks_exact_mut(4) {
+        let [a0, a1, a2, a3] = [column[0], column[1], column[2], column[3]];
+
+        column[0] = mul(a0, 2) ^ mul(a1, 3) ^ a2 ^ a3;
+        column[1] = a0 ^ mul(a1, 2) ^ mul(a2, 3) ^ a3;
+        column[2] = a0 ^ a1 ^ mul(a2, 2) ^ mul(a3, 3);
+        column[3] = mul(a0, 3) ^ a1 ^ a2 ^ mul(a3, 2);
+    }
+}
+
+fn inv_mix_columns(state: &mut [u8; BLOCK_SIZE]) {
+    for column in state.chunks_exact_mut(4) {
+        let [a0, a1, a2, a3] = [column[0], column[1], column[2], column[3]];
+
+        column[0] = mul(a0, 14) ^ mul(a1, 11) ^ mul(a2, 13) ^ mul(a3, 9);
+        column[1] = mul(a0, 9) ^ mul(a1, 14) ^ mul(a2, 11) ^ mul(a3, 13);
+        column[2] = mul(a0, 13) ^ mul(a1, 9) ^ mul(a2, 14) ^ mul(a3, 11);
+        column[3] = mul(a0, 11) ^ mul(a1, 13) ^ mul(a2, 9) ^ mul(a3, 14);
+    }
+}
+
+fn xtime(b: u8) -> u8 {
+    (b << 1) ^ if b & 0x80 != 0 { 0x1b } else { 0 }
+}
+
+/// Multiplication in GF(2^8)
+fn mul(mut a: u8, mut b: u8) -> u8 {
+    let mut res = 0;
+
+    while b != 0 {
+        if b & 1 != 0 {
+            res ^= a;
+        }
+        a = xtime(a);
+        b >>= 1;
+    }
+
+    res
+}
+
+#[rustfmt::skip]
+const SBOX: [u8; 256] = [
+    0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
+    0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
+    0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5, 0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15,
+    0x04, 0xc7, 0x23, 0xc3, 0x18, 0x96, 0x05, 0x9a, 0x07, 0x12, 0x80, 0xe2, 0xeb, 0x27, 0xb2, 0x75,
+    0x09, 0x83, 0x2c, 0x1a, 0x1b, 0x6e, 0x5a, 0xa0, 0x52, 0x3b, 0xd6, 0xb3, 0x29, 0xe3, 0x2f, 0x84,
+    0x53, 0xd1, 0x00, 0xed, 0x20, 0xfc, 0xb1, 0x5b, 0x6a, 0xcb, 0xbe, 0x39, 0x4a, 0x4c, 0x58, 0xcf,
+    0xd0, 0xef, 0xaa, 0xfb, 0x43, 0x4d, 0x33, 0x85, 0x45, 0xf9, 0x02, 0x7f, 0x50, 0x3c, 0x9f, 0xa8,
+    0x51, 0xa3, 0x40, 0x8f, 0x92, 0x9d, 0x38, 0xf5, 0xbc, 0xb6, 0xda, 0x21, 0x10, 0xff, 0xf3, 0xd2,
+    0xcd, 0x0c, 0x13, 0xec, 0x5f, 0x97, 0x44, 0x17, 0xc4, 0xa7, 0x7e, 0x3d, 0x64, 0x5d, 0x19, 0x73,
+    0x60, 0x81, 0x4f, 0xdc, 0x22, 0x2a, 0x90, 0x88, 0x46, 0xee, 0xb8, 0x14, 0xde, 0x5e, 0x0b, 0xdb,
+    0xe0, 0x32, 0x3a, 0x0a, 0x49, 0x06, 0x24, 0x5c, 0xc2, 0xd3, 0xac, 0x62, 0x91, 0x95, 0xe4, 0x79,
+    0xe7, 0xc8, 0x37, 0x6d, 0x8d, 0xd5, 0x4e, 0xa9, 0x6c, 0x56, 0xf4, 0xea, 0x65, 0x7a, 0xae, 0x08,
+    0xba, 0x78, 0x25, 0x2e, 0x1c, 0xa6, 0xb4, 0xc6, 0xe8, 0xdd, 0x74, 0x1f, 0x4b, 0xbd, 0x8b, 0x8a,
+    0x70, 0x3e, 0xb5, 0x66, 0x48, 0x03, 0xf6, 0x0e, 0x61, 0x35, 0x57, 0xb9, 0x86, 0xc1, 0x1d, 0x9e,
+    0xe1, 0xf8, 0x98, 0x11, 0x69, 0xd9, 0x8e, 0x94, 0x9b, 0x1e, 0x87, 0xe9, 0xce, 0x55, 0x28, 0xdf,
+    0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16,
+];
+
+#[rustfmt::skip]
+const INV_SBOX: [u8; 256] = [
+    0x52, 0x09, 0x6a, 0xd5, 0x30, 0x36, 0xa5, 0x38, 0xbf, 0x40, 0xa3, 0x9e, 0x81, 0xf3, 0xd7, 0xfb,
+    0x7c, 0xe3, 0x39, 0x82, 0x9b, 0x2f, 0xff, 0x87, 0x34, 0x8e, 0x43, 0x44, 0xc4, 0xde, 0xe9, 0xcb,
+    0x54, 0x7b, 0x94, 0x32, 0xa6, 0xc2, 0x23, 0x3d, 0xee, 0x4c, 0x95, 0x0b, 0x42, 0xfa, 0xc3, 0x4e,
+    0x08, 0x2e, 0xa1, 0x66, 0x28, 0xd9, 0x24, 0xb2, 0x76, 0x5b, 0xa2, 0x49, 0x6d, 0x8b, 0xd1, 0x25,
+    0x72, 0xf8, 0xf6, 0x64, 0x86, 0x68, 0x98, 0x16, 0xd4, 0xa4, 0x5c, 0xcc, 0x5d, 0x65, 0xb6, 0x92,
+    0x6c, 0x70, 0x48, 0x50, 0xfd, 0xed, 0xb9, 0xda, 0x5e, 0x15, 0x46, 0x57, 0xa7, 0x8d, 0x9d, 0x84,
+    0x90, 0xd8, 0xab, 0x00, 0x8c, 0xbc, 0xd3, 0x0a, 0xf7, 0xe4, 0x58, 0x05, 0xb8, 0xb3, 0x45, 0x06,
+    0xd0, 0x2c, 0x1e, 0x8f, 0xca, 0x3f, 0x0f, 0x02, 0xc1, 0xaf, 0xbd, 0x03, 0x01, 0x13, 0x8a, 0x6b,
+    0x3a, 0x91, 0x11, 0x41, 0x4f, 0x67, 0xdc, 0xea, 0x97, 0xf2, 0xcf, 0xce, 0xf0, 0xb4, 0xe6, 0x73,
+    0x96, 0xac, 0x74, 0x22, 0xe7, 0xad, 0x35, 0x85, 0xe2, 0xf9, 0x37, 0xe8, 0x1c, 0x75, 0xdf, 0x6e,
+    0x47, 0xf1, 0x1a, 0x71, 0x1d, 0x29, 0xc5, 0x89, 0x6f, 0xb7, 0x62, 0x0e, 0xaa, 0x18, 0xbe, 0x1b,
+    0xfc, 0x56, 0x3e, 0x4b, 0xc6, 0xd2, 0x79, 0x20, 0x9a, 0xdb, 0xc0, 0xfe, 0x78, 0xcd, 0x5a, 0xf4,
+    0x1f, 0xdd, 0xa8, 0x33, 0x88, 0x07, 0xc7, 0x31, 0xb1, 0x12, 0x10, 0x59, 0x27, 0x80, 0xec, 0x5f,
+    0x60, 0x51, 0x7f, 0xa9, 0x19, 0xb5, 0x4a, 0x0d, 0x2d, 0xe5, 0x7a, 0x9f, 0x93, 0xc9, 0x9c, 0xef,
+    0xa0, 0xe0, 0x3b, 0x4d, 0xae, 0x2a, 0xf5, 0xb0, 0xc8, 0xeb, 0xbb, 0x3c, 0x83, 0x53, 0x99, 0x61,
+    0x17, 0x2b, 0x04, 0x7e, 0xba, 0x77, 0xd6, 0x26, 0xe1, 0x69, 0x14, 0x63, 0x55, 0x21, 0x0c, 0x7d,
+];
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{Aes, AesKeySize, BLOCK_SIZE};
+    use crate::algorithms::modes::alg::BlockModeCipher;
+    use crate::algorithms::Algorithm;
+    use crate::config::AesConfig;
+
+    fn from_hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn check_vector(key: &str, plaintext: &str, ciphertext: &str) {
+        let aes = Aes::new(&from_hex(key));
+
+        let mut block: [u8; BLOCK_SIZE] = from_hex(plaintext).try_into().unwrap();
+        aes.encrypt_block(&mut block);
+        assert_eq!(block.to_vec(), from_hex(ciphertext));
+
+        aes.decrypt_block(&mut block);
+        assert_eq!(block.to_vec(), from_hex(plaintext));
+    }
+
+    // FIPS-197, Appendix B
+    #[test]
+    fn fips_197_cipher_example() {
+        check_vector(
+            "2b7e151628aed2a6abf7158809cf4f3c",
+            "3243f6a8885a308d313198a2e0370734",
+            "3925841d02dc09fbdc118597196a0b32",
+        );
+    }
+
+    // FIPS-197, Appendix C.1
+    #[test]
+    fn fips_197_aes_128() {
+        check_vector(
+            "000102030405060708090a0b0c0d0e0f",
+            "00112233445566778899aabbccddeeff",
+            "69c4e0d86a7b0430d8cdb78070b4c55a",
+        );
+    }
+
+    // FIPS-197, Appendix C.2
+    #[test]
+    fn fips_197_aes_192() {
+        check_vector(
+            "000102030405060708090a0b0c0d0e0f1011121314151617",
+            "00112233445566778899aabbccddeeff",
+            "dda97ca4864cdfe06eaf70a0ec0d7191",
+        );
+    }
+
+    // FIPS-197, Appendix C.3
+    #[test]
+    fn fips_197_aes_256() {
+        check_vector(
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+            "00112233445566778899aabbccddeeff",
+            "8ea2b7ca516745bfeafc49904b496089",
+        );
+    }
+
+    #[test]
+    fn aes_full() {
+        for key_size in [AesKeySize::Aes128, AesKeySize::Aes192, AesKeySize::Aes256] {
+            let config = AesConfig {
+                key: "SecretKey".to_owned().into(),
+                key_size,
+                ..Default::default()
+            };
+            let alg = BlockModeCipher::try_new(
+                Arc::new(Aes::from_config(&config)),
+                config.mode,
+                &config.iv,
+            )
+            .unwrap();
+
+            for data in ["".as_bytes(), "Hellouw there".as_bytes(), &[7u8; 32]] {
+                let encrypted = alg.encrypt(data).unwrap();
+                assert_eq!(encrypted.len() % BLOCK_SIZE, 0);
+                assert!(encrypted.len() > data.len());
+
+                let decrypted = alg.decrypt(&encrypted).unwrap();
+                assert_eq!(data, decrypted);
+            }
+        }
+    }
+}
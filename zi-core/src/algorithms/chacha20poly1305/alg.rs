@@ -0,0 +1,580 @@
+use anyhow::anyhow;
+use zeroize::Zeroize;
+
+use crate::algorithms::{Algorithm, AuthenticationError, StreamProcessor, StreamingAlgorithm};
+use crate::config::ChaCha20Poly1305Config;
+
+pub const KEY_SIZE: usize = 32;
+pub const NONCE_SIZE: usize = 12;
+pub const TAG_SIZE: usize = 16;
+
+const CHACHA_BLOCK_SIZE: usize = 64;
+const CONSTANTS: [u32; 4] = [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574];
+
+/// ChaCha20-Poly1305 AEAD (RFC 8439).
+///
+/// Every encryption picks a random nonce, the output is
+/// `nonce | ciphertext | tag`.
+pub struct ChaCha20Poly1305 {
+    key: [u32; 8],
+}
+
+impl ChaCha20Poly1305 {
+    /// Uses the typed key as it is, zero-padded
+    pub fn try_new(config: &ChaCha20Poly1305Config) -> anyhow::Result<ChaCha20Poly1305> {
+        let key = config.key.as_bytes();
+
+        if key.len() > KEY_SIZE {
+            return Err(anyhow!("Key must be at most {} bytes long", KEY_SIZE));
+        }
+
+        let mut fixed = [0u8; KEY_SIZE];
+        fixed[..key.len()].copy_from_slice(key);
+
+        return Ok(ChaCha20Poly1305::new(&fixed));
+    }
+
+    pub fn new(key: &[u8; KEY_SIZE]) -> ChaCha20Poly1305 {
+        let mut words = [0u32; 8];
+        for (word, chunk) in words.iter_mut().zip(key.chunks_exact(4)) {
+            *word = u32::from_le_bytes(chunk.try_into().unwrap());
+        }
+
+        ChaCha20Poly1305 { key: words }
+    }
+
+    /// Like [`Algorithm::encrypt`], also authenticating `aad`, which is
+    /// not part of the output
+    pub fn seal(&self, data: &[u8], aad: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_SIZE];
+        getrandom::getrandom(&mut nonce).expect("System random number generator is unavailable");
+
+        let mut sealer = self.sealer(nonce, aad);
+        let mut out = sealer.update(data)?;
+        out.extend(sealer.finalize()?);
+
+        return Ok(out);
+    }
+
+    /// Fails with [`AuthenticationError`] when `data` or `aad` were changed
+    pub fn open(&self, data: &[u8], aad: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let mut opener = self.opener(aad);
+        let mut out = opener.update(data)?;
+        out.extend(opener.finalize()?);
+
+        return Ok(out);
+    }
+
+    fn sealer(&self, nonce: [u8; NONCE_SIZE], aad: &[u8]) -> ChaChaEncryptor {
+        ChaChaEncryptor {
+            aead: AeadState::new(&self.key, &nonce, aad),
+            nonce: Some(nonce),
+        }
+    }
+
+    fn opener(&self, aad: &[u8]) -> ChaChaDecryptor {
+        ChaChaDecryptor {
+            key: self.key,
+            aad: aad.to_vec(),
+            aead: None,
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl Drop for ChaCha20Poly1305 {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+impl Algorithm for ChaCha20Poly1305 {
+    fn encrypt(&self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let mut encryptor = self.encryptor();
+        let mut out = encryptor.update(data)?;
+        out.extend(encryptor.finalize()?);
+
+        return Ok(out);
+    }
+
+    fn decrypt(&self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let mut decryptor = self.decryptor();
+        let mut out = decryptor.update(data)?;
+        out.extend(decryptor.finalize()?);
+
+        return Ok(out);
+    }
+}
+
+impl StreamingAlgorithm for ChaCha20Poly1305 {
+    fn encryptor(&self) -> Box<dyn StreamProcessor + Send> {
+        let mut nonce = [0u8; NONCE_SIZE];
+        getrandom::getrandom(&mut nonce).expect("System random number generator is unavailable");
+
+        Box::new(self.sealer(nonce, &[]))
+    }
+
+    fn decryptor(&self) -> Box<dyn StreamProcessor + Send> {
+        Box::new(self.opener(&[]))
+    }
+}
+
+/// Keystream and MAC state shared by both directions
+struct AeadState {
+    key: [u32; 8],
+    nonce: [u32; 3],
+    counter: u32,
+    keystream: [u8; CHACHA_BLOCK_SIZE],
+    keystream_used: usize,
+    mac: Poly1305,
+    aad_len: u64,
+    data_len: u64,
+}
+
+impl AeadState {
+    fn new(key: &[u32; 8], nonce: &[u8; NONCE_SIZE], aad: &[u8]) -> AeadState {
+        let mut nonce_words = [0u32; 3];
+        for (word, chunk) in nonce_words.iter_mut().zip(nonce.chunks_exact(4)) {
+            *word = u32::from_le_bytes(chunk.try_into().unwrap());
+        }
+
+        let otk = chacha20_block(key, 0, &nonce_words);
+        let mut mac = Poly1305::new(otk[..32].try_into().unwrap());
+        mac.update(aad);
+        mac.pad();
+
+        AeadState {
+            key: *key,
+            nonce: nonce_words,
+            counter: 1,
+            keystream: [0; CHACHA_BLOCK_SIZE],
+            keystream_used: CHACHA_BLOCK_SIZE,
+            mac,
+            aad_len: aad.len() as u64,
+            data_len: 0,
+        }
+    }
+
+    fn apply_keystream(&mut self, data: &mut [u8]) -> anyhow::Result<()> {
+        for byte in data.iter_mut() {
+            if self.keystream_used == CHACHA_BLOCK_SIZE {
+                if self.counter == 0 {
+                    return Err(anyhow!("Message is too long for ChaCha20"));
+                }
+
+                self.keystream = chacha20_block(&self.key, self.counter, &self.nonce);
+                self.keystream_used = 0;
+                self.counter = self.counter.wrapping_add(1);
+            }
+
+            *byte ^= self.keystream[self.keystream_used];
+            self.keystream_used += 1;
+        }
+
+        Ok(())
+    }
+
+    fn tag(&mut self) -> [u8; TAG_SIZE] {
+        self.mac.pad();
+        self.mac.update(&self.aad_len.to_le_bytes());
+        self.mac.update(&self.data_len.to_le_bytes());
+
+        self.mac.finish()
+    }
+}
+
+struct ChaChaEncryptor {
+    aead: AeadState,
+    /// Written in front of the first output chunk
+    nonce: Option<[u8; NONCE_SIZE]>,
+}
+
+impl StreamProcessor for ChaChaEncryptor {
+    fn update(&mut self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let mut out = self.nonce.take().map(|n| n.to_vec()).unwrap_or_default();
+        let start = out.len();
+
+        out.extend_from_slice(data);
+        self.aead.apply_keystream(&mut out[start..])?;
+        self.aead.mac.update(&out[start..]);
+        self.aead.data_len += data.len() as u64;
+
+        return Ok(out);
+    }
+
+    fn finalize(&mut self) -> anyhow::Result<Vec<u8>> {
+        let mut out = self.nonce.take().map(|n| n.to_vec()).unwrap_or_default();
+        out.extend_from_slice(&self.aead.tag());
+
+        return Ok(out);
+    }
+}
+
+/// Plaintext is released before the tag is checked, callers must discard
+/// their output if `finalize` fails.
+struct ChaChaDecryptor {
+    key: [u32; 8],
+    aad: Vec<u8>,
+    aead: Option<AeadState>,
+    /// Bytes that may still turn out to be the nonce or the tag
+    pending: Vec<u8>,
+}
+
+impl StreamProcessor for ChaChaDecryptor {
+    fn update(&mut self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        self.pending.extend_from_slice(data);
+
+        if self.aead.is_none() {
+            if self.pending.len() < NONCE_SIZE {
+                return Ok(Vec::new());
+            }
+
+            let nonce: [u8; NONCE_SIZE] = self.pending[..NONCE_SIZE].try_into().unwrap();
+            self.aead = Some(AeadState::new(&self.key, &nonce, &self.aad));
+            self.pending.drain(..NONCE_SIZE);
+        }
+
+        if self.pending.len() <= TAG_SIZE {
+            return Ok(Vec::new());
+        }
+
+        let ready = self.pending.len() - TAG_SIZE;
+        let mut out: Vec<u8> = self.pending.drain(..ready).collect();
+
+        let aead = self.aead.as_mut().unwrap();
+        aead.mac.update(&out);
+        aead.data_len += out.len() as u64;
+        aead.apply_keystream(&mut out)?;
+
+        return Ok(out);
+    }
+
+    fn finalize(&mut self) -> anyhow::Result<Vec<u8>> {
+        let aead = match self.aead.as_mut() {
+            Some(aead) if self.pending.len() == TAG_SIZE => aead,
+            _ => return Err(anyhow!("Data is too short for ChaCha20-Poly1305")),
+        };
+
+        if !constant_time_eq(&aead.tag(), &self.pending) {
+            return Err(AuthenticationError.into());
+        }
+
+        self.pending.clear();
+
+        return Ok(Vec::new());
+    }
+}
+
+fn quarter_round(state: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(12);
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(8);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(7);
+}
+
+fn chacha20_block(key: &[u32; 8], counter: u32, nonce: &[u32; 3]) -> [u8; CHACHA_BLOCK_SIZE] {
+    let mut initial = [0u32; 16];
+    initial[..4].copy_from_slice(&CONSTANTS);
+    initial[4..12].copy_from_slice(key);
+    initial[12] = counter;
+    initial[13..].copy_from_slice(nonce);
+
+    let mut state = initial;
+    for _ in 0..10 {
+        quarter_round(&mut state, 0, 4, 8, 12);
+        quarter_round(&mut state, 1, 5, 9, 13);
+        quarter_round(&mut state, 2, 6, 10, 14);
+        quarter_round(&mut state, 3, 7, 11, 15);
+        quarter_round(&mut state, 0, 5, 10, 15);
+        quarter_round(&mut state, 1, 6, 11, 12);
+        quarter_round(&mut state, 2, 7, 8, 13);
+        quarter_round(&mut state, 3, 4, 9, 14);
+    }
+
+    let mut out = [0u8; CHACHA_BLOCK_SIZE];
+    for (i, chunk) in out.chunks_exact_mut(4).enumerate() {
+        chunk.copy_from_slice(&state[i].wrapping_add(initial[i]).to_le_bytes());
+    }
+
+    out
+}
+
+/// Poly1305 over 26-bit limbs
+struct Poly1305 {
+    r: [u32; 5],
+    h: [u32; 5],
+    pad: [u32; 4],
+    buffer: [u8; 16],
+    buffered: usize,
+}
+
+impl Poly1305 {
+    fn new(key: &[u8; 32]) -> Poly1305 {
+        let le = |i: usize| u32::from_le_bytes(key[i..i + 4].try_into().unwrap());
+
+        Poly1305 {
+            r: [
+                le(0) & 0x3ffffff,
+                (le(3) >> 2) & 0x3ffff03,
+                (le(6) >> 4) & 0x3ffc0ff,
+                (le(9) >> 6) & 0x3f03fff,
+                (le(12) >> 8) & 0x00fffff,
+            ],
+            h: [0; 5],
+            pad: [le(16), le(20), le(24), le(28)],
+            buffer: [0; 16],
+            buffered: 0,
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        if self.buffered > 0 {
+            let take = (16 - self.buffered).min(data.len());
+            self.buffer[self.buffered..self.buffered + take].copy_from_slice(&data[..take]);
+            self.buffered += take;
+            data = &data[take..];
+
+            if self.buffered < 16 {
+                return;
+            }
+
+            let block = self.buffer;
+            self.block(&block, 1 << 24);
+            self.buffered = 0;
+        }
+
+        let mut blocks = data.chunks_exact(16);
+        for block in &mut blocks {
+            self.block(block.try_into().unwrap(), 1 << 24);
+        }
+
+        let rest = blocks.remainder();
+        self.buffer[..rest.len()].copy_from_slice(rest);
+        self.buffered = rest.len();
+    }
+
+    /// Zero-pads the input so far to a multiple of 16 bytes
+    fn pad(&mut self) {
+        if self.buffered > 0 {
+            let zeros = [0u8; 16];
+            self.update(&zeros[..16 - self.buffered]);
+        }
+    }
+
+    fn block(&mut self, block: &[u8; 16], hibit: u32) {
+        let le = |i: usize| u32::from_le_bytes(block[i..i + 4].try_into().unwrap());
+        let [r0, r1, r2, r3, r4] = self.r.map(|r| r as u64);
+        let (s1, s2, s3, s4) = (r1 * 5, r2 * 5, r3 * 5, r4 * 5);
+
+        let h = &mut self.h;
+        h[0] += le(0) & 0x3ffffff;
+        h[1] += (le(3) >> 2) & 0x3ffffff;
+        h[2] += (le(6) >> 4) & 0x3ffffff;
+        h[3] += (le(9) >> 6) & 0x3ffffff;
+        h[4] += (le(12) >> 8) | hibit;
+
+        let [h0, h1, h2, h3, h4] = h.map(|h| h as u64);
+        let d0 = h0 * r0 + h1 * s4 + h2 * s3 + h3 * s2 + h4 * s1;
+        let mut d1 = h0 * r1 + h1 * r0 + h2 * s4 + h3 * s3 + h4 * s2;
+        let mut d2 = h0 * r2 + h1 * r1 + h2 * r0 + h3 * s4 + h4 * s3;
+        let mut d3 = h0 * r3 + h1 * r2 + h2 * r1 + h3 * r0 + h4 * s4;
+        let mut d4 = h0 * r4 + h1 * r3 + h2 * r2 + h3 * r1 + h4 * r0;
+
+        h[0] = (d0 & 0x3ffffff) as u32;
+        d1 += d0 >> 26;
+        h[1] = (d1 & 0x3ffffff) as u32;
+        d2 += d1 >> 26;
+        h[2] = (d2 & 0x3ffffff) as u32;
+        d3 += d2 >> 26;
+        h[3] = (d3 & 0x3ffffff) as u32;
+        d4 += d3 >> 26;
+        h[4] = (d4 & 0x3ffffff) as u32;
+        h[0] += (d4 >> 26) as u32 * 5;
+        h[1] += h[0] >> 26;
+        h[0] &= 0x3ffffff;
+    }
+
+    fn finish(&mut self) -> [u8; TAG_SIZE] {
+        if self.buffered > 0 {
+            let mut block = [0u8; 16];
+            block[..self.buffered].copy_from_slice(&self.buffer[..self.buffered]);
+            block[self.buffered] = 1;
+            self.block(&block, 0);
+            self.buffered = 0;
+        }
+
+        let mut h = self.h;
+        for i in 1..4 {
+            h[i + 1] += h[i] >> 26;
+            h[i] &= 0x3ffffff;
+        }
+        h[0] += (h[4] >> 26) * 5;
+        h[4] &= 0x3ffffff;
+        h[1] += h[0] >> 26;
+        h[0] &= 0x3ffffff;
+
+        // g = h + 5 - 2^130, taken instead of h when it doesn't underflow
+        let mut g = [0u32; 5];
+        let mut carry = 5;
+        for i in 0..4 {
+            g[i] = h[i] + carry;
+            carry = g[i] >> 26;
+            g[i] &= 0x3ffffff;
+        }
+        g[4] = (h[4] + carry).wrapping_sub(1 << 26);
+
+        let use_g = (g[4] >> 31).wrapping_sub(1);
+        for i in 0..5 {
+            h[i] = (h[i] & !use_g) | (g[i] & use_g);
+        }
+
+        let words = [
+            h[0] | (h[1] << 26),
+            (h[1] >> 6) | (h[2] << 20),
+            (h[2] >> 12) | (h[3] << 14),
+            (h[3] >> 18) | (h[4] << 8),
+        ];
+
+        let mut out = [0u8; TAG_SIZE];
+        let mut carry = 0u64;
+        for (i, chunk) in out.chunks_exact_mut(4).enumerate() {
+            let sum = words[i] as u64 + self.pad[i] as u64 + carry;
+            chunk.copy_from_slice(&(sum as u32).to_le_bytes());
+            carry = sum >> 32;
+        }
+
+        out
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{chacha20_block, ChaCha20Poly1305, Poly1305, NONCE_SIZE};
+    use crate::algorithms::{Algorithm, AuthenticationError, StreamProcessor, StreamingAlgorithm};
+    use crate::config::ChaCha20Poly1305Config;
+
+    const SUNSCREEN: &[u8] = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+
+    fn seq_key(start: u8) -> [u8; 32] {
+        std::array::from_fn(|i| start + i as u8)
+    }
+
+    fn hex(data: &str) -> Vec<u8> {
+        let data: String = data.split_whitespace().collect();
+        (0..data.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&data[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    // RFC 8439 2.3.2
+    #[test]
+    fn chacha20_block_function() {
+        let key = ChaCha20Poly1305::new(&seq_key(0)).key;
+        let block = chacha20_block(&key, 1, &[0x09000000, 0x4a000000, 0]);
+
+        assert_eq!(
+            block.to_vec(),
+            hex(
+                "10f1e7e4d13b5915500fdd1fa32071c4c7d1f4c733c068030422aa9ac3d46c4e
+                 d2826446079faa0914c2d705d98b02a2b5129cd1de164eb9cbd083e8a2503c4e"
+            )
+        );
+    }
+
+    // RFC 8439 2.5.2
+    #[test]
+    fn poly1305_mac() {
+        let key = hex("85d6be7857556d337f4452fe42d506a80103808afb0db2fd4abff6af4149f51b");
+        let mut mac = Poly1305::new(key[..].try_into().unwrap());
+        mac.update(b"Cryptographic Forum ");
+        mac.update(b"Research Group");
+
+        assert_eq!(
+            mac.finish().to_vec(),
+            hex("a8061dc1305136c6c22b8baf0c0127a9")
+        );
+    }
+
+    // RFC 8439 2.8.2
+    #[test]
+    fn aead_encryption() {
+        let alg = ChaCha20Poly1305::new(&seq_key(0x80));
+        let nonce: [u8; NONCE_SIZE] = hex("070000004041424344454647")[..].try_into().unwrap();
+        let aad = hex("50515253c0c1c2c3c4c5c6c7");
+
+        let mut sealer = alg.sealer(nonce, &aad);
+        let mut sealed = sealer.update(SUNSCREEN).unwrap();
+        sealed.extend(sealer.finalize().unwrap());
+
+        let expected = hex(
+            "d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d6
+             3dbea45e8ca9671282fafb69da92728b1a71de0a9e060b2905d6a5b67ecd3b36
+             92ddbd7f2d778b8c9803aee328091b58fab324e4fad675945585808b4831d7bc
+             3ff4def08e4b7a9de576d26586cec64b6116
+             1ae10b594f09e26a7e902ecbd0600691",
+        );
+        assert_eq!(&sealed[..NONCE_SIZE], &nonce);
+        assert_eq!(sealed[NONCE_SIZE..], expected);
+
+        let mut opener = alg.opener(&aad);
+        let mut opened = Vec::new();
+        for chunk in sealed.chunks(7) {
+            opened.extend(opener.update(chunk).unwrap());
+        }
+        opened.extend(opener.finalize().unwrap());
+        assert_eq!(opened, SUNSCREEN);
+    }
+
+    #[test]
+    fn streaming_round_trip() {
+        let alg = ChaCha20Poly1305::try_new(&ChaCha20Poly1305Config::default()).unwrap();
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 7) as u8).collect();
+
+        let mut encryptor = alg.encryptor();
+        let mut encrypted = Vec::new();
+        for chunk in data.chunks(33) {
+            encrypted.extend(encryptor.update(chunk).unwrap());
+        }
+        encrypted.extend(encryptor.finalize().unwrap());
+
+        assert_eq!(alg.decrypt(&encrypted).unwrap(), data);
+        assert_eq!(
+            alg.decrypt(&alg.encrypt(&[]).unwrap()).unwrap(),
+            Vec::<u8>::new()
+        );
+    }
+
+    #[test]
+    fn tampering_is_an_authentication_error() {
+        let alg = ChaCha20Poly1305::try_new(&ChaCha20Poly1305Config::default()).unwrap();
+        let encrypted = alg.encrypt(SUNSCREEN).unwrap();
+
+        let mut tampered = encrypted.clone();
+        tampered[NONCE_SIZE + 3] ^= 1;
+        let err = alg.decrypt(&tampered).unwrap_err();
+        assert!(err.downcast_ref::<AuthenticationError>().is_some());
+
+        let other = ChaCha20Poly1305::try_new(&ChaCha20Poly1305Config {
+            key: "OtherKey".to_owned().into(),
+        })
+        .unwrap();
+        let err = other.decrypt(&encrypted).unwrap_err();
+        assert!(err.downcast_ref::<AuthenticationError>().is_some());
+
+        let err = alg.decrypt(&encrypted[..20]).unwrap_err();
+        assert!(err.downcast_ref::<AuthenticationError>().is_none());
+    }
+}
@@ -0,0 +1,1011 @@
+use std::fmt::Display;
+
+use anyhow::anyhow;
+
+use crate::{
+    algorithms::{Algorithm, StreamProcessor, StreamingAlgorithm},
+    config::EnigmaConfig,
+};
+
+use super::{
+    catalogue, plugboard::Plugboard, reflector::Reflector, rotor::Rotor,
+    rotor_assembly::RotorAssembly, simulator::EnigmaSimulator, utils,
+};
+
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum EnigmaMachine {
+    /// Three rotors and a wide reflector
+    #[default]
+    M3,
+    /// Kriegsmarine four-rotor machine: a Greek wheel and a thin reflector
+    M4,
+    /// Not a historical machine: three rotors over all 256 byte values,
+    /// keyed by a passphrase. See [`super::binary::BinaryEnigma`].
+    Binary,
+}
+
+impl Display for EnigmaMachine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                EnigmaMachine::M3 => "M3",
+                EnigmaMachine::M4 => "M4 (Kriegsmarine)",
+                EnigmaMachine::Binary => "Byte (256 symbols)",
+            }
+        )
+    }
+}
+
+impl EnigmaMachine {
+    pub const ALL: [EnigmaMachine; 3] =
+        [EnigmaMachine::M3, EnigmaMachine::M4, EnigmaMachine::Binary];
+
+    /// Reflector the machine was normally used with
+    pub fn default_reflector(&self) -> Option<&'static str> {
+        match self {
+            EnigmaMachine::M3 => Some(catalogue::UKW_B),
+            EnigmaMachine::M4 => Some(catalogue::UKW_B_THIN),
+            EnigmaMachine::Binary => None,
+        }
+    }
+}
+
+/// What happens to bytes that aren't ASCII letters
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum EnigmaTextMode {
+    /// Like the real machine: only letters go through, lowercased
+    #[default]
+    LettersOnly,
+    /// Everything else is copied unchanged without stepping the rotors, the
+    /// case of letters is kept
+    PassThrough,
+}
+
+impl Display for EnigmaTextMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                EnigmaTextMode::LettersOnly => "Letters only",
+                EnigmaTextMode::PassThrough => "Keep formatting",
+            }
+        )
+    }
+}
+
+impl EnigmaTextMode {
+    pub const ALL: [EnigmaTextMode; 2] = [EnigmaTextMode::LettersOnly, EnigmaTextMode::PassThrough];
+}
+
+/// How the rotors turn and carry the signal
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Mechanics {
+    /// What versions before the fix did: the rotor offset is only taken off
+    /// on the way back and the left rotor also steps at its own notch. Kept
+    /// to decrypt the raw files they wrote, it doesn't match a real machine.
+    Legacy,
+    /// Like the real machine
+    Historical,
+}
+
+pub struct Enigma {
+    reflector: Reflector,
+    rotor_assembly: RotorAssembly,
+    plugboard: Plugboard,
+    text_mode: EnigmaTextMode,
+    /// Only applies to encryption in [`EnigmaTextMode::LettersOnly`]
+    five_letter_groups: bool,
+}
+
+impl Enigma {
+    /// `machine` picks between the M3 and the M4, which also uses the Greek
+    /// wheel of `config`
+    pub fn try_new(config: &EnigmaConfig, machine: EnigmaMachine) -> anyhow::Result<Enigma> {
+        Enigma::validate(config, machine)?;
+
+        let rotors = config.rotors.clone().map(|rotor| Rotor {
+            wiring: utils::to_u8_array_26(&rotor.wiring),
+            notches: rotor.notches,
+            ringstellung: rotor.ringstellung,
+            position: rotor.position as usize,
+        });
+
+        let rotor_assembly = match (machine, &config.greek) {
+            (EnigmaMachine::M4, Some(greek)) => RotorAssembly::with_greek_wheel(
+                rotors,
+                Rotor {
+                    wiring: utils::to_u8_array_26(&greek.wiring),
+                    notches: Vec::new(),
+                    ringstellung: greek.ringstellung,
+                    position: greek.position as usize,
+                },
+            ),
+            _ => RotorAssembly::new(rotors),
+        }
+        .with_mechanics(Mechanics::Historical);
+
+        Ok(Enigma {
+            reflector: Reflector {
+                wiring: utils::to_u8_array_26(&config.reflector),
+            },
+            rotor_assembly,
+            plugboard: Plugboard::new(&config.plugboard),
+            text_mode: config.text_mode,
+            five_letter_groups: config.five_letter_groups,
+        })
+    }
+
+    pub fn with_mechanics(mut self, mechanics: Mechanics) -> Enigma {
+        self.rotor_assembly = self.rotor_assembly.with_mechanics(mechanics);
+
+        self
+    }
+
+    fn validate(config: &EnigmaConfig, machine: EnigmaMachine) -> anyhow::Result<()> {
+        if machine == EnigmaMachine::Binary {
+            return Err(anyhow!("The byte machine is built by BinaryEnigma"));
+        }
+
+        if !utils::is_shuffled_alphabet(&config.reflector) {
+            return Err(anyhow!("The reflector wiring is not a shuffled alphabet"));
+        }
+
+        for (number, rotor) in config.rotors.iter().enumerate() {
+            if !utils::is_shuffled_alphabet(&rotor.wiring) {
+                return Err(anyhow!(
+                    "The wiring of rotor {} is not a shuffled alphabet",
+                    number + 1
+                ));
+            }
+            if rotor.notches.is_empty() || !rotor.notches.iter().all(|&notch| is_index(notch)) {
+                return Err(anyhow!(
+                    "Rotor {} needs notches between 0 and 25",
+                    number + 1
+                ));
+            }
+            if !is_index(rotor.ringstellung) || !is_index(rotor.position) {
+                return Err(anyhow!(
+                    "Ring setting and position of rotor {} must be between 0 and 25",
+                    number + 1
+                ));
+            }
+        }
+
+        if machine == EnigmaMachine::M4 {
+            let greek = config
+                .greek
+                .as_ref()
+                .ok_or(anyhow!("The M4 needs a Greek wheel"))?;
+
+            if !utils::is_shuffled_alphabet(&greek.wiring) {
+                return Err(anyhow!(
+                    "The wiring of the Greek wheel is not a shuffled alphabet"
+                ));
+            }
+            if !is_index(greek.ringstellung) || !is_index(greek.position) {
+                return Err(anyhow!(
+                    "Ring setting and position of the Greek wheel must be between 0 and 25"
+                ));
+            }
+        }
+
+        fn is_index(n: u8) -> bool {
+            n <= 25
+        }
+
+        if !config
+            .plugboard
+            .split_whitespace()
+            .all(|pair| (pair.len() == 2) && pair.chars().all(|c| c.is_ascii_lowercase()))
+        {
+            return Err(anyhow!("The plugboard takes lowercase letter pairs"));
+        }
+
+        let mut plugged = Vec::new();
+        for letter in config.plugboard.chars().filter(|c| !c.is_whitespace()) {
+            if plugged.contains(&letter) {
+                return Err(anyhow!(
+                    "The plugboard uses {} in more than one pair",
+                    letter
+                ));
+            }
+            plugged.push(letter);
+        }
+
+        Ok(())
+    }
+}
+
+impl Algorithm for Enigma {
+    fn encrypt(&self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        self.encryptor().update(data)
+    }
+
+    // The machine is reciprocal, decrypting only skips the grouping
+    fn decrypt(&self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        self.decryptor().update(data)
+    }
+}
+
+impl StreamingAlgorithm for Enigma {
+    fn encryptor(&self) -> Box<dyn StreamProcessor + Send> {
+        Box::new(self.processor(self.five_letter_groups))
+    }
+
+    fn decryptor(&self) -> Box<dyn StreamProcessor + Send> {
+        Box::new(self.processor(false))
+    }
+}
+
+impl Enigma {
+    fn processor(&self, five_letter_groups: bool) -> EnigmaProcessor {
+        EnigmaProcessor {
+            rotors: self.rotor_assembly.clone(),
+            reflector: self.reflector.clone(),
+            plugboard: self.plugboard.clone(),
+            text_mode: self.text_mode,
+            five_letter_groups,
+            letters_written: 0,
+        }
+    }
+
+    /// A copy of this machine to press keys on one at a time
+    pub fn simulator(&self) -> EnigmaSimulator {
+        EnigmaSimulator {
+            rotor_assembly: self.rotor_assembly.clone(),
+            reflector: self.reflector.clone(),
+            plugboard: self.plugboard.clone(),
+        }
+    }
+}
+
+/// Keeps the rotor positions between calls, so a message split into
+/// arbitrary chunks encrypts the same as the whole message.
+pub struct EnigmaProcessor {
+    rotors: RotorAssembly,
+    reflector: Reflector,
+    plugboard: Plugboard,
+    text_mode: EnigmaTextMode,
+    five_letter_groups: bool,
+    letters_written: usize,
+}
+
+impl EnigmaProcessor {
+    fn encipher(&mut self, letter: u8) -> u8 {
+        encipher(&mut self.rotors, &self.reflector, &self.plugboard, letter)
+    }
+}
+
+/// One key press: step the rotors, then plugboard, rotors, reflector and back
+pub(super) fn encipher<const N: usize>(
+    rotors: &mut RotorAssembly<N>,
+    reflector: &Reflector<N>,
+    plugboard: &Plugboard<N>,
+    letter: u8,
+) -> u8 {
+    rotors.rotate();
+
+    let l1 = plugboard.get_output(letter);
+
+    let l2 = rotors.get_output(l1);
+    let l3 = reflector.reflect(l2);
+    let l4 = rotors.get_output_inverse(l3);
+
+    plugboard.get_output(l4)
+}
+
+impl StreamProcessor for EnigmaProcessor {
+    fn update(&mut self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let mut out = Vec::with_capacity(data.len() + data.len() / 5);
+
+        for &byte in data {
+            match self.text_mode {
+                EnigmaTextMode::LettersOnly => {
+                    if !byte.is_ascii_alphabetic() {
+                        continue;
+                    }
+
+                    if self.five_letter_groups
+                        && self.letters_written > 0
+                        && self.letters_written.is_multiple_of(5)
+                    {
+                        out.push(b' ');
+                    }
+
+                    out.push(self.encipher(byte.to_ascii_lowercase()));
+                    self.letters_written += 1;
+                }
+                EnigmaTextMode::PassThrough => {
+                    if !byte.is_ascii_alphabetic() {
+                        out.push(byte);
+                        continue;
+                    }
+
+                    let letter = self.encipher(byte.to_ascii_lowercase());
+                    out.push(if byte.is_ascii_uppercase() {
+                        letter.to_ascii_uppercase()
+                    } else {
+                        letter
+                    });
+                }
+            }
+        }
+
+        Ok(out)
+    }
+
+    fn finalize(&mut self) -> anyhow::Result<Vec<u8>> {
+        Ok(Vec::new())
+    }
+}
+
+// ekmflgdqvzntowyhxuspaibrcj 8 0 ajdksiruxblhwtmcqgznpyfvoe 8 0 bdfhjlcprtxvznyeiwgakmusqo 0 0 yruhqsldpxngokmiebfzcwvjat PO ML IU KJ NH YT GB VF RE DC
+
+//
+//
+//
+//
+//
+#[cfg(test)]
+#[allow(
+    clippy::char_lit_as_u8,
+    clippy::bool_comparison,
+    clippy::needless_borrow
+)]
+mod tests {
+    use crate::algorithms::enigma::catalogue;
+    use crate::algorithms::enigma::plugboard::Plugboard;
+    use crate::algorithms::enigma::{
+        alg::{Enigma, EnigmaMachine, EnigmaTextMode, Mechanics},
+        reflector::Reflector,
+        rotor::Rotor,
+        rotor_assembly::RotorAssembly,
+        utils,
+    };
+    use crate::algorithms::{Algorithm, StreamingAlgorithm};
+    use crate::config::{EnigmaConfig, RotorConfig};
+
+    fn expected_output(input: &[u8]) -> Vec<u8> {
+        input
+            .iter()
+            .filter_map(|&b| match b {
+                b'A'..=b'Z' => Some(b + 32),
+                b'a'..=b'z' => Some(b),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Settings as written in a key sheet: catalogue rotors left to right,
+    /// then ring settings and start positions as letters
+    fn historical(rotors: [usize; 3], reflector: &str, rings: &str, start: &str) -> EnigmaConfig {
+        let letters = |letters: &str| -> Vec<u8> { letters.bytes().map(|b| b - b'a').collect() };
+        let (rings, start) = (letters(rings), letters(start));
+
+        EnigmaConfig {
+            reflector: reflector.to_owned(),
+            rotors: std::array::from_fn(|i| RotorConfig {
+                wiring: catalogue::ROTORS[rotors[i]].wiring.to_owned(),
+                notches: catalogue::ROTORS[rotors[i]].notch_positions(),
+                ringstellung: rings[i],
+                position: start[i],
+            }),
+            plugboard: String::new(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_alg() {
+        let str = "Hello asdjfk df asdf asd";
+        let input = str.as_bytes();
+
+        let enigma = Enigma::try_new(&EnigmaConfig::default(), EnigmaMachine::M3).unwrap();
+        let encrypted = enigma.encrypt(&input).unwrap();
+        let decrypted = enigma.encrypt(&encrypted).unwrap();
+
+        assert_eq!(decrypted, expected_output(input));
+
+        println!("{} {}", str, String::from_utf8(decrypted).unwrap());
+    }
+
+    #[test]
+    fn test_streaming_matches_one_shot() {
+        let input = "Hello asdjfk df asdf asd, and some more text to span chunks".as_bytes();
+
+        let enigma = Enigma::try_new(&EnigmaConfig::default(), EnigmaMachine::M3).unwrap();
+        let expected = enigma.encrypt(input).unwrap();
+
+        for chunk_size in [1, 3, 7, 64] {
+            let mut encryptor = enigma.encryptor();
+            let mut streamed = Vec::new();
+            for chunk in input.chunks(chunk_size) {
+                streamed.extend(encryptor.update(chunk).unwrap());
+            }
+            streamed.extend(encryptor.finalize().unwrap());
+
+            assert_eq!(streamed, expected);
+        }
+    }
+
+    #[test]
+    fn test_rotor_simple() {
+        let rotor = Rotor {
+            wiring: utils::to_u8_array_26("ekmflgdqvzntowyhxuspaibrcj"),
+            notches: vec![0],
+            ringstellung: 0,
+            position: 0,
+        };
+
+        assert_eq!(rotor.get_output('a' as u8), 'e' as u8);
+        assert_eq!(rotor.get_output('b' as u8), 'k' as u8);
+
+        assert_eq!(rotor.get_output_inverted('e' as u8), 'a' as u8);
+        assert_eq!(rotor.get_output_inverted('k' as u8), 'b' as u8);
+    }
+
+    #[test]
+    fn test_rotor_with_offset() {
+        let mut rotor = Rotor {
+            wiring: utils::to_u8_array_26("ekmflgdqvzntowyhxuspaibrcj"),
+            notches: vec![0],
+            ringstellung: 0,
+            position: 1,
+        };
+
+        assert_eq!(rotor.get_output('a' as u8), 'k' as u8);
+        assert_eq!(rotor.get_output('b' as u8), 'm' as u8);
+
+        assert_eq!(rotor.get_output_inverted('k' as u8), 'a' as u8);
+        assert_eq!(rotor.get_output_inverted('m' as u8), 'b' as u8);
+
+        rotor.position = 2;
+        assert_eq!(rotor.get_output_inverted('k' as u8), 'z' as u8);
+    }
+
+    #[test]
+    fn test_rotor_with_offset_historical() {
+        let mut rotor = Rotor {
+            wiring: utils::to_u8_array_26("ekmflgdqvzntowyhxuspaibrcj"),
+            notches: vec![0],
+            ringstellung: 0,
+            position: 1,
+        };
+        let inverse = rotor.inverse_wiring();
+        let forward = |rotor: &Rotor, letter| rotor.forward(Mechanics::Historical, letter);
+        let backward =
+            |rotor: &Rotor, letter| rotor.backward(Mechanics::Historical, &inverse, letter);
+
+        // The signal enters one contact further along the wiring and leaves
+        // one contact back
+        assert_eq!(forward(&rotor, b'a'), b'j');
+        assert_eq!(forward(&rotor, b'b'), b'l');
+
+        assert_eq!(backward(&rotor, b'j'), b'a');
+        assert_eq!(backward(&rotor, b'l'), b'b');
+
+        rotor.position = 2;
+        assert_eq!(backward(&rotor, b'i'), b'z');
+
+        // The ring setting turns the wiring the other way
+        rotor.ringstellung = 2;
+        assert_eq!(forward(&rotor, b'a'), b'e');
+    }
+
+    #[test]
+    fn test_assembly() {
+        // Example
+        // https://www.codesandciphers.org.uk/enigma/example1.htm
+
+        let rotors = RotorAssembly::new([
+            Rotor {
+                wiring: utils::to_u8_array_26("ekmflgdqvzntowyhxuspaibrcj"),
+                notches: vec![8],
+                ringstellung: 0,
+                position: 0,
+            },
+            Rotor {
+                wiring: utils::to_u8_array_26("ajdksiruxblhwtmcqgznpyfvoe"),
+                notches: vec![8],
+                ringstellung: 0,
+                position: 0,
+            },
+            Rotor {
+                wiring: utils::to_u8_array_26("bdfhjlcprtxvznyeiwgakmusqo"),
+                notches: vec![0],
+                ringstellung: 0,
+                position: 0,
+            },
+        ]);
+
+        let reflector = Reflector {
+            wiring: utils::to_u8_array_26("yruhqsldpxngokmiebfzcwvjat"),
+        };
+
+        let l1 = rotors.get_output('g' as u8);
+        assert_eq!(l1, 'f' as u8);
+
+        let l2 = reflector.reflect(l1);
+        assert_eq!(l2, 's' as u8);
+
+        let l3 = rotors.get_output_inverse(l2);
+        assert_eq!(l3, 'p' as u8);
+    }
+
+    #[test]
+    fn test_known_answers() {
+        let aaa = historical([0, 1, 2], catalogue::UKW_B, "aaa", "aaa");
+        let enigma = Enigma::try_new(&aaa, EnigmaMachine::M3).unwrap();
+        assert_eq!(enigma.encrypt(b"aaaaa").unwrap(), b"bdzgo");
+
+        let rings = historical([0, 1, 2], catalogue::UKW_B, "bbb", "aaa");
+        let enigma = Enigma::try_new(&rings, EnigmaMachine::M3).unwrap();
+        assert_eq!(enigma.encrypt(b"aaaaa").unwrap(), b"ewtyx");
+    }
+
+    #[test]
+    fn test_legacy_mechanics() {
+        let aaa = historical([0, 1, 2], catalogue::UKW_B, "aaa", "aaa");
+        let enigma = Enigma::try_new(&aaa, EnigmaMachine::M3)
+            .unwrap()
+            .with_mechanics(Mechanics::Legacy);
+        let input = b"thequickbrownfoxjumpsoverthelazydog".repeat(30);
+
+        let encrypted = enigma.encrypt(&input).unwrap();
+        assert_ne!(encrypted[..5], *b"bdzgo");
+        assert_eq!(enigma.decrypt(&encrypted).unwrap(), input);
+
+        // Only the legacy left rotor steps at its own notch
+        let rotor = |position: usize| Rotor {
+            wiring: utils::to_u8_array_26(catalogue::ROTORS[0].wiring),
+            notches: vec![8],
+            ringstellung: 0,
+            position,
+        };
+        let rotors = RotorAssembly::new([rotor(1), rotor(0), rotor(0)]);
+
+        let mut legacy = rotors.clone();
+        legacy.rotate();
+        assert_eq!(legacy.rotors[0].position, 2);
+
+        let mut historical = rotors.with_mechanics(Mechanics::Historical);
+        historical.rotate();
+        assert_eq!(historical.rotors[0].position, 1);
+    }
+
+    #[test]
+    fn test_barbarossa_message() {
+        // Operation Barbarossa, 7 July 1941, first part under message key BLA
+        let config = EnigmaConfig {
+            plugboard: "av bs cg dl fu hz in km ow rx".to_owned(),
+            ..historical([1, 3, 4], catalogue::UKW_B, "bul", "bla")
+        };
+        let enigma = Enigma::try_new(&config, EnigmaMachine::M3).unwrap();
+
+        let ciphertext = "EDPUD NRGYS ZRCXN UYTPO MRMBO FKTBZ REZKM LXLVE FGUEY SIOZV \
+                          EQMIK UBPMM YLKLT TDEIS MDICA GYKUA CTCDO MOHWX MUUIA UBSTS \
+                          LRNBZ SZWNR FXWFY SSXJZ VIJHI DISHP RKLKA YUPAD TXQSP INQMA \
+                          TLPIF SVKDA SCTAC DPBOP VHJK";
+        let plaintext = "aufklxabteilungxvonxkurtinowaxkurtinowaxnordwestlxsebezxsebezx\
+                         uaffliegerstraszeriqtungxdubrowkixdubrowkixopotschkaxopotschkax\
+                         umxeinsaqtdreinullxuhrangetretenxangriffxinfxrgtx";
+
+        assert_eq!(
+            enigma.decrypt(ciphertext.as_bytes()).unwrap(),
+            plaintext.as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_rotor_alignment() {
+        let mut rotor = Rotor {
+            wiring: utils::to_u8_array_26("ekmflgdqvzntowyhxuspaibrcj"),
+            notches: vec![0],
+            ringstellung: 0,
+            position: 0,
+        };
+
+        rotor.notches = vec![7];
+        rotor.position = 1;
+        assert!(rotor.is_aligned() == false);
+
+        rotor.notches = vec![7];
+        rotor.position = 0;
+        assert!(rotor.is_aligned());
+
+        rotor.notches = vec![8];
+        rotor.position = 1;
+        assert!(rotor.is_aligned());
+
+        rotor.notches = vec![6];
+        rotor.position = 25;
+        assert!(rotor.is_aligned());
+    }
+
+    // #[test]
+    // fn test_rotating() {
+    //     let mut rotors = RotorAssembly::new([
+    //         Rotor {
+    //             wiring: utils::to_u8_array_26("ekmflgdqvzntowyhxuspaibrcj"),
+    //             notches: vec![0],
+    //             position: 0,
+    //         },
+    //         Rotor {
+    //             wiring: utils::to_u8_array_26("ajdksiruxblhwtmcqgznpyfvoe"),
+    //             notches: vec![8],
+    //             position: 7,
+    //         },
+    //         Rotor {
+    //             wiring: utils::to_u8_array_26("bdfhjlcprtxvznyeiwgakmusqo"),
+    //             notches: vec![8],
+    //             position: 7,
+    //         },
+    //     ]);
+    //
+    //     rotors.rotate();
+    //     assert_eq!(rotors.rotors[2].position, 8);
+    //     assert_eq!(rotors.rotors[1].position, 0);
+    //     assert_eq!(rotors.rotors[0].position, 0);
+    //
+    //     rotors.rotate();
+    //     assert_eq!(rotors.rotors[2].position, 9);
+    //     assert_eq!(rotors.rotors[1].position, 1);
+    //     assert_eq!(rotors.rotors[0].position, 0);
+    //
+    //     rotors.rotate();
+    //     assert_eq!(rotors.rotors[2].position, 10);
+    //     assert_eq!(rotors.rotors[1].position, 2);
+    //     assert_eq!(rotors.rotors[0].position, 1);
+    // }
+
+    #[test]
+    fn test_rotating_intense() {
+        let mut rotors = RotorAssembly::new([
+            Rotor {
+                wiring: utils::to_u8_array_26("bdfhjlcprtxvznyeiwgakmusqo"),
+                notches: vec![0],
+                ringstellung: 0,
+                position: 0,
+            },
+            Rotor {
+                wiring: utils::to_u8_array_26("ajdksiruxblhwtmcqgznpyfvoe"),
+                notches: vec![8],
+                ringstellung: 0,
+                position: 0,
+            },
+            Rotor {
+                wiring: utils::to_u8_array_26("ekmflgdqvzntowyhxuspaibrcj"),
+                notches: vec![8],
+                ringstellung: 0,
+                position: 0,
+            },
+        ]);
+
+        for _ in 0..26 {
+            rotors.rotate();
+        }
+
+        assert_eq!(rotors.rotors[2].position, 26);
+        assert_eq!(rotors.rotors[1].position, 2);
+        assert_eq!(rotors.rotors[0].position, 1);
+    }
+
+    #[test]
+    fn test_rotate_and_output() {
+        let mut rotors = RotorAssembly::new([
+            Rotor {
+                wiring: utils::to_u8_array_26("ekmflgdqvzntowyhxuspaibrcj"),
+                notches: vec![8],
+                ringstellung: 0,
+                position: 0,
+            },
+            Rotor {
+                wiring: utils::to_u8_array_26("ajdksiruxblhwtmcqgznpyfvoe"),
+                notches: vec![8],
+                ringstellung: 0,
+                position: 0,
+            },
+            Rotor {
+                wiring: utils::to_u8_array_26("bdfhjlcprtxvznyeiwgakmusqo"),
+                notches: vec![0],
+                ringstellung: 0,
+                position: 0,
+            },
+        ]);
+
+        let l1 = rotors.rotors[2].get_output(b'g');
+        let l2 = rotors.rotors[1].get_output(l1);
+        let l3 = rotors.rotors[0].get_output(l2);
+
+        assert_eq!(l1, 'c' as u8);
+        assert_eq!(l2, 'd' as u8);
+        assert_eq!(l3, 'f' as u8);
+
+        rotors.rotate();
+
+        let l1 = rotors.rotors[2].get_output(b'g');
+        let l2 = rotors.rotors[1].get_output(l1);
+        let l3 = rotors.rotors[0].get_output(l2);
+
+        assert_eq!(l1, 'p' as u8);
+        assert_eq!(l2, 'c' as u8);
+        assert_eq!(l3, 'm' as u8);
+
+        rotors.rotate();
+
+        let l1 = rotors.rotors[2].get_output(b'g');
+        let l2 = rotors.rotors[1].get_output(l1);
+        let l3 = rotors.rotors[0].get_output(l2);
+
+        assert_eq!(l1, 'r' as u8);
+        assert_eq!(l2, 'g' as u8);
+        assert_eq!(l3, 'd' as u8);
+    }
+
+    #[test]
+    fn test_reflector() {
+        let reflector = Reflector {
+            wiring: utils::to_u8_array_26("yruhqsldpxngokmiebfzcwvjat"),
+        };
+
+        assert_eq!(reflector.reflect('a' as u8), 'y' as u8);
+        assert_eq!(reflector.reflect('y' as u8), 'a' as u8);
+
+        assert_eq!(reflector.reflect('g' as u8), 'l' as u8);
+        assert_eq!(reflector.reflect('l' as u8), 'g' as u8);
+
+        assert_eq!(reflector.reflect('r' as u8), 'b' as u8);
+        assert_eq!(reflector.reflect('b' as u8), 'r' as u8);
+    }
+
+    #[test]
+    fn test_plugboard() {
+        let plugboard = Plugboard::new("PO ML IU KJ NH YT GB VF RE DC");
+
+        assert_eq!(plugboard.get_output('a' as u8), 'a' as u8);
+        assert_eq!(plugboard.get_output('k' as u8), 'j' as u8);
+        assert_eq!(plugboard.get_output('c' as u8), 'd' as u8);
+    }
+
+    #[test]
+    fn test_plugboard_spacing_and_reused_letters() {
+        let mut config = EnigmaConfig {
+            plugboard: " po  ml\tiu ".to_owned(),
+            ..Default::default()
+        };
+        let spaced = Enigma::try_new(&config, EnigmaMachine::M3).unwrap();
+        config.plugboard = "po ml iu".to_owned();
+        let plain = Enigma::try_new(&config, EnigmaMachine::M3).unwrap();
+        assert_eq!(
+            spaced.encrypt(b"plugboard").unwrap(),
+            plain.encrypt(b"plugboard").unwrap()
+        );
+
+        for plugboard in ["ab bc", "ab ab", "aa", "ab c", "a b"] {
+            config.plugboard = plugboard.to_owned();
+            assert!(
+                Enigma::try_new(&config, EnigmaMachine::M3).is_err(),
+                "{}",
+                plugboard
+            );
+        }
+    }
+
+    #[test]
+    fn test_m4_with_greek_wheel_at_rest_matches_m3() {
+        let input = "Funkspruch von Befehlshaber der Unterseeboote".as_bytes();
+
+        for (greek, thin, wide) in [
+            (
+                catalogue::GREEK_BETA,
+                catalogue::UKW_B_THIN,
+                catalogue::UKW_B,
+            ),
+            (
+                catalogue::GREEK_GAMMA,
+                catalogue::UKW_C_THIN,
+                catalogue::UKW_C,
+            ),
+        ] {
+            let m3 = Enigma::try_new(
+                &EnigmaConfig {
+                    reflector: wide.to_owned(),
+                    ..Default::default()
+                },
+                EnigmaMachine::M3,
+            )
+            .unwrap();
+            let m4 = Enigma::try_new(
+                &EnigmaConfig {
+                    reflector: thin.to_owned(),
+                    greek: Some(RotorConfig {
+                        wiring: greek.to_owned(),
+                        notches: Vec::new(),
+                        ringstellung: 0,
+                        position: 0,
+                    }),
+                    ..Default::default()
+                },
+                EnigmaMachine::M4,
+            )
+            .unwrap();
+
+            assert_eq!(m4.encrypt(input).unwrap(), m3.encrypt(input).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_m4_doenitz_message() {
+        // Dönitz's message of 1 May 1945: Beta II IV I, UKW-b thin, rings AAAV,
+        // start VJNA
+        let config = EnigmaConfig {
+            greek: Some(RotorConfig {
+                wiring: catalogue::GREEK_BETA.to_owned(),
+                notches: Vec::new(),
+                ringstellung: 0,
+                position: b'v' - b'a',
+            }),
+            plugboard: "at bl df gj hm nw op qy rz vx".to_owned(),
+            ..historical([1, 3, 0], catalogue::UKW_B_THIN, "aav", "jna")
+        };
+        let enigma = Enigma::try_new(&config, EnigmaMachine::M4).unwrap();
+
+        let ciphertext = "NCZW VUSX PNYM INHZ XMQX SFWX WLKJ AHSH NMCO CCAK UQPM KCSM \
+                          HKSE INJU SBLK IOSX CKUB HMLL XCSJ USRR DVKO HULX WCCB GVLI \
+                          YXEO AHXR HKKF VDRE WEZL XOBA FGYU JQUK GRTV UKAM EURB VEKS \
+                          UHHV OYHA BCJW MAKL FKLM YFVN RIZR VVRT KOFD ANJM OLBG FFLE \
+                          OPRG TFLV RHOW OPBE KVWM UQFM PWPA RMFH AGKX IIBG";
+        let plaintext = "vonvonjlooksjhffttteinseinsdreizwoyyqnnsneuninhaltxxbeiangriffunterwassergedrueckt\
+                         ywabosxletztergegnerstandnulachtdreinuluhrmarquantonjotaneunachtseyhsdreiyzwozwonul\
+                         gradyachtsmystossenachxeknsviermbfaelltynnnnnnooovierysichteinsnull";
+
+        assert_eq!(
+            enigma.decrypt(ciphertext.as_bytes()).unwrap(),
+            plaintext.as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_m4_is_reciprocal_and_greek_wheel_does_not_step() {
+        let config = EnigmaConfig {
+            reflector: catalogue::UKW_C_THIN.to_owned(),
+            greek: Some(RotorConfig {
+                wiring: catalogue::GREEK_GAMMA.to_owned(),
+                notches: Vec::new(),
+                ringstellung: 3,
+                position: 11,
+            }),
+            ..Default::default()
+        };
+        let enigma = Enigma::try_new(&config, EnigmaMachine::M4).unwrap();
+        let input = "Kriegsmarine".repeat(60);
+
+        let encrypted = enigma.encrypt(input.as_bytes()).unwrap();
+        assert_eq!(
+            enigma.decrypt(&encrypted).unwrap(),
+            expected_output(input.as_bytes())
+        );
+
+        let mut rotors = enigma.rotor_assembly.clone();
+        for _ in 0..700 {
+            rotors.rotate();
+        }
+        assert_eq!(rotors.greek_wheel.unwrap().position, 11);
+
+        let missing_greek = EnigmaConfig {
+            greek: None,
+            ..config
+        };
+        assert!(Enigma::try_new(&missing_greek, EnigmaMachine::M4).is_err());
+    }
+
+    #[test]
+    fn test_double_notch_steps_left_rotor_twice() {
+        let rotor = |notches: Vec<u8>, position: usize| Rotor {
+            wiring: utils::to_u8_array_26(catalogue::ROTORS[5].wiring),
+            notches,
+            ringstellung: 0,
+            position,
+        };
+        let mut rotors =
+            RotorAssembly::new([rotor(vec![], 0), rotor(vec![6, 19], 12), rotor(vec![], 0)]);
+
+        rotors.rotate();
+        assert_eq!(rotors.rotors[0].position, 1);
+        assert_eq!(rotors.rotors[1].position, 13);
+
+        rotors.rotate();
+        assert_eq!(rotors.rotors[0].position, 1);
+
+        rotors.rotors[1].position = 25;
+        rotors.rotate();
+        assert_eq!(rotors.rotors[0].position, 2);
+    }
+
+    #[test]
+    fn test_catalogue_rotors_are_valid_config() {
+        let [first, second, third] = [0, 5, 7].map(|i| catalogue::ROTORS[i]);
+        let mut config = EnigmaConfig {
+            reflector: catalogue::REFLECTORS[0].wiring.to_owned(),
+            rotors: [first, second, third].map(|rotor| RotorConfig {
+                wiring: rotor.wiring.to_owned(),
+                notches: rotor.notch_positions(),
+                ringstellung: 0,
+                position: 0,
+            }),
+            ..Default::default()
+        };
+        let enigma = Enigma::try_new(&config, EnigmaMachine::M3).unwrap();
+
+        assert_eq!(enigma.rotor_assembly.rotors[1].notches, vec![6, 19]);
+
+        config.rotors[1].notches.clear();
+        assert!(Enigma::try_new(&config, EnigmaMachine::M3).is_err());
+        config.rotors[1].notches = vec![26];
+        assert!(Enigma::try_new(&config, EnigmaMachine::M3).is_err());
+    }
+
+    #[test]
+    fn test_pass_through_keeps_formatting() {
+        let input = b"Hello, World!\n\t42 \x00\xff U-Boot";
+
+        let letters = Enigma::try_new(&EnigmaConfig::default(), EnigmaMachine::M3).unwrap();
+        let enigma = Enigma::try_new(
+            &EnigmaConfig {
+                text_mode: EnigmaTextMode::PassThrough,
+                ..Default::default()
+            },
+            EnigmaMachine::M3,
+        )
+        .unwrap();
+
+        let encrypted = enigma.encrypt(input).unwrap();
+        assert_eq!(encrypted.len(), input.len());
+        assert_eq!(enigma.decrypt(&encrypted).unwrap(), input);
+
+        for (&original, &encrypted) in input.iter().zip(&encrypted) {
+            assert_eq!(
+                original.is_ascii_uppercase(),
+                encrypted.is_ascii_uppercase()
+            );
+            if !original.is_ascii_alphabetic() {
+                assert_eq!(original, encrypted);
+            }
+        }
+
+        // Non-letters don't step the rotors
+        assert_eq!(expected_output(&encrypted), letters.encrypt(input).unwrap());
+    }
+
+    #[test]
+    fn test_five_letter_groups() {
+        let input = "Hello asdjfk df asdf asd".as_bytes();
+
+        let plain = Enigma::try_new(&EnigmaConfig::default(), EnigmaMachine::M3).unwrap();
+        let grouped = Enigma::try_new(
+            &EnigmaConfig {
+                five_letter_groups: true,
+                ..Default::default()
+            },
+            EnigmaMachine::M3,
+        )
+        .unwrap();
+
+        let ungrouped = plain.encrypt(input).unwrap();
+        let expected: Vec<u8> = ungrouped.chunks(5).collect::<Vec<_>>().join(&b' ');
+
+        let mut encryptor = grouped.encryptor();
+        let mut streamed = Vec::new();
+        for chunk in input.chunks(3) {
+            streamed.extend(encryptor.update(chunk).unwrap());
+        }
+        streamed.extend(encryptor.finalize().unwrap());
+
+        assert_eq!(grouped.encrypt(input).unwrap(), expected);
+        assert_eq!(streamed, expected);
+        assert_eq!(grouped.decrypt(&expected).unwrap(), expected_output(input));
+    }
+}
@@ -0,0 +1,520 @@
+//! Ciphertext-only attack on the three-rotor Enigma, after Gillogly: find
+//! rotor order and start positions by index of coincidence, then ring
+//! settings and plugboard by n-gram statistics.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::anyhow;
+
+use crate::config::{EnigmaConfig, RotorConfig};
+
+use super::{
+    alg::{encipher, Enigma, EnigmaMachine},
+    catalogue::Component,
+    plugboard::Plugboard,
+    reflector::Reflector,
+    rotor_assembly::RotorAssembly,
+    utils,
+};
+
+/// Sample of English prose the n-gram model is trained on
+const CORPUS: &str = include_str!("english.txt");
+
+/// Settings taken from the index of coincidence stage to the n-gram stages
+const KEPT: usize = 10;
+/// Most plugboard pairs the hill climb will put in
+const MAX_PLUGS: usize = 10;
+/// Below this the statistics say nothing
+const MIN_LETTERS: usize = 60;
+
+/// Share of the progress bar taken by the rotor order and position search
+const POSITION_STAGE: f32 = 0.8;
+
+/// Rotors to choose the order from and the reflector they run with
+#[derive(Debug, Clone)]
+pub struct SearchSpace {
+    pub rotors: Vec<Component>,
+    pub reflector: Component,
+}
+
+impl SearchSpace {
+    pub(super) fn validate(&self) -> anyhow::Result<()> {
+        if self.rotors.len() < 3 {
+            return Err(anyhow!("Pick at least three rotors"));
+        }
+        if !utils::is_shuffled_alphabet(self.reflector.wiring) {
+            return Err(anyhow!("Pick a reflector from the catalogue"));
+        }
+
+        Ok(())
+    }
+
+    /// Every order of three different rotors, as indices into `rotors`
+    pub(super) fn orders(&self) -> Vec<[usize; 3]> {
+        let n = self.rotors.len();
+
+        (0..n)
+            .flat_map(|a| (0..n).flat_map(move |b| (0..n).map(move |c| [a, b, c])))
+            .filter(|[a, b, c]| a != b && b != c && a != c)
+            .collect()
+    }
+}
+
+#[derive(Clone)]
+pub struct Candidate {
+    pub config: EnigmaConfig,
+    /// Average log probability per n-gram of `plaintext`, higher is better
+    pub score: f64,
+    pub plaintext: String,
+}
+
+// The config is left out, it is key material
+impl std::fmt::Debug for Candidate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Candidate")
+            .field("score", &self.score)
+            .field("plaintext", &self.plaintext)
+            .finish_non_exhaustive()
+    }
+}
+
+pub fn index_of_coincidence(text: &[u8]) -> f64 {
+    let mut counts = [0usize; 26];
+    let mut total = 0;
+
+    for letter in text.iter().filter(|l| l.is_ascii_lowercase()) {
+        counts[(letter - b'a') as usize] += 1;
+        total += 1;
+    }
+
+    if total < 2 {
+        return 0.0;
+    }
+
+    let coincidences: usize = counts.iter().map(|&c| c * c.saturating_sub(1)).sum();
+
+    return coincidences as f64 / (total * (total - 1)) as f64;
+}
+
+/// Log probabilities of letter n-grams
+pub struct NgramModel {
+    n: usize,
+    log_probs: Vec<f64>,
+}
+
+impl NgramModel {
+    /// Counts n-grams of the corpus letters, unseen ones get half a count
+    pub fn from_corpus(corpus: &str, n: usize) -> NgramModel {
+        let letters: Vec<u8> = corpus
+            .bytes()
+            .filter(u8::is_ascii_alphabetic)
+            .map(|l| l.to_ascii_lowercase())
+            .collect();
+
+        let mut counts = vec![0.5f64; 26usize.pow(n as u32)];
+        for gram in letters.windows(n) {
+            counts[NgramModel::index(gram)] += 1.0;
+        }
+
+        let total: f64 = counts.iter().sum();
+
+        NgramModel {
+            n,
+            log_probs: counts.iter().map(|count| (count / total).ln()).collect(),
+        }
+    }
+
+    pub fn english_bigrams() -> NgramModel {
+        NgramModel::from_corpus(CORPUS, 2)
+    }
+
+    /// Average log probability per n-gram of lowercase `text`
+    pub fn score(&self, text: &[u8]) -> f64 {
+        if text.len() < self.n {
+            return f64::NEG_INFINITY;
+        }
+
+        let sum: f64 = text
+            .windows(self.n)
+            .map(|gram| self.log_probs[NgramModel::index(gram)])
+            .sum();
+
+        return sum / (text.len() - self.n + 1) as f64;
+    }
+
+    fn index(gram: &[u8]) -> usize {
+        gram.iter()
+            .fold(0, |index, &l| index * 26 + (l - b'a') as usize)
+    }
+}
+
+/// Rotor order as indices into [`SearchSpace::rotors`], left to right
+#[derive(Clone, Copy)]
+pub(super) struct Setting {
+    pub(super) order: [usize; 3],
+    pub(super) rings: [u8; 3],
+    pub(super) positions: [u8; 3],
+}
+
+impl Setting {
+    pub(super) fn config(&self, space: &SearchSpace, plugboard: String) -> EnigmaConfig {
+        let rotors = std::array::from_fn(|i| {
+            let rotor = space.rotors[self.order[i]];
+            RotorConfig {
+                wiring: rotor.wiring.to_owned(),
+                notches: rotor.notch_positions(),
+                ringstellung: self.rings[i],
+                position: self.positions[i],
+            }
+        });
+
+        EnigmaConfig {
+            reflector: space.reflector.wiring.to_owned(),
+            rotors,
+            plugboard,
+            ..Default::default()
+        }
+    }
+
+    /// The rotors of this setting, built once per order and adjusted after
+    pub(super) fn apply(&self, template: &RotorAssembly) -> RotorAssembly {
+        let mut rotors = template.clone();
+
+        for (i, rotor) in rotors.rotors.iter_mut().enumerate() {
+            rotor.ringstellung = self.rings[i];
+            rotor.position = self.positions[i] as usize;
+        }
+
+        rotors
+    }
+}
+
+/// Search every rotor order of `space` and return the best settings, best
+/// first. `progress` gets the done fraction, setting `cancel` stops the
+/// search with an error.
+pub fn search(
+    ciphertext: &str,
+    space: &SearchSpace,
+    cancel: &AtomicBool,
+    mut progress: impl FnMut(f32),
+) -> anyhow::Result<Vec<Candidate>> {
+    let text = letters(ciphertext);
+
+    if text.len() < MIN_LETTERS {
+        return Err(anyhow!(
+            "The ciphertext needs at least {} letters",
+            MIN_LETTERS
+        ));
+    }
+    space.validate()?;
+
+    let orders = space.orders();
+
+    let model = NgramModel::english_bigrams();
+    let identity = Plugboard::new("");
+
+    // Rotor order and start positions, rings at zero and no plugs
+    let mut kept: Vec<(f64, Setting, usize)> = Vec::with_capacity(KEPT + 1);
+    let mut templates = Vec::with_capacity(orders.len());
+
+    for (done, &order) in orders.iter().enumerate() {
+        let zero = Setting {
+            order,
+            rings: [0; 3],
+            positions: [0; 3],
+        };
+        let machine =
+            Enigma::try_new(&zero.config(space, String::new()), EnigmaMachine::M3)?.simulator();
+        templates.push(machine.rotor_assembly);
+        let reflector = machine.reflector;
+
+        for left in 0..26 {
+            if cancel.load(Ordering::Relaxed) {
+                return Err(anyhow!("The search was cancelled"));
+            }
+
+            for middle in 0..26 {
+                for right in 0..26 {
+                    let setting = Setting {
+                        positions: [left, middle, right],
+                        ..zero
+                    };
+                    let rotors = setting.apply(&templates[done]);
+                    let ioc = index_of_coincidence(&decrypt(rotors, &reflector, &identity, &text));
+
+                    if kept.len() < KEPT || ioc > kept[KEPT - 1].0 {
+                        let at = kept.partition_point(|(other, _, _)| *other >= ioc);
+                        kept.insert(at, (ioc, setting, done));
+                        kept.truncate(KEPT);
+                    }
+                }
+            }
+        }
+
+        progress(POSITION_STAGE * (done + 1) as f32 / orders.len() as f32);
+    }
+
+    let reflector = Reflector {
+        wiring: utils::to_u8_array_26(space.reflector.wiring),
+    };
+
+    let mut candidates = Vec::with_capacity(kept.len());
+
+    for (done, (_, setting, template)) in kept.iter().enumerate() {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(anyhow!("The search was cancelled"));
+        }
+
+        let template = &templates[*template];
+        let setting = best_rings(setting, template, &reflector, &identity, &text, &model);
+        let rotors = setting.apply(template);
+        let (plugboard, _) = climb_plugboard(&rotors, &reflector, &text, &model);
+        let plugboard_wiring = Plugboard::from_wiring(plugboard);
+
+        // A ring setting a step off only garbles the letters after a
+        // turnover, which the missing plugs can hide, so try again with them
+        let setting = best_rings(
+            &setting,
+            template,
+            &reflector,
+            &plugboard_wiring,
+            &text,
+            &model,
+        );
+        let plaintext = decrypt(
+            setting.apply(template),
+            &reflector,
+            &plugboard_wiring,
+            &text,
+        );
+        let score = model.score(&plaintext);
+        let pairs = plug_pairs(&plugboard);
+
+        candidates.push(Candidate {
+            config: setting.config(space, pairs),
+            score,
+            plaintext: String::from_utf8(plaintext).unwrap(),
+        });
+
+        progress(POSITION_STAGE + (1.0 - POSITION_STAGE) * (done + 1) as f32 / kept.len() as f32);
+    }
+
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    return Ok(candidates);
+}
+
+/// The letters of `text`, lowercased
+pub(super) fn letters(text: &str) -> Vec<u8> {
+    text.bytes()
+        .filter(u8::is_ascii_alphabetic)
+        .map(|l| l.to_ascii_lowercase())
+        .collect()
+}
+
+fn decrypt(
+    mut rotors: RotorAssembly,
+    reflector: &Reflector,
+    plugboard: &Plugboard,
+    text: &[u8],
+) -> Vec<u8> {
+    text.iter()
+        .map(|&letter| encipher(&mut rotors, reflector, plugboard, letter))
+        .collect()
+}
+
+/// Ring settings of the middle and right rotor only change when the rotors
+/// step, so they are tried with the position moved along to keep the wiring
+/// offset the position search found
+fn best_rings(
+    setting: &Setting,
+    template: &RotorAssembly,
+    reflector: &Reflector,
+    plugboard: &Plugboard,
+    text: &[u8],
+    model: &NgramModel,
+) -> Setting {
+    let mut best = (f64::NEG_INFINITY, *setting);
+
+    for middle in 0..26u8 {
+        for right in 0..26u8 {
+            let trial = Setting {
+                rings: [setting.rings[0], middle, right],
+                positions: [
+                    setting.positions[0],
+                    (setting.positions[1] + 26 - setting.rings[1] + middle) % 26,
+                    (setting.positions[2] + 26 - setting.rings[2] + right) % 26,
+                ],
+                ..*setting
+            };
+            let score = model.score(&decrypt(trial.apply(template), reflector, plugboard, text));
+
+            if score > best.0 {
+                best = (score, trial);
+            }
+        }
+    }
+
+    return best.1;
+}
+
+/// Steepest ascent over putting in or pulling out one plug at a time
+fn climb_plugboard(
+    rotors: &RotorAssembly,
+    reflector: &Reflector,
+    text: &[u8],
+    model: &NgramModel,
+) -> ([u8; 26], f64) {
+    let score = |wiring: [u8; 26]| {
+        model.score(&decrypt(
+            rotors.clone(),
+            reflector,
+            &Plugboard::from_wiring(wiring),
+            text,
+        ))
+    };
+
+    let mut wiring: [u8; 26] = std::array::from_fn(|i| b'a' + i as u8);
+    let mut best = score(wiring);
+
+    loop {
+        let plugs = wiring
+            .iter()
+            .enumerate()
+            .filter(|(i, &l)| l != b'a' + *i as u8)
+            .count()
+            / 2;
+        let mut improved = None;
+
+        for a in 0..26 {
+            for b in a + 1..26 {
+                let (la, lb) = (b'a' + a as u8, b'a' + b as u8);
+                let mut trial = wiring;
+
+                if wiring[a] == la && wiring[b] == lb && plugs < MAX_PLUGS {
+                    trial[a] = lb;
+                    trial[b] = la;
+                } else if wiring[a] == lb {
+                    trial[a] = la;
+                    trial[b] = lb;
+                } else {
+                    continue;
+                }
+
+                let trial_score = score(trial);
+                if trial_score > improved.map_or(best, |(_, s)| s) {
+                    improved = Some((trial, trial_score));
+                }
+            }
+        }
+
+        match improved {
+            Some((trial, trial_score)) => {
+                wiring = trial;
+                best = trial_score;
+            }
+            None => return (wiring, best),
+        }
+    }
+}
+
+/// Plugboard wiring as "ab cd" pairs
+pub(super) fn plug_pairs(wiring: &[u8; 26]) -> String {
+    wiring
+        .iter()
+        .enumerate()
+        .filter(|(i, &l)| l > b'a' + *i as u8)
+        .map(|(i, &l)| format!("{}{}", (b'a' + i as u8) as char, l as char))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicBool;
+
+    use super::{index_of_coincidence, search, NgramModel, SearchSpace};
+    use crate::algorithms::enigma::alg::{Enigma, EnigmaMachine};
+    use crate::algorithms::enigma::{catalogue, catalogue::Component};
+    use crate::algorithms::Algorithm;
+    use crate::config::{EnigmaConfig, RotorConfig};
+
+    const PLAINTEXT: &str = "weather report for the northern sector follows clouds \
+        moving in from the west with rain expected before evening and strong winds \
+        along the coast the convoy will wait in the harbour until the storm has \
+        passed and the sea is calm enough for the ships to leave at first light";
+
+    #[test]
+    fn english_scores_above_random_letters() {
+        let model = NgramModel::english_bigrams();
+        let english = PLAINTEXT.replace(' ', "");
+        let shuffled: String = english
+            .bytes()
+            .map(|l| (b'a' + (l - b'a') * 7 % 26) as char)
+            .collect();
+
+        assert!(model.score(english.as_bytes()) > model.score(shuffled.as_bytes()));
+        assert!(index_of_coincidence(english.as_bytes()) > 0.055);
+        assert!(index_of_coincidence(b"abcdefghijklmnopqrstuvwxyz") == 0.0);
+    }
+
+    #[test]
+    fn finds_the_key_of_an_english_message() {
+        let [first, second, third] = [1, 0, 2].map(|i| catalogue::ROTORS[i]);
+        let rotor = |component: Component, ringstellung, position| RotorConfig {
+            wiring: component.wiring.to_owned(),
+            notches: component.notch_positions(),
+            ringstellung,
+            position,
+        };
+        let config = EnigmaConfig {
+            reflector: catalogue::UKW_B.to_owned(),
+            rotors: [
+                rotor(first, 0, 7),
+                rotor(second, 0, 19),
+                rotor(third, 4, 11),
+            ],
+            plugboard: "aq ek".to_owned(),
+            ..Default::default()
+        };
+        let ciphertext = Enigma::try_new(&config, EnigmaMachine::M3)
+            .unwrap()
+            .encrypt(PLAINTEXT.as_bytes())
+            .unwrap();
+
+        let space = SearchSpace {
+            rotors: catalogue::ROTORS[..3].to_vec(),
+            reflector: catalogue::REFLECTORS[1],
+        };
+        let mut last_progress = 0.0;
+        let candidates = search(
+            &String::from_utf8(ciphertext).unwrap(),
+            &space,
+            &AtomicBool::new(false),
+            |done| last_progress = done,
+        )
+        .unwrap();
+
+        assert_eq!(last_progress, 1.0);
+        assert_eq!(candidates[0].plaintext, PLAINTEXT.replace(' ', ""));
+        assert_eq!(
+            candidates[0].config.rotors[0].wiring,
+            config.rotors[0].wiring
+        );
+        assert_eq!(
+            candidates[0].config.rotors[2].wiring,
+            config.rotors[2].wiring
+        );
+    }
+
+    #[test]
+    fn cancel_stops_the_search() {
+        let space = SearchSpace {
+            rotors: catalogue::ROTORS[..3].to_vec(),
+            reflector: catalogue::REFLECTORS[1],
+        };
+
+        assert!(search(PLAINTEXT, &space, &AtomicBool::new(true), |_| {}).is_err());
+        assert!(search("too short", &space, &AtomicBool::new(false), |_| {}).is_err());
+    }
+}
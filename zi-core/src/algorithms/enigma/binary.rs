@@ -0,0 +1,252 @@
+use zeroize::Zeroizing;
+
+use crate::{
+    algorithms::{Algorithm, StreamProcessor, StreamingAlgorithm},
+    config::BinaryEnigmaConfig,
+    hash,
+};
+
+use super::{
+    alg::{encipher, Mechanics},
+    plugboard::Plugboard,
+    reflector::Reflector,
+    rotor::Rotor,
+    rotor_assembly::RotorAssembly,
+};
+
+const SYMBOLS: usize = 256;
+const KEY_DOMAIN: &[u8] = b"zi binary enigma";
+/// Symbol pairs swapped by the generated plugboard
+const PLUGBOARD_PAIRS: usize = 64;
+
+/// Enigma over all 256 byte values, so any file round-trips. Rotors,
+/// notches, ring settings, start positions, reflector and plugboard are all
+/// derived from a passphrase.
+pub struct BinaryEnigma {
+    reflector: Reflector<SYMBOLS>,
+    rotor_assembly: RotorAssembly<SYMBOLS>,
+    plugboard: Plugboard<SYMBOLS>,
+}
+
+impl BinaryEnigma {
+    pub fn new(config: &BinaryEnigmaConfig) -> BinaryEnigma {
+        BinaryEnigma::from_passphrase(&config.passphrase)
+    }
+
+    pub fn from_passphrase(passphrase: &str) -> BinaryEnigma {
+        BinaryEnigma::from_seed(&KeyStream::seed(passphrase))
+    }
+
+    /// Builds the machine from key bytes that were already derived from the
+    /// passphrase elsewhere
+    pub fn from_seed(seed: &[u8]) -> BinaryEnigma {
+        let mut keys = KeyStream::new(seed);
+
+        let rotors = [(); 3].map(|_| Rotor {
+            wiring: keys.permutation(),
+            notches: vec![keys.next_byte()],
+            ringstellung: keys.next_byte(),
+            position: keys.next_byte() as usize,
+        });
+
+        // Pair up a shuffled alphabet, which gives an involution without
+        // fixed points
+        let order = keys.permutation();
+        let mut reflector = [0u8; SYMBOLS];
+        for pair in order.chunks_exact(2) {
+            reflector[pair[0] as usize] = pair[1];
+            reflector[pair[1] as usize] = pair[0];
+        }
+
+        let order = keys.permutation();
+        let mut plugboard: [u8; SYMBOLS] = std::array::from_fn(|i| i as u8);
+        for pair in order.chunks_exact(2).take(PLUGBOARD_PAIRS) {
+            plugboard.swap(pair[0] as usize, pair[1] as usize);
+        }
+
+        BinaryEnigma {
+            reflector: Reflector { wiring: reflector },
+            rotor_assembly: RotorAssembly::new(rotors).with_mechanics(Mechanics::Historical),
+            plugboard: Plugboard::from_wiring(plugboard),
+        }
+    }
+
+    fn processor(&self) -> BinaryEnigmaProcessor {
+        BinaryEnigmaProcessor {
+            rotors: self.rotor_assembly.clone(),
+            reflector: self.reflector.clone(),
+            plugboard: self.plugboard.clone(),
+        }
+    }
+}
+
+impl Algorithm for BinaryEnigma {
+    fn encrypt(&self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        self.processor().update(data)
+    }
+
+    fn decrypt(&self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        self.encrypt(data)
+    }
+}
+
+impl StreamingAlgorithm for BinaryEnigma {
+    fn encryptor(&self) -> Box<dyn StreamProcessor + Send> {
+        Box::new(self.processor())
+    }
+
+    fn decryptor(&self) -> Box<dyn StreamProcessor + Send> {
+        Box::new(self.processor())
+    }
+}
+
+pub struct BinaryEnigmaProcessor {
+    rotors: RotorAssembly<SYMBOLS>,
+    reflector: Reflector<SYMBOLS>,
+    plugboard: Plugboard<SYMBOLS>,
+}
+
+impl StreamProcessor for BinaryEnigmaProcessor {
+    fn update(&mut self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        Ok(data
+            .iter()
+            .map(|&byte| encipher(&mut self.rotors, &self.reflector, &self.plugboard, byte))
+            .collect())
+    }
+
+    fn finalize(&mut self) -> anyhow::Result<Vec<u8>> {
+        Ok(Vec::new())
+    }
+}
+
+/// Deterministic bytes from a passphrase: Tiger over the passphrase digest
+/// and a block counter
+struct KeyStream {
+    seed: Zeroizing<Vec<u8>>,
+    counter: u64,
+    block: Vec<u8>,
+    used: usize,
+}
+
+impl KeyStream {
+    fn seed(passphrase: &str) -> Zeroizing<Vec<u8>> {
+        let mut material = Zeroizing::new(KEY_DOMAIN.to_vec());
+        material.extend_from_slice(passphrase.as_bytes());
+
+        Zeroizing::new(hash::hash_data(&material))
+    }
+
+    fn new(seed: &[u8]) -> KeyStream {
+        KeyStream {
+            seed: Zeroizing::new(seed.to_vec()),
+            counter: 0,
+            block: Vec::new(),
+            used: 0,
+        }
+    }
+
+    fn next_byte(&mut self) -> u8 {
+        if self.used == self.block.len() {
+            let mut material = Zeroizing::new(self.seed.to_vec());
+            material.extend_from_slice(&self.counter.to_le_bytes());
+
+            self.block = hash::hash_data(&material);
+            self.counter += 1;
+            self.used = 0;
+        }
+
+        self.used += 1;
+
+        self.block[self.used - 1]
+    }
+
+    /// Uniform in `0..n`, for `n` up to 256
+    fn below(&mut self, n: usize) -> usize {
+        let limit = SYMBOLS - SYMBOLS % n;
+
+        loop {
+            let byte = self.next_byte() as usize;
+            if byte < limit {
+                return byte % n;
+            }
+        }
+    }
+
+    fn permutation(&mut self) -> [u8; SYMBOLS] {
+        let mut permutation: [u8; SYMBOLS] = std::array::from_fn(|i| i as u8);
+
+        for i in (1..SYMBOLS).rev() {
+            let j = self.below(i + 1);
+            permutation.swap(i, j);
+        }
+
+        permutation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BinaryEnigma, SYMBOLS};
+    use crate::algorithms::{Algorithm, StreamingAlgorithm};
+
+    #[test]
+    fn every_byte_round_trips() {
+        let enigma = BinaryEnigma::from_passphrase("correct horse battery staple");
+        let data: Vec<u8> = (0..4096u32).map(|i| (i * 31 % 256) as u8).collect();
+
+        let encrypted = enigma.encrypt(&data).unwrap();
+
+        assert_ne!(encrypted, data);
+        assert_eq!(enigma.decrypt(&encrypted).unwrap(), data);
+        // The reflector has no fixed points, so no byte maps to itself
+        assert!(data.iter().zip(&encrypted).all(|(a, b)| a != b));
+    }
+
+    #[test]
+    fn components_are_valid() {
+        let enigma = BinaryEnigma::from_passphrase("components");
+
+        for i in 0..SYMBOLS as u8 {
+            let reflected = enigma.reflector.reflect(i);
+            assert_ne!(reflected, i);
+            assert_eq!(enigma.reflector.reflect(reflected), i);
+
+            let plugged = enigma.plugboard.get_output(i);
+            assert_eq!(enigma.plugboard.get_output(plugged), i);
+        }
+
+        for rotor in &enigma.rotor_assembly.rotors {
+            let mut seen = [false; SYMBOLS];
+            rotor.wiring.iter().for_each(|&s| seen[s as usize] = true);
+            assert!(seen.iter().all(|&s| s));
+        }
+    }
+
+    #[test]
+    fn streaming_matches_one_shot_and_key_matters() {
+        let enigma = BinaryEnigma::from_passphrase("stream");
+        let data: Vec<u8> = (0..1000u32).map(|i| (i % 7) as u8).collect();
+        let expected = enigma.encrypt(&data).unwrap();
+
+        let mut encryptor = enigma.encryptor();
+        let mut streamed = Vec::new();
+        for chunk in data.chunks(77) {
+            streamed.extend(encryptor.update(chunk).unwrap());
+        }
+        streamed.extend(encryptor.finalize().unwrap());
+
+        assert_eq!(streamed, expected);
+        assert_eq!(
+            BinaryEnigma::from_passphrase("stream")
+                .encrypt(&data)
+                .unwrap(),
+            expected
+        );
+        assert_ne!(
+            BinaryEnigma::from_passphrase("Stream")
+                .encrypt(&data)
+                .unwrap(),
+            expected
+        );
+    }
+}
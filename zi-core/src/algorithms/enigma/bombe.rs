@@ -0,0 +1,385 @@
+//! Turing–Welchman bombe: a crib placed against the ciphertext gives a menu
+//! of letter pairs the machine swaps at known steps. For every rotor order
+//! and start position the bombe assumes a plugboard partner for the most
+//! connected menu letter and follows the consequences through the menu,
+//! with Welchman's diagonal board adding the reverse of every conclusion. A
+//! contradiction rules the assumption out, anything else is a stop.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::anyhow;
+
+use crate::config::EnigmaConfig;
+
+use super::{
+    alg::{Enigma, EnigmaMachine},
+    analysis::{letters, plug_pairs, SearchSpace, Setting},
+    reflector::Reflector,
+    rotor_assembly::RotorAssembly,
+};
+
+/// A letter pair of the menu: at `step` the machine turns `plain` into
+/// `cipher`, or the other way round
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Edge {
+    pub plain: u8,
+    pub cipher: u8,
+    /// Key presses from the start of the message, the first one is 0
+    pub step: usize,
+}
+
+/// A rotor order and start position the menu does not contradict, ring
+/// settings at zero like on the real bombe
+#[derive(Clone)]
+pub struct Stop {
+    /// Plugboard holds the pairs the stop implies, letters that turned out
+    /// unplugged are left out
+    pub config: EnigmaConfig,
+}
+
+// The config is left out, it is key material
+impl std::fmt::Debug for Stop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Stop").finish_non_exhaustive()
+    }
+}
+
+/// Offsets at which `crib` fits under `ciphertext`, which excludes those
+/// where a letter would encrypt to itself
+pub fn possible_offsets(ciphertext: &str, crib: &str) -> Vec<usize> {
+    let text = letters(ciphertext);
+    let crib = letters(crib);
+
+    if crib.is_empty() || crib.len() > text.len() {
+        return Vec::new();
+    }
+
+    (0..=text.len() - crib.len())
+        .filter(|&offset| crib.iter().zip(&text[offset..]).all(|(p, c)| p != c))
+        .collect()
+}
+
+/// The menu of `crib` placed at letter `offset` of `ciphertext`
+pub fn menu(ciphertext: &str, crib: &str, offset: usize) -> anyhow::Result<Vec<Edge>> {
+    let text = letters(ciphertext);
+    let crib = letters(crib);
+
+    if crib.is_empty() {
+        return Err(anyhow!("The crib has no letters"));
+    }
+    if offset + crib.len() > text.len() {
+        return Err(anyhow!("The crib runs past the end of the ciphertext"));
+    }
+
+    crib.iter()
+        .zip(&text[offset..])
+        .enumerate()
+        .map(|(i, (&plain, &cipher))| {
+            if plain == cipher {
+                return Err(anyhow!(
+                    "At offset {} the crib has '{}' encrypt to itself",
+                    offset,
+                    plain as char
+                ));
+            }
+
+            Ok(Edge {
+                plain,
+                cipher,
+                step: offset + i,
+            })
+        })
+        .collect()
+}
+
+/// Independent loops of the menu, a bombe needs about three to avoid a
+/// flood of false stops
+pub fn loops(menu: &[Edge]) -> usize {
+    // Union-find over letters, every edge that joins an already connected
+    // pair closes a loop
+    let mut parent: [usize; 26] = std::array::from_fn(|i| i);
+
+    fn root(parent: &mut [usize; 26], mut letter: usize) -> usize {
+        while parent[letter] != letter {
+            parent[letter] = parent[parent[letter]];
+            letter = parent[letter];
+        }
+        letter
+    }
+
+    menu.iter()
+        .filter(|edge| {
+            let a = root(&mut parent, (edge.plain - b'a') as usize);
+            let b = root(&mut parent, (edge.cipher - b'a') as usize);
+            parent[a] = b;
+            a == b
+        })
+        .count()
+}
+
+/// Run the bombe over every rotor order of `space`. `progress` gets the done
+/// fraction, setting `cancel` stops the run with an error.
+pub fn run(
+    ciphertext: &str,
+    crib: &str,
+    offset: usize,
+    space: &SearchSpace,
+    cancel: &AtomicBool,
+    mut progress: impl FnMut(f32),
+) -> anyhow::Result<Vec<Stop>> {
+    let menu = menu(ciphertext, crib, offset)?;
+    space.validate()?;
+
+    let orders = space.orders();
+    let connections = connections(&menu);
+    let test_letter = (0..26)
+        .max_by_key(|&letter| connections[letter].len())
+        .unwrap();
+    let last_step = menu.iter().map(|edge| edge.step).max().unwrap();
+
+    let mut stops = Vec::new();
+
+    for (done, &order) in orders.iter().enumerate() {
+        let zero = Setting {
+            order,
+            rings: [0; 3],
+            positions: [0; 3],
+        };
+        let machine =
+            Enigma::try_new(&zero.config(space, String::new()), EnigmaMachine::M3)?.simulator();
+        let mut scrambler = Scrambler::new(machine.rotor_assembly, &machine.reflector, last_step);
+
+        for left in 0..26 {
+            if cancel.load(Ordering::Relaxed) {
+                return Err(anyhow!("The bombe was cancelled"));
+            }
+
+            for middle in 0..26 {
+                for right in 0..26 {
+                    let setting = Setting {
+                        positions: [left, middle, right],
+                        ..zero
+                    };
+                    scrambler.start_at(setting.positions);
+
+                    for partner in 0..26 {
+                        if let Some(plugboard) =
+                            test_hypothesis(&connections, &mut scrambler, test_letter, partner)
+                        {
+                            let pairs = plug_pairs(&plugboard);
+                            stops.push(Stop {
+                                config: setting.config(space, pairs),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        progress((done + 1) as f32 / orders.len() as f32);
+    }
+
+    return Ok(stops);
+}
+
+/// For every letter index, the letters it shares a menu edge with and the
+/// step of that edge
+fn connections(menu: &[Edge]) -> [Vec<(usize, usize)>; 26] {
+    let mut connections: [Vec<(usize, usize)>; 26] = Default::default();
+
+    for edge in menu {
+        let (plain, cipher) = ((edge.plain - b'a') as usize, (edge.cipher - b'a') as usize);
+        connections[plain].push((cipher, edge.step));
+        connections[cipher].push((plain, edge.step));
+    }
+
+    connections
+}
+
+/// The machine without its plugboard at every step up to the end of the
+/// crib, worked out only for the letters the bombe asks about
+struct Scrambler<'a> {
+    rotors: RotorAssembly,
+    reflector: &'a Reflector,
+    /// Rotor positions at every step
+    steps: Vec<[usize; 3]>,
+    /// Letter index plus one, 0 until it is needed
+    cache: Vec<[u8; 26]>,
+}
+
+impl<'a> Scrambler<'a> {
+    fn new(rotors: RotorAssembly, reflector: &'a Reflector, last_step: usize) -> Scrambler<'a> {
+        Scrambler {
+            rotors,
+            reflector,
+            steps: vec![[0; 3]; last_step + 1],
+            cache: vec![[0; 26]; last_step + 1],
+        }
+    }
+
+    /// Rewind to start positions, the rings stay at zero
+    fn start_at(&mut self, positions: [u8; 3]) {
+        for (rotor, &position) in self.rotors.rotors.iter_mut().zip(&positions) {
+            rotor.position = position as usize;
+        }
+
+        for step in self.steps.iter_mut() {
+            self.rotors.rotate();
+            *step = self.rotors.rotors.each_ref().map(|rotor| rotor.position);
+        }
+
+        self.cache.fill([0; 26]);
+    }
+
+    fn scramble(&mut self, step: usize, letter: usize) -> usize {
+        if self.cache[step][letter] == 0 {
+            for (rotor, &position) in self.rotors.rotors.iter_mut().zip(&self.steps[step]) {
+                rotor.position = position;
+            }
+
+            let rotors = &self.rotors;
+            let out = rotors.get_output_inverse(
+                self.reflector
+                    .reflect(rotors.get_output(b'a' + letter as u8)),
+            );
+
+            // The scrambler is an involution, so this fills two entries
+            let out = (out - b'a') as usize;
+            self.cache[step][letter] = out as u8 + 1;
+            self.cache[step][out] = letter as u8 + 1;
+        }
+
+        (self.cache[step][letter] - 1) as usize
+    }
+}
+
+/// Follow "`test_letter` is plugged to `partner`" through the menu. Returns
+/// the implied plugboard wiring, or `None` on a contradiction.
+fn test_hypothesis(
+    connections: &[Vec<(usize, usize)>; 26],
+    scrambler: &mut Scrambler,
+    test_letter: usize,
+    partner: usize,
+) -> Option<[u8; 26]> {
+    // partners[a] is the plugboard partner of letter a, once known
+    let mut partners: [Option<usize>; 26] = [None; 26];
+    let mut pending = vec![(test_letter, partner)];
+
+    while let Some((letter, plugged)) = pending.pop() {
+        // The diagonal board: a plugged to b also means b plugged to a
+        for (a, b) in [(letter, plugged), (plugged, letter)] {
+            match partners[a] {
+                Some(known) if known == b => continue,
+                Some(_) => return None,
+                None => partners[a] = Some(b),
+            }
+
+            // Across an edge the current runs through the unplugged machine
+            for &(other, step) in &connections[a] {
+                pending.push((other, scrambler.scramble(step, b)));
+            }
+        }
+    }
+
+    Some(std::array::from_fn(|letter| {
+        b'a' + partners[letter].unwrap_or(letter) as u8
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicBool;
+
+    use super::{loops, menu, possible_offsets, run};
+    use crate::algorithms::enigma::alg::{Enigma, EnigmaMachine};
+    use crate::algorithms::enigma::{analysis::SearchSpace, catalogue, catalogue::Component};
+    use crate::algorithms::Algorithm;
+    use crate::config::{EnigmaConfig, RotorConfig};
+
+    const PLAINTEXT: &[u8] = b"wettervorhersagefuerdiebiskayaregenundstarkerwindausnordwest";
+    const CRIB: &str = "wettervorhersagefuerdiebiskaya";
+
+    fn key() -> EnigmaConfig {
+        let [first, second, third] = [2, 0, 1].map(|i| catalogue::ROTORS[i]);
+        let rotor = |component: Component, position| RotorConfig {
+            wiring: component.wiring.to_owned(),
+            notches: component.notch_positions(),
+            ringstellung: 0,
+            position,
+        };
+
+        EnigmaConfig {
+            reflector: catalogue::UKW_B.to_owned(),
+            rotors: [rotor(first, 3), rotor(second, 14), rotor(third, 22)],
+            plugboard: "eq rx".to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn offsets_exclude_letters_encrypting_to_themselves() {
+        assert_eq!(possible_offsets("abcab", "ab"), vec![1, 2]);
+        assert!(menu("abcab", "ab", 3).is_err());
+        assert!(menu("abcab", "ab", 4).is_err());
+
+        let edges = menu("xacab", "ab", 0).unwrap();
+        assert_eq!(edges[1].plain, b'b');
+        assert_eq!(edges[1].step, 1);
+    }
+
+    #[test]
+    fn loops_are_counted() {
+        // a-b, b-c and c-a close one loop, d-e and e-x do not
+        let edges = menu("bcaex", "abcde", 0).unwrap();
+        assert_eq!(loops(&edges), 1);
+    }
+
+    #[test]
+    fn bombe_stops_at_the_key() {
+        let key = key();
+        let ciphertext = Enigma::try_new(&key, EnigmaMachine::M3)
+            .unwrap()
+            .encrypt(PLAINTEXT)
+            .unwrap();
+        let ciphertext = String::from_utf8(ciphertext).unwrap();
+
+        assert!(possible_offsets(&ciphertext, CRIB).contains(&0));
+        assert!(loops(&menu(&ciphertext, CRIB, 0).unwrap()) >= 3);
+
+        let space = SearchSpace {
+            rotors: catalogue::ROTORS[..3].to_vec(),
+            reflector: catalogue::REFLECTORS[1],
+        };
+        let stops = run(
+            &ciphertext,
+            CRIB,
+            0,
+            &space,
+            &AtomicBool::new(false),
+            |_| {},
+        )
+        .unwrap();
+
+        let stop = stops
+            .iter()
+            .find(|stop| {
+                stop.config.rotors[0].wiring == key.rotors[0].wiring
+                    && stop.config.rotors[1].wiring == key.rotors[1].wiring
+                    && stop
+                        .config
+                        .rotors
+                        .iter()
+                        .zip(&key.rotors)
+                        .all(|(found, rotor)| found.position == rotor.position)
+            })
+            .expect("no stop at the key");
+
+        // Every pair the stop found is a real one
+        assert!(stop
+            .config
+            .plugboard
+            .split_whitespace()
+            .all(|pair| pair == "eq" || pair == "rx"));
+        assert!(stops.len() < 20, "{} stops", stops.len());
+    }
+}
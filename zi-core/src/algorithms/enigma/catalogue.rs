@@ -0,0 +1,183 @@
+//! Wirings of historical Enigma components
+
+use std::fmt::Display;
+
+/// Wide reflector UKW-B, used by the M3
+pub const UKW_B: &str = "yruhqsldpxngokmiebfzcwvjat";
+/// Wide reflector UKW-C, used by the M3
+pub const UKW_C: &str = "fvpjiaoyedrzxwgctkuqsbnmhl";
+
+/// Thin reflector UKW-b, used by the M4 together with a Greek wheel
+pub const UKW_B_THIN: &str = "enkqauywjicopblmdxzvfthrgs";
+/// Thin reflector UKW-c, used by the M4 together with a Greek wheel
+pub const UKW_C_THIN: &str = "rdobjntkvehmlfcwzaxgyipsuq";
+
+/// Greek wheel Beta, the M4's fourth, non-stepping rotor
+pub const GREEK_BETA: &str = "leyjvcnixwpbqmdrtakzgfuhos";
+/// Greek wheel Gamma, the M4's fourth, non-stepping rotor
+pub const GREEK_GAMMA: &str = "fsokanuerhmbtiycwlqpzxvgjd";
+
+/// A named rotor or reflector. Reflectors and Greek wheels have no
+/// turnovers.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Component {
+    pub name: &'static str,
+    pub wiring: &'static str,
+    /// Window letters at which the rotor steps its left neighbour
+    pub turnovers: &'static str,
+}
+
+impl Component {
+    /// Pick list entry for hand-entered wirings, picking it keeps the fields
+    pub const CUSTOM: Component = Component {
+        name: "Custom",
+        wiring: "",
+        turnovers: "",
+    };
+
+    const fn new(name: &'static str, wiring: &'static str, turnovers: &'static str) -> Component {
+        Component {
+            name,
+            wiring,
+            turnovers,
+        }
+    }
+
+    /// Turnovers as the notches of a [`crate::config::RotorConfig`]
+    pub fn notch_positions(&self) -> Vec<u8> {
+        self.turnovers.bytes().map(notch_for_turnover).collect()
+    }
+
+    /// Notches as typed into a settings form, separated by spaces
+    pub fn notches(&self) -> String {
+        self.notch_positions()
+            .iter()
+            .map(|notch| notch.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl Display for Component {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+pub const ROTORS: [Component; 8] = [
+    Component::new("I", "ekmflgdqvzntowyhxuspaibrcj", "q"),
+    Component::new("II", "ajdksiruxblhwtmcqgznpyfvoe", "e"),
+    Component::new("III", "bdfhjlcprtxvznyeiwgakmusqo", "v"),
+    Component::new("IV", "esovpzjayquirhxlnftgkdcmwb", "j"),
+    Component::new("V", "vzbrgityupsdnhlxawmjqofeck", "z"),
+    Component::new("VI", "jpgvoumfyqbenhzrdkasxlictw", "zm"),
+    Component::new("VII", "nzjhgrcxmyswboufaivlpekqdt", "zm"),
+    Component::new("VIII", "fkqhtlxocbjspdzramewniuygv", "zm"),
+];
+
+pub const REFLECTORS: [Component; 3] = [
+    Component::new("UKW-A", "ejmzalyxvbwfcrquontspikhgd", ""),
+    Component::new("UKW-B", UKW_B, ""),
+    Component::new("UKW-C", UKW_C, ""),
+];
+
+pub const THIN_REFLECTORS: [Component; 2] = [
+    Component::new("UKW-b (thin)", UKW_B_THIN, ""),
+    Component::new("UKW-c (thin)", UKW_C_THIN, ""),
+];
+
+pub const GREEK_WHEELS: [Component; 2] = [
+    Component::new("Beta", GREEK_BETA, ""),
+    Component::new("Gamma", GREEK_GAMMA, ""),
+];
+
+/// `components` plus [`Component::CUSTOM`], for pick lists
+pub fn with_custom(components: &[Component]) -> Vec<Component> {
+    components
+        .iter()
+        .copied()
+        .chain(std::iter::once(Component::CUSTOM))
+        .collect()
+}
+
+/// Entry of `components` with this wiring (and notches, if given), or
+/// [`Component::CUSTOM`]
+pub fn identify(
+    components: &[Component],
+    wiring: Option<&str>,
+    notches: Option<&str>,
+) -> Component {
+    components
+        .iter()
+        .find(|component| {
+            Some(component.wiring) == wiring
+                && notches.is_none_or(|notches| {
+                    notches
+                        .split_whitespace()
+                        .eq(component.notches().split_whitespace())
+                })
+        })
+        .copied()
+        .unwrap_or(Component::CUSTOM)
+}
+
+/// `Rotor` counts notches so that the rotor is at its turnover when its
+/// position equals `notch + 19`, this converts a turnover window letter.
+fn notch_for_turnover(letter: u8) -> u8 {
+    (letter - b'a' + 7) % 26
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{identify, Component, GREEK_WHEELS, REFLECTORS, ROTORS, THIN_REFLECTORS};
+    use crate::algorithms::enigma::utils;
+
+    #[test]
+    fn wirings_are_permutations() {
+        for component in ROTORS
+            .iter()
+            .chain(&REFLECTORS)
+            .chain(&THIN_REFLECTORS)
+            .chain(&GREEK_WHEELS)
+        {
+            assert!(
+                utils::is_shuffled_alphabet(component.wiring),
+                "{}",
+                component
+            );
+        }
+    }
+
+    #[test]
+    fn reflectors_are_fixed_point_free_involutions() {
+        for reflector in REFLECTORS.iter().chain(&THIN_REFLECTORS) {
+            let wiring = reflector.wiring.as_bytes();
+            for (i, &out) in wiring.iter().enumerate() {
+                assert_ne!(out, b'a' + i as u8, "{}", reflector);
+                assert_eq!(
+                    wiring[(out - b'a') as usize],
+                    b'a' + i as u8,
+                    "{}",
+                    reflector
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn identify_matches_wiring_and_notches() {
+        let vi = ROTORS[5];
+        assert_eq!(vi.notches(), "6 19");
+
+        assert_eq!(identify(&ROTORS, Some(vi.wiring), Some("6  19")), vi);
+        assert_eq!(
+            identify(&ROTORS, Some(vi.wiring), Some("6")),
+            Component::CUSTOM
+        );
+        assert_eq!(
+            identify(&REFLECTORS, Some(REFLECTORS[0].wiring), None),
+            REFLECTORS[0]
+        );
+        assert_eq!(identify(&REFLECTORS, None, None), Component::CUSTOM);
+    }
+}
@@ -1,7 +1,12 @@
 pub mod alg;
+pub mod analysis;
+pub mod binary;
+pub mod bombe;
+pub mod catalogue;
 mod plugboard;
 mod reflector;
 mod rotor;
 mod rotor_assembly;
+pub mod simulator;
 mod tests;
 mod utils;
@@ -1,8 +1,8 @@
-use super::utils::to_u8_array_26;
+use super::utils::{first_symbol, to_u8_array_26};
 
 #[derive(Clone)]
-pub struct Plugboard {
-    wiring: [u8; 26],
+pub struct Plugboard<const N: usize = 26> {
+    wiring: [u8; N],
 }
 
 impl Plugboard {
@@ -11,11 +11,8 @@ impl Plugboard {
 
         let mut wiring = to_u8_array_26("abcdefghijklmnopqrstuvwxyz");
 
-        if pairs.is_empty() {
-            return Plugboard { wiring };
-        }
-
-        pairs.split(' ').for_each(|pair| {
+        // Split like `Enigma::validate` checks the pairs
+        pairs.split_whitespace().for_each(|pair| {
             assert_eq!(pair.len(), 2);
 
             let pair = pair.to_ascii_lowercase();
@@ -29,12 +26,18 @@ impl Plugboard {
 
         Plugboard { wiring }
     }
+}
 
-    pub fn get_output(&self, letter: u8) -> u8 {
-        assert!(letter.is_ascii_lowercase());
+impl<const N: usize> Plugboard<N> {
+    /// `wiring` has to be an involution
+    pub fn from_wiring(wiring: [u8; N]) -> Self {
+        Plugboard { wiring }
+    }
 
-        let index = letter - b'a';
+    pub fn get_output(&self, letter: u8) -> u8 {
+        let index = letter.wrapping_sub(first_symbol(N)) as usize;
+        assert!(index < N);
 
-        return self.wiring[index as usize];
+        return self.wiring[index];
     }
 }
@@ -1,14 +1,14 @@
+use super::utils::first_symbol;
+
 #[derive(Debug, Clone)]
-pub struct Reflector {
-    pub wiring: [u8; 26],
+pub struct Reflector<const N: usize = 26> {
+    pub wiring: [u8; N],
 }
 
-impl Reflector {
+impl<const N: usize> Reflector<N> {
     pub fn reflect(&self, letter: u8) -> u8 {
-        assert!(letter.is_ascii_lowercase());
-
-        let index = (letter - b'a') as usize;
-        assert!(index < 26);
+        let index = letter.wrapping_sub(first_symbol(N)) as usize;
+        assert!(index < N);
 
         return self.wiring[index];
 
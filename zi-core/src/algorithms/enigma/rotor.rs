@@ -0,0 +1,88 @@
+use super::alg::Mechanics;
+use super::utils::first_symbol;
+
+/// Rotor over an `N`-symbol alphabet, see [`first_symbol`]
+#[derive(Debug, Clone)]
+pub struct Rotor<const N: usize = 26> {
+    pub wiring: [u8; N],
+    /// Rotors VI-VIII have two notches, Greek wheels none
+    pub notches: Vec<u8>,
+    pub ringstellung: u8,
+    pub position: usize,
+}
+
+impl<const N: usize> Rotor<N> {
+    /// [`Rotor::forward`] with the [`Mechanics::Legacy`] the rotor tests were
+    /// written against
+    #[cfg(test)]
+    pub fn get_output(&self, letter: u8) -> u8 {
+        return self.forward(Mechanics::Legacy, letter);
+    }
+
+    /// [`Rotor::backward`] by searching the wiring, with the
+    /// [`Mechanics::Legacy`] the rotor tests were written against
+    #[cfg(test)]
+    pub fn get_output_inverted(&self, letter: u8) -> u8 {
+        let index = self.wiring.iter().position(|el| *el == letter).unwrap();
+
+        return self.exit(index);
+    }
+
+    /// Signal on its way to the reflector
+    pub fn forward(&self, mechanics: Mechanics, letter: u8) -> u8 {
+        let index = (Self::index(letter) + self.shift()) % N;
+
+        return match mechanics {
+            Mechanics::Legacy => self.wiring[index],
+            Mechanics::Historical => self.exit(Self::index(self.wiring[index])),
+        };
+    }
+
+    /// Signal on its way back from the reflector, `inverse` is the inverse of
+    /// `wiring` looked up in advance, which matters for large alphabets
+    pub fn backward(&self, mechanics: Mechanics, inverse: &[u8; N], letter: u8) -> u8 {
+        let index = match mechanics {
+            Mechanics::Legacy => Self::index(letter),
+            Mechanics::Historical => (Self::index(letter) + self.shift()) % N,
+        };
+
+        return self.exit(inverse[index] as usize);
+    }
+
+    /// Inverse of `wiring`, as indices
+    pub fn inverse_wiring(&self) -> [u8; N] {
+        let mut inverse = [0u8; N];
+        for (index, &symbol) in self.wiring.iter().enumerate() {
+            inverse[Self::index(symbol)] = index as u8;
+        }
+
+        return inverse;
+    }
+
+    pub fn is_aligned(&self) -> bool {
+        return self
+            .notches
+            .iter()
+            .any(|&notch| (self.position % N) == (notch as usize + N - 7) % N);
+    }
+
+    fn index(letter: u8) -> usize {
+        let index = letter.wrapping_sub(first_symbol(N)) as usize;
+        assert!(index < N);
+
+        return index;
+    }
+
+    /// How far the wiring is turned against the fixed contacts: the window
+    /// position less the ring setting
+    fn shift(&self) -> usize {
+        return (self.position % N + N - self.ringstellung as usize % N) % N;
+    }
+
+    /// Takes the turn of the wiring off a contact on the way out
+    fn exit(&self, index: usize) -> u8 {
+        let index = (index + N - self.shift()) % N;
+
+        return first_symbol(N) + (index as u8);
+    }
+}
@@ -0,0 +1,117 @@
+use super::{alg::Mechanics, rotor::Rotor};
+
+// pub trait RotorAssembly {
+//     fn rotate(&mut self);
+//     fn get_output(&self, letter: u8) -> u8;
+//     fn get_output_inverse(&self, letter: u8) -> u8;
+// }
+
+/// The three stepping rotors, left to right, plus the M4's Greek wheel which
+/// sits between the left rotor and the reflector and never steps.
+#[derive(Clone)]
+pub struct RotorAssembly<const N: usize = 26> {
+    pub(super) rotors: [Rotor<N>; 3],
+    pub(super) greek_wheel: Option<Rotor<N>>,
+    /// Inverse wirings of `rotors` and `greek_wheel`, in that order
+    inverses: Vec<[u8; N]>,
+    mechanics: Mechanics,
+}
+
+impl<const N: usize> RotorAssembly<N> {
+    pub fn new(rotors: [Rotor<N>; 3]) -> RotorAssembly<N> {
+        RotorAssembly {
+            inverses: rotors.iter().map(Rotor::inverse_wiring).collect(),
+            rotors,
+            greek_wheel: None,
+            mechanics: Mechanics::Legacy,
+        }
+    }
+
+    pub fn with_mechanics(mut self, mechanics: Mechanics) -> RotorAssembly<N> {
+        self.mechanics = mechanics;
+
+        self
+    }
+
+    pub fn with_greek_wheel(rotors: [Rotor<N>; 3], greek_wheel: Rotor<N>) -> RotorAssembly<N> {
+        let mut assembly = RotorAssembly::new(rotors);
+        assembly.inverses.push(greek_wheel.inverse_wiring());
+        assembly.greek_wheel = Some(greek_wheel);
+
+        assembly
+    }
+
+    /// Steps like the pawls of the real machine: the right rotor always, the
+    /// middle one when the right or the middle itself is at its notch (the
+    /// double step), the left one only when the middle is at its notch. With
+    /// [`Mechanics::Legacy`] the left one also steps at its own notch.
+    pub fn rotate(&mut self) {
+        let middle_aligned = self.rotors[1].is_aligned();
+        let left_aligned = self.mechanics == Mechanics::Legacy && self.rotors[0].is_aligned();
+
+        if middle_aligned || left_aligned {
+            self.rotors[0].position += 1;
+        }
+        if middle_aligned || self.rotors[2].is_aligned() {
+            self.rotors[1].position += 1;
+        }
+        self.rotors[2].position += 1;
+    }
+
+    pub fn get_output(&self, letter: u8) -> u8 {
+        let l1 = self.rotors[2].forward(self.mechanics, letter);
+        let l2 = self.rotors[1].forward(self.mechanics, l1);
+        let l3 = self.rotors[0].forward(self.mechanics, l2);
+
+        return match &self.greek_wheel {
+            Some(greek_wheel) => greek_wheel.forward(self.mechanics, l3),
+            None => l3,
+        };
+    }
+
+    pub fn get_output_inverse(&self, letter: u8) -> u8 {
+        let letter = match &self.greek_wheel {
+            Some(greek_wheel) => greek_wheel.backward(self.mechanics, &self.inverses[3], letter),
+            None => letter,
+        };
+
+        let l1 = self.rotors[0].backward(self.mechanics, &self.inverses[0], letter);
+        let l2 = self.rotors[1].backward(self.mechanics, &self.inverses[1], l1);
+        let l3 = self.rotors[2].backward(self.mechanics, &self.inverses[2], l2);
+
+        return l3;
+    }
+
+    /// Letter after each rotor on the way to the reflector: right, middle,
+    /// left, then the Greek wheel if there is one
+    pub fn get_output_stages(&self, letter: u8) -> Vec<u8> {
+        let mut stages = Vec::with_capacity(4);
+        let mut letter = letter;
+
+        for rotor in self.rotors.iter().rev().chain(&self.greek_wheel) {
+            letter = rotor.forward(self.mechanics, letter);
+            stages.push(letter);
+        }
+
+        return stages;
+    }
+
+    /// Letter after each rotor on the way back from the reflector, the
+    /// reverse order of [`RotorAssembly::get_output_stages`]
+    pub fn get_output_inverse_stages(&self, letter: u8) -> Vec<u8> {
+        let mut stages = Vec::with_capacity(4);
+        let mut letter = letter;
+
+        if let Some(greek_wheel) = &self.greek_wheel {
+            letter = greek_wheel.backward(self.mechanics, &self.inverses[3], letter);
+            stages.push(letter);
+        }
+
+        for i in 0..3 {
+            letter = self.rotors[i].backward(self.mechanics, &self.inverses[i], letter);
+            stages.push(letter);
+        }
+
+        return stages;
+    }
+}
@@ -0,0 +1,152 @@
+use super::{plugboard::Plugboard, reflector::Reflector, rotor_assembly::RotorAssembly};
+
+/// An Enigma driven one key at a time, for the simulator page. Unlike
+/// [`super::alg::EnigmaProcessor`] it exposes the rotor positions and the
+/// route each key press takes.
+#[derive(Clone)]
+pub struct EnigmaSimulator {
+    pub(super) rotor_assembly: RotorAssembly,
+    pub(super) reflector: Reflector,
+    pub(super) plugboard: Plugboard,
+}
+
+/// Letters seen by the current of one key press, from key to lamp
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignalPath {
+    pub key: u8,
+    /// After the plugboard on the way in
+    pub plugboard_in: u8,
+    /// After the right, middle and left rotor, then the Greek wheel if any
+    pub rotors_in: Vec<u8>,
+    pub reflector: u8,
+    /// After the Greek wheel if any, then the left, middle and right rotor
+    pub rotors_out: Vec<u8>,
+    /// After the plugboard on the way out, the letter that lights up
+    pub lamp: u8,
+}
+
+impl EnigmaSimulator {
+    /// Press a lowercase letter key: step the rotors, then follow the signal
+    pub fn press(&mut self, key: u8) -> SignalPath {
+        self.rotor_assembly.rotate();
+
+        let plugboard_in = self.plugboard.get_output(key);
+        let rotors_in = self.rotor_assembly.get_output_stages(plugboard_in);
+        let reflector = self.reflector.reflect(*rotors_in.last().unwrap());
+        let rotors_out = self.rotor_assembly.get_output_inverse_stages(reflector);
+        let lamp = self.plugboard.get_output(*rotors_out.last().unwrap());
+
+        SignalPath {
+            key,
+            plugboard_in,
+            rotors_in,
+            reflector,
+            rotors_out,
+            lamp,
+        }
+    }
+
+    /// Letters in the rotor windows, left to right, starting with the Greek
+    /// wheel on the M4
+    pub fn windows(&self) -> Vec<char> {
+        self.rotor_assembly
+            .greek_wheel
+            .iter()
+            .chain(&self.rotor_assembly.rotors)
+            .map(|rotor| (b'A' + (rotor.position % 26) as u8) as char)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithms::enigma::alg::{Enigma, EnigmaMachine};
+    use crate::algorithms::enigma::catalogue;
+    use crate::algorithms::Algorithm;
+    use crate::config::{EnigmaConfig, RotorConfig};
+
+    fn enigma(positions: [u8; 3]) -> Enigma {
+        let config = EnigmaConfig {
+            reflector: catalogue::UKW_B.to_string(),
+            rotors: std::array::from_fn(|i| RotorConfig {
+                wiring: catalogue::ROTORS[i].wiring.to_string(),
+                notches: catalogue::ROTORS[i].notch_positions(),
+                ringstellung: 0,
+                position: positions[i],
+            }),
+            plugboard: "ab cd".to_string(),
+            ..Default::default()
+        };
+
+        Enigma::try_new(&config, EnigmaMachine::M3).unwrap()
+    }
+
+    #[test]
+    fn key_presses_match_encryption() {
+        let enigma = enigma([0, 0, 0]);
+        let mut simulator = enigma.simulator();
+        let input = b"helloworldthisisatest";
+
+        let lamps: Vec<u8> = input.iter().map(|&key| simulator.press(key).lamp).collect();
+
+        assert_eq!(lamps, enigma.encrypt(input).unwrap());
+    }
+
+    #[test]
+    fn signal_path_is_consistent() {
+        let mut simulator = enigma([0, 0, 0]).simulator();
+
+        let path = simulator.press(b'a');
+
+        // "ab" is plugged, so the current enters the rotors at 'b'
+        assert_eq!(path.plugboard_in, b'b');
+        assert_eq!(path.rotors_in.len(), 3);
+        assert_eq!(path.rotors_out.len(), 3);
+        assert_ne!(path.lamp, b'a');
+
+        // Pressing the lamp letter at the same positions leads back to the key
+        let mut again = enigma([0, 0, 0]).simulator();
+        assert_eq!(again.press(path.lamp).lamp, b'a');
+    }
+
+    #[test]
+    fn windows_show_double_stepping() {
+        // Rotor II turns over at E and rotor III at V, so starting from ADU
+        // the middle rotor steps on two key presses in a row and takes the
+        // left rotor along on the second one
+        let mut simulator = enigma([0, 3, 20]).simulator();
+
+        let mut windows = vec![simulator.windows().into_iter().collect::<String>()];
+        for _ in 0..4 {
+            simulator.press(b'a');
+            windows.push(simulator.windows().into_iter().collect());
+        }
+
+        assert_eq!(windows, ["ADU", "ADV", "AEW", "BFX", "BFY"]);
+    }
+
+    #[test]
+    fn left_rotor_only_steps_with_the_middle_one() {
+        // Rotor I turns over at Q, but nothing to its left would step
+        let mut simulator = enigma([16, 0, 0]).simulator();
+
+        simulator.press(b'a');
+        assert_eq!(simulator.windows().into_iter().collect::<String>(), "QAB");
+        simulator.press(b'a');
+        assert_eq!(simulator.windows().into_iter().collect::<String>(), "QAC");
+    }
+
+    #[test]
+    fn lamps_match_a_real_machine() {
+        // Without plugs AAAAA lights BDZGO, "ab cd" swaps B into the rotors
+        // and B and D on their way to the lamps
+        let mut simulator = enigma([0, 0, 0]).simulator();
+
+        let lamps: Vec<u8> = b"bbbbb"
+            .iter()
+            .map(|&key| simulator.press(key).lamp)
+            .collect();
+
+        assert_eq!(lamps, b"aczgo");
+    }
+}
@@ -1,3 +1,13 @@
+/// First symbol of an `N`-symbol alphabet. The 26-symbol alphabet is the
+/// lowercase letters, any other size counts raw byte values from 0.
+pub const fn first_symbol(n: usize) -> u8 {
+    if n == 26 {
+        b'a'
+    } else {
+        0
+    }
+}
+
 pub const fn to_u8_array_26(s: &str) -> [u8; 26] {
     assert!(s.len() == 26);
 
@@ -0,0 +1,40 @@
+use zeroize::Zeroizing;
+
+use crate::hash::pbkdf2::pbkdf2;
+
+pub const SALT_LEN: usize = 16;
+pub const DEFAULT_ITERATIONS: u32 = 100_000;
+/// Most iterations a stored count may ask for. Counts are read before
+/// anything can be checked, so a tampered one must not keep the KDF busy for
+/// hours.
+pub const MAX_ITERATIONS: u32 = 10 * DEFAULT_ITERATIONS;
+
+/// Cipher key bytes out of [`KeyDerivation::derive`]
+pub type DerivedKey = Zeroizing<Vec<u8>>;
+
+/// Salt and cost of the PBKDF2-HMAC-Tiger run that turns a typed key into
+/// cipher key bytes. Neither is secret, both travel in the container header.
+#[derive(Clone, PartialEq, Debug)]
+pub struct KeyDerivation {
+    pub salt: [u8; SALT_LEN],
+    pub iterations: u32,
+}
+
+impl KeyDerivation {
+    /// Fresh salt, so the same password gives a different key in every file
+    pub fn random(iterations: u32) -> KeyDerivation {
+        let mut salt = [0u8; SALT_LEN];
+        getrandom::getrandom(&mut salt).expect("System random number generator is unavailable");
+
+        KeyDerivation { salt, iterations }
+    }
+
+    pub fn derive(&self, password: &str, len: usize) -> DerivedKey {
+        Zeroizing::new(pbkdf2(
+            password.as_bytes(),
+            &self.salt,
+            self.iterations,
+            len,
+        ))
+    }
+}
@@ -0,0 +1,569 @@
+use std::fmt::Display;
+
+use crate::algorithms::modes::alg::BlockMode;
+use crate::algorithms::{Algorithm, BlockCipher, StreamProcessor, StreamingAlgorithm};
+use crate::config::XxteaConfig;
+use anyhow::{anyhow, Ok};
+use zeroize::Zeroize;
+
+const DELTA: u32 = 0x9e3779b9;
+
+/// Plaintext is split into chunks of this size and every chunk is encrypted
+/// as a separate XXTEA message (data + length word). Inputs that fit into a
+/// single chunk produce the same output as before chunking was introduced.
+const CHUNK_SIZE: usize = 64 * 1024;
+const ENCRYPTED_CHUNK_SIZE: usize = CHUNK_SIZE + 4;
+
+pub struct Xxtea {
+    key: [u32; 4],
+}
+
+impl Xxtea {
+    pub fn new(config: &XxteaConfig) -> Xxtea {
+        Xxtea::with_key(config.key.as_bytes())
+    }
+
+    /// Uses the first 16 bytes of `key`, shorter keys are zero-padded
+    pub fn with_key(key: &[u8]) -> Xxtea {
+        Xxtea {
+            key: fix_key(&to_u32(key, false)),
+        }
+    }
+}
+
+impl Drop for Xxtea {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+impl Algorithm for Xxtea {
+    fn encrypt(&self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        run_to_end(self.encryptor(), data)
+    }
+
+    fn decrypt(&self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        run_to_end(self.decryptor(), data)
+    }
+}
+
+impl StreamingAlgorithm for Xxtea {
+    fn encryptor(&self) -> Box<dyn StreamProcessor + Send> {
+        Box::new(XxteaEncryptor {
+            key: self.key,
+            buffer: Vec::with_capacity(CHUNK_SIZE),
+        })
+    }
+
+    fn decryptor(&self) -> Box<dyn StreamProcessor + Send> {
+        Box::new(XxteaDecryptor {
+            key: self.key,
+            buffer: Vec::with_capacity(ENCRYPTED_CHUNK_SIZE),
+        })
+    }
+}
+
+struct XxteaEncryptor {
+    key: [u32; 4],
+    buffer: Vec<u8>,
+}
+
+impl XxteaEncryptor {
+    fn encrypt_chunk(&self, chunk: &[u8]) -> anyhow::Result<Vec<u8>> {
+        to_bytes(&encrypt_(to_u32(chunk, true), &self.key), false)
+    }
+}
+
+impl StreamProcessor for XxteaEncryptor {
+    fn update(&mut self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        self.buffer.extend_from_slice(data);
+
+        let mut res = Vec::new();
+
+        // The last (possibly full) chunk is kept until finalize, so an input
+        // whose length is a multiple of CHUNK_SIZE doesn't get an extra empty chunk
+        while self.buffer.len() > CHUNK_SIZE {
+            let rest = self.buffer.split_off(CHUNK_SIZE);
+            let chunk = std::mem::replace(&mut self.buffer, rest);
+            res.extend(self.encrypt_chunk(&chunk)?);
+        }
+
+        Ok(res)
+    }
+
+    fn finalize(&mut self) -> anyhow::Result<Vec<u8>> {
+        // XXTEA needs at least two words, so empty input stays empty
+        if self.buffer.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let chunk = std::mem::take(&mut self.buffer);
+        self.encrypt_chunk(&chunk)
+    }
+}
+
+struct XxteaDecryptor {
+    key: [u32; 4],
+    buffer: Vec<u8>,
+}
+
+impl StreamProcessor for XxteaDecryptor {
+    fn update(&mut self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        self.buffer.extend_from_slice(data);
+
+        let mut res = Vec::new();
+
+        while self.buffer.len() > ENCRYPTED_CHUNK_SIZE {
+            let rest = self.buffer.split_off(ENCRYPTED_CHUNK_SIZE);
+            let chunk = std::mem::replace(&mut self.buffer, rest);
+            res.extend(decrypt_chunk(&chunk, &self.key)?);
+        }
+
+        Ok(res)
+    }
+
+    fn finalize(&mut self) -> anyhow::Result<Vec<u8>> {
+        if self.buffer.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let chunk = std::mem::take(&mut self.buffer);
+        decrypt_chunk(&chunk, &self.key)
+    }
+}
+
+fn decrypt_chunk(chunk: &[u8], key: &[u32; 4]) -> anyhow::Result<Vec<u8>> {
+    if !chunk.len().is_multiple_of(4) {
+        return Err(anyhow!("XXTEA ciphertext length must be a multiple of 4"));
+    }
+
+    to_bytes(&decrypt_(to_u32(chunk, false), key), true)
+}
+
+/// XXTEA modes offered in settings: the original whole-message XXTEA or a
+/// fixed-size block primitive run in one of the [`BlockMode`]s
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum XxteaMode {
+    #[default]
+    WholeMessage,
+    Block(BlockMode),
+}
+
+impl XxteaMode {
+    pub fn all() -> Vec<XxteaMode> {
+        std::iter::once(XxteaMode::WholeMessage)
+            .chain(BlockMode::ALL.into_iter().map(XxteaMode::Block))
+            .collect()
+    }
+}
+
+impl Display for XxteaMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            XxteaMode::WholeMessage => write!(f, "Whole message"),
+            XxteaMode::Block(mode) => write!(f, "{}", mode),
+        }
+    }
+}
+
+/// XXTEA over blocks of a fixed, configurable size
+pub struct XxteaBlock {
+    block_size: usize,
+    key: [u32; 4],
+}
+
+impl XxteaBlock {
+    pub fn try_new(config: &XxteaConfig) -> anyhow::Result<XxteaBlock> {
+        XxteaBlock::try_with_key(config, config.key.as_bytes())
+    }
+
+    /// Block size from `config`, key bytes from `key`
+    pub fn try_with_key(config: &XxteaConfig, key: &[u8]) -> anyhow::Result<XxteaBlock> {
+        let block_size = config.block_size;
+
+        if block_size < 8 {
+            return Err(anyhow!("Block Size must be 8 or more"));
+        }
+
+        if !block_size.is_multiple_of(4) {
+            return Err(anyhow!("Block Size must be a multiple of 4"));
+        }
+
+        return Ok(XxteaBlock {
+            block_size,
+            key: fix_key(&to_u32(key, false)),
+        });
+    }
+}
+
+impl Drop for XxteaBlock {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+impl BlockCipher for XxteaBlock {
+    fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    fn encrypt_block(&self, block: &mut [u8]) {
+        let encrypted = to_bytes(&encrypt_(to_u32(block, false), &self.key), false)
+            .expect("Conversion without length word can't fail");
+        block.copy_from_slice(&encrypted);
+    }
+
+    fn decrypt_block(&self, block: &mut [u8]) {
+        let decrypted = to_bytes(&decrypt_(to_u32(block, false), &self.key), false)
+            .expect("Conversion without length word can't fail");
+        block.copy_from_slice(&decrypted);
+    }
+}
+
+fn run_to_end(
+    mut processor: Box<dyn StreamProcessor + Send>,
+    data: &[u8],
+) -> anyhow::Result<Vec<u8>> {
+    let mut res = processor.update(data)?;
+    res.extend(processor.finalize()?);
+
+    Ok(res)
+}
+
+fn encrypt_(mut v: Vec<u32>, key: &[u32; 4]) -> Vec<u32> {
+    let length: u32 = v.len() as u32;
+
+    let n: u32 = length - 1;
+
+    let mut e: u32;
+    let mut y: u32;
+    let mut z = v[n as usize];
+    let mut q: u32 = 6 + 52 / length;
+
+    let mut sum: u32 = 0;
+
+    while q > 0 {
+        sum = sum.wrapping_add(DELTA);
+        e = sum >> 2 & 3;
+
+        for p in 0..n {
+            y = v[(p as usize) + 1];
+            v[p as usize] = v[p as usize].wrapping_add(mx(sum, y, z, p, e, key));
+            z = v[p as usize];
+        }
+
+        y = v[0];
+        v[n as usize] = v[n as usize].wrapping_add(mx(sum, y, z, n, e, key));
+        z = v[n as usize];
+        q -= 1;
+    }
+
+    return v;
+}
+
+fn decrypt_(mut v: Vec<u32>, key: &[u32; 4]) -> Vec<u32> {
+    let length: u32 = v.len() as u32;
+
+    let n: u32 = length - 1;
+
+    let mut e: u32;
+    let mut y: u32 = v[0];
+    let mut z;
+    let q: u32 = 6 + 52 / length;
+
+    let mut sum: u32 = q.wrapping_mul(DELTA);
+
+    while sum != 0 {
+        e = sum >> 2 & 3;
+        let mut p: usize = n as usize;
+
+        while p > 0 {
+            z = v[p - 1];
+            v[p] = v[p].wrapping_sub(mx(sum, y, z, p as u32, e, key));
+            y = v[p];
+            p -= 1;
+        }
+
+        z = v[n as usize];
+        v[0] = v[0].wrapping_sub(mx(sum, y, z, 0, e, key));
+        y = v[0];
+
+        sum = sum.wrapping_sub(DELTA);
+    }
+
+    return v;
+}
+
+fn mx(sum: u32, y: u32, z: u32, p: u32, e: u32, k: &[u32; 4]) -> u32 {
+    ((z >> 5 ^ y << 2).wrapping_add(y >> 3 ^ z << 4))
+        ^ ((sum ^ y).wrapping_add(k[(p & 3 ^ e) as usize] ^ z))
+}
+
+fn fix_key(key: &[u32]) -> [u32; 4] {
+    let mut out = [0u32; 4];
+
+    for (i, &val) in key.iter().take(4).enumerate() {
+        out[i] = val;
+    }
+
+    return out;
+}
+
+fn to_bytes(arr: &[u32], include_length: bool) -> anyhow::Result<Vec<u8>> {
+    let length: u32 = arr.len() as u32;
+
+    let mut bytes_count = length * 4;
+
+    if include_length {
+        let original_length: u32 = *arr
+            .last()
+            .ok_or(anyhow!("Decrypted data is missing the length word"))?;
+
+        {
+            // Checking validity, a wrong key or corrupted data lands here
+            bytes_count -= 4;
+            if original_length.saturating_add(3) < bytes_count || original_length > bytes_count {
+                return Err(anyhow!(
+                    "Decrypted data is inconsistent, wrong key or corrupted data"
+                ));
+            }
+        }
+
+        bytes_count = original_length;
+    }
+
+    let mut bytes: Vec<u8> = vec![0; bytes_count as usize];
+
+    for i in 0..bytes_count {
+        bytes[i as usize] = (arr[(i >> 2) as usize] >> ((i & 3) << 3)) as u8;
+    }
+
+    return Ok(bytes);
+}
+
+fn to_u32(bytes: &[u8], include_length: bool) -> Vec<u32> {
+    let length: u32 = bytes.len() as u32;
+    let n = length.div_ceil(4);
+
+    let mut output = vec![0; n as usize + if include_length { 1 } else { 0 }];
+    if include_length {
+        output[n as usize] = length;
+    }
+
+    for i in 0..length {
+        output[(i >> 2) as usize] |= (bytes[i as usize] as u32) << ((i & 3) << 3);
+    }
+
+    return output;
+}
+
+#[test]
+fn xxtea_1() {
+    let starting = "Hellouw".as_bytes();
+
+    let u32s = to_u32(starting, true);
+    let bytes = to_bytes(&u32s, true).unwrap();
+
+    assert_eq!(starting, bytes);
+
+    let u32s = to_u32(starting, false);
+    let bytes = to_bytes(&u32s, false).unwrap();
+
+    assert_ne!(starting, bytes);
+}
+
+#[cfg(test)]
+#[allow(clippy::needless_borrow)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{decrypt_, encrypt_, fix_key, to_bytes, to_u32, Xxtea, XxteaBlock, CHUNK_SIZE};
+    use crate::algorithms::modes::alg::{BlockMode, BlockModeCipher};
+    use crate::algorithms::{Algorithm, StreamProcessor, StreamingAlgorithm};
+    use crate::config::XxteaConfig;
+
+    fn cfb_alg() -> BlockModeCipher {
+        let config = XxteaConfig {
+            iv: b"asdfas34asdfasdfasdkljsdklfj".to_vec(),
+            block_size: 8,
+            key: "SecretKey".to_owned().into(),
+            ..Default::default()
+        };
+
+        BlockModeCipher::try_new(
+            Arc::new(XxteaBlock::try_new(&config).unwrap()),
+            BlockMode::Cfb,
+            &config.iv,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn xxtea_2() {
+        fn encrypt_raw(data: &[u8], key: &str) -> Vec<u8> {
+            let key = fix_key(&to_u32(key.as_bytes(), false));
+
+            to_bytes(&encrypt_(to_u32(data, false), &key), false).unwrap()
+        }
+
+        fn decrypt_raw(data: &[u8], key: &str) -> Vec<u8> {
+            let key = fix_key(&to_u32(key.as_bytes(), false));
+            to_bytes(&decrypt_(to_u32(data, false), &key), false).unwrap()
+        }
+
+        let key: &str = "SecretKey";
+
+        let data: [u8; 5] = [11, 13, 0, 14, 15];
+        println!("Data: {:?}", data);
+        let encrypted_data = encrypt_raw(&data, key);
+        println!("Encrypted data: {:?}", encrypted_data);
+
+        let decrypted_data = decrypt_raw(&encrypted_data, key);
+        println!("Decrypted data: {:?}", decrypted_data);
+
+        assert!(data.iter().eq(decrypted_data[0..data.len()].iter()));
+    }
+
+    #[test]
+    fn xxtea_3() {
+        fn encrypt(data: &[u8], key: &str) -> Vec<u8> {
+            let key = fix_key(&to_u32(key.as_bytes(), false));
+            to_bytes(&encrypt_(to_u32(data, true), &key), false).unwrap()
+        }
+
+        fn decrypt(data: &[u8], key: &str) -> Vec<u8> {
+            let key = fix_key(&to_u32(key.as_bytes(), false));
+            to_bytes(&decrypt_(to_u32(data, false), &key), true).unwrap()
+        }
+
+        let key: &str = "SecretKey";
+
+        let data = "Hellouw";
+        println!("Data: {:?}", data);
+
+        let encrypted_data = encrypt(&data.as_bytes(), key);
+        println!("Encrypted data: {:?}", encrypted_data);
+        println!(
+            "Encrypted data: {:?}",
+            String::from_utf8_lossy(&encrypted_data)
+        );
+
+        let decrypted_data = decrypt(&encrypted_data, key);
+        println!("Decrypted data: {:?}", decrypted_data);
+
+        let result = String::from_utf8(decrypted_data).unwrap();
+
+        assert_eq!(data, result)
+    }
+
+    #[test]
+    fn xxtea_full() {
+        let alg = Xxtea::new(&XxteaConfig {
+            key: "SecretKey".to_owned().into(),
+            ..Default::default()
+        });
+
+        let data = "Hellouw there".as_bytes();
+
+        let encrypted = alg.encrypt(data).unwrap();
+
+        println!("Encrypted data: {:?}", encrypted);
+        println!("Encrypted data: {:?}", String::from_utf8_lossy(&encrypted));
+
+        let decrypted = alg.decrypt(&encrypted).unwrap();
+
+        println!("Decrypted data: {:?}", decrypted);
+        println!("Decrypted data: {:?}", String::from_utf8_lossy(&decrypted));
+
+        assert_eq!(data, decrypted)
+    }
+
+    #[test]
+    fn cfb() {
+        let alg = cfb_alg();
+
+        let data = "Hellouw there".as_bytes();
+
+        let encrypted = alg.encrypt(data).unwrap();
+
+        println!("Encrypted data: {:?}", encrypted);
+        println!("Encrypted data: {:?}", String::from_utf8_lossy(&encrypted));
+
+        let decrypted = alg.decrypt(&encrypted).unwrap();
+
+        println!("Decrypted data: {:?}", decrypted);
+        println!("Decrypted data: {:?}", String::from_utf8_lossy(&decrypted));
+
+        assert_eq!(data, decrypted)
+    }
+
+    fn run_chunked(
+        mut processor: Box<dyn StreamProcessor + Send>,
+        data: &[u8],
+        chunk_size: usize,
+    ) -> Vec<u8> {
+        let mut res = Vec::new();
+        for chunk in data.chunks(chunk_size) {
+            res.extend(processor.update(chunk).unwrap());
+        }
+        res.extend(processor.finalize().unwrap());
+        res
+    }
+
+    #[test]
+    fn xxtea_streaming() {
+        let alg = Xxtea::new(&XxteaConfig {
+            key: "SecretKey".to_owned().into(),
+            ..Default::default()
+        });
+
+        for len in [0, 13, CHUNK_SIZE, CHUNK_SIZE + 1, 2 * CHUNK_SIZE + 4321] {
+            let data: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+
+            let encrypted = alg.encrypt(&data).unwrap();
+            assert_eq!(run_chunked(alg.encryptor(), &data, 1000), encrypted);
+            assert_eq!(run_chunked(alg.decryptor(), &encrypted, 777), data);
+        }
+    }
+
+    #[test]
+    fn cfb_streaming() {
+        let alg = cfb_alg();
+
+        let data = "Hellouw there, this message spans quite a few blocks".as_bytes();
+
+        let encrypted = alg.encrypt(data).unwrap();
+        for chunk_size in [1, 5, 8, 100] {
+            assert_eq!(run_chunked(alg.encryptor(), data, chunk_size), encrypted);
+            assert_eq!(run_chunked(alg.decryptor(), &encrypted, chunk_size), data);
+        }
+    }
+
+    #[test]
+    fn xxtea_wrong_key_is_an_error() {
+        let alg = Xxtea::new(&XxteaConfig {
+            key: "SecretKey".to_owned().into(),
+            ..Default::default()
+        });
+        let other = Xxtea::new(&XxteaConfig {
+            key: "OtherKey".to_owned().into(),
+            ..Default::default()
+        });
+
+        let encrypted = alg.encrypt("Hellouw there".as_bytes()).unwrap();
+
+        assert!(other.decrypt(&encrypted).is_err());
+        assert!(alg.decrypt(&encrypted[..5]).is_err());
+    }
+
+    // Output of the dedicated XxteaCfb implementation the generic CFB mode replaced
+    #[test]
+    fn cfb_matches_previous_implementation() {
+        let encrypted = cfb_alg().encrypt("Hellouw there".as_bytes()).unwrap();
+
+        assert_eq!(
+            encrypted,
+            [0x27, 0x12, 0x67, 0x38, 0x60, 0xe3, 0x54, 0x0f, 0x72, 0x98, 0xb0, 0xfc, 0x4b]
+        );
+    }
+}
@@ -0,0 +1,212 @@
+//! Typed settings the algorithms are built from. Front ends parse their own
+//! input into these, the algorithms check what can't be told from the types
+//! alone, like whether a wiring is a permutation.
+
+use std::fmt::Display;
+
+use zeroize::Zeroizing;
+
+use crate::algorithms::aes::alg::AesKeySize;
+use crate::algorithms::enigma::alg::{EnigmaMachine, EnigmaTextMode};
+use crate::algorithms::enigma::catalogue;
+use crate::algorithms::kdf::DEFAULT_ITERATIONS;
+use crate::algorithms::modes::alg::BlockMode;
+use crate::algorithms::xxtea::alg::XxteaMode;
+use crate::algorithms::AlgorithmOption;
+use crate::container::ContainerFormat;
+use crate::hash::HashAlgorithm;
+
+/// Text wiped from memory when dropped, for keys and passphrases
+pub type Secret = Zeroizing<String>;
+
+/// The settings of the selected algorithm are missing. The others are
+/// optional, a container header can still switch to one of them.
+#[derive(Debug)]
+pub struct MissingConfigError {
+    pub algorithm: AlgorithmOption,
+}
+
+impl Display for MissingConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "There are no valid {} settings", self.algorithm)
+    }
+}
+
+impl std::error::Error for MissingConfigError {}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct RotorConfig {
+    /// Where `a` to `z` are wired to, as 26 lowercase letters
+    pub wiring: String,
+    /// Positions at which the rotor steps its left neighbour, none for a
+    /// Greek wheel
+    pub notches: Vec<u8>,
+    pub ringstellung: u8,
+    pub position: u8,
+}
+
+impl RotorConfig {
+    fn new(wiring: &str, notches: Vec<u8>) -> RotorConfig {
+        RotorConfig {
+            wiring: wiring.to_owned(),
+            notches,
+            ringstellung: 0,
+            position: 0,
+        }
+    }
+}
+
+/// Settings of the M3 and M4
+#[derive(Clone, PartialEq, Debug)]
+pub struct EnigmaConfig {
+    pub reflector: String,
+    /// Needed by the M4, an M3 ignores it
+    pub greek: Option<RotorConfig>,
+    /// Left to right
+    pub rotors: [RotorConfig; 3],
+    /// Letter pairs separated by whitespace, e.g. "ab cd"
+    pub plugboard: String,
+    pub text_mode: EnigmaTextMode,
+    pub five_letter_groups: bool,
+}
+
+impl Default for EnigmaConfig {
+    fn default() -> Self {
+        EnigmaConfig {
+            reflector: catalogue::UKW_B.to_owned(),
+            greek: None,
+            rotors: [
+                RotorConfig::new("ekmflgdqvzntowyhxuspaibrcj", vec![8]),
+                RotorConfig::new("ajdksiruxblhwtmcqgznpyfvoe", vec![8]),
+                RotorConfig::new("bdfhjlcprtxvznyeiwgakmusqo", vec![0]),
+            ],
+            plugboard: "po ml iu kj nh yt gb vf re dc".to_owned(),
+            text_mode: EnigmaTextMode::LettersOnly,
+            five_letter_groups: false,
+        }
+    }
+}
+
+/// Settings of the byte machine, everything is derived from the passphrase
+#[derive(Clone, PartialEq, Debug)]
+pub struct BinaryEnigmaConfig {
+    pub passphrase: Secret,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct XxteaConfig {
+    pub key: Secret,
+    pub mode: XxteaMode,
+    /// Only used by the block modes
+    pub iv: Vec<u8>,
+    /// Bytes per block, only used by the block modes
+    pub block_size: usize,
+}
+
+impl Default for XxteaConfig {
+    fn default() -> Self {
+        XxteaConfig {
+            key: Secret::from("SecureKey".to_owned()),
+            mode: Default::default(),
+            iv: b"asdjgasdjgasdjfasdjkhasdf".to_vec(),
+            block_size: 8,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct AesConfig {
+    pub key: Secret,
+    pub key_size: AesKeySize,
+    pub mode: BlockMode,
+    /// Ignored by the modes without one
+    pub iv: Vec<u8>,
+}
+
+impl Default for AesConfig {
+    fn default() -> Self {
+        AesConfig {
+            key: Secret::from("SecureKey".to_owned()),
+            key_size: Default::default(),
+            mode: Default::default(),
+            iv: b"asdjgasdjgasdjfasdjkhasdf".to_vec(),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ChaCha20Poly1305Config {
+    pub key: Secret,
+}
+
+impl Default for ChaCha20Poly1305Config {
+    fn default() -> Self {
+        ChaCha20Poly1305Config {
+            key: Secret::from("SecureKey".to_owned()),
+        }
+    }
+}
+
+/// Everything needed to encrypt, decrypt and send files
+#[derive(Clone, PartialEq, Debug)]
+pub struct Config {
+    pub algorithm: AlgorithmOption,
+    /// Which machine [`AlgorithmOption::Enigma`] stands for
+    pub enigma_machine: EnigmaMachine,
+    pub enigma: Option<EnigmaConfig>,
+    pub binary_enigma: Option<BinaryEnigmaConfig>,
+    pub xxtea: Option<XxteaConfig>,
+    pub aes: Option<AesConfig>,
+    pub chacha20poly1305: Option<ChaCha20Poly1305Config>,
+    pub container_format: ContainerFormat,
+    /// Hash sent along with files over TCP, both sides must pick the same
+    pub integrity_hash: HashAlgorithm,
+    /// Turns the TCP hash into an HMAC, so it also proves who sent the file
+    pub shared_secret: Option<Secret>,
+    /// PBKDF2 iterations for new containers
+    pub kdf_iterations: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            algorithm: Default::default(),
+            enigma_machine: Default::default(),
+            enigma: Some(Default::default()),
+            binary_enigma: None,
+            xxtea: Some(Default::default()),
+            aes: Some(Default::default()),
+            chacha20poly1305: Some(Default::default()),
+            container_format: Default::default(),
+            integrity_hash: Default::default(),
+            shared_secret: None,
+            kdf_iterations: DEFAULT_ITERATIONS,
+        }
+    }
+}
+
+impl Config {
+    pub fn enigma(&self) -> Result<&EnigmaConfig, MissingConfigError> {
+        required(&self.enigma, AlgorithmOption::Enigma)
+    }
+
+    pub fn binary_enigma(&self) -> Result<&BinaryEnigmaConfig, MissingConfigError> {
+        required(&self.binary_enigma, AlgorithmOption::Enigma)
+    }
+
+    pub fn xxtea(&self) -> Result<&XxteaConfig, MissingConfigError> {
+        required(&self.xxtea, AlgorithmOption::Xxtea)
+    }
+
+    pub fn aes(&self) -> Result<&AesConfig, MissingConfigError> {
+        required(&self.aes, AlgorithmOption::Aes)
+    }
+
+    pub fn chacha20poly1305(&self) -> Result<&ChaCha20Poly1305Config, MissingConfigError> {
+        required(&self.chacha20poly1305, AlgorithmOption::ChaCha20Poly1305)
+    }
+}
+
+fn required<T>(config: &Option<T>, algorithm: AlgorithmOption) -> Result<&T, MissingConfigError> {
+    config.as_ref().ok_or(MissingConfigError { algorithm })
+}
@@ -0,0 +1,844 @@
+use std::fmt::Display;
+
+use anyhow::anyhow;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt};
+
+use crate::algorithms::aes::alg::AesKeySize;
+use crate::algorithms::enigma::alg::EnigmaMachine;
+use crate::algorithms::kdf::{DerivedKey, KeyDerivation, MAX_ITERATIONS, SALT_LEN};
+use crate::algorithms::modes::alg::BlockMode;
+use crate::algorithms::xxtea::alg::XxteaMode;
+use crate::algorithms::AlgorithmOption;
+use crate::config::{Config, MissingConfigError};
+use crate::hash::{self, hmac, HashAlgorithm};
+use crate::utils::derive_key;
+
+// Layout (all integers little endian):
+//
+// | magic | version | algorithm id | params len | params       | kdf len | kdf       | key fingerprint | payload ...
+// | 4 B   | u8      | u8           | u16        | params len B | u8      | kdf len B | 8 B             |
+//
+// The kdf is either empty or the iteration count (u32) followed by the salt.
+pub const MAGIC: &[u8; 4] = b"ZIEC";
+pub const FORMAT_VERSION: u8 = 3;
+
+const FINGERPRINT_LEN: usize = 8;
+const FINGERPRINT_DOMAIN: &[u8] = b"zi key fingerprint";
+
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum ContainerFormat {
+    #[default]
+    Container,
+    /// Bare ciphertext, as written by versions without the header. Keys are
+    /// used as typed, there is nowhere to keep a salt, and the M3 and M4
+    /// keep the rotor mechanics of those versions, see
+    /// [`crate::algorithms::enigma::alg::Mechanics::Legacy`].
+    Raw,
+}
+
+impl Display for ContainerFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ContainerFormat::Container => "ZI container",
+                ContainerFormat::Raw => "Raw (legacy)",
+            }
+        )
+    }
+}
+
+/// The key in settings is not the one the file was encrypted with
+#[derive(Debug)]
+pub struct WrongKeyError {
+    pub algorithm: AlgorithmOption,
+}
+
+impl Display for WrongKeyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "The file was encrypted with {} using a different key than the one in settings",
+            self.algorithm
+        )
+    }
+}
+
+impl std::error::Error for WrongKeyError {}
+
+/// Non-secret parameters needed to decrypt the payload
+#[derive(Clone, PartialEq, Debug)]
+pub enum Params {
+    Enigma {
+        positions: [u8; 3],
+        /// Only present for the M4
+        greek_position: Option<u8>,
+    },
+    /// The byte machine takes everything from the passphrase
+    BinaryEnigma,
+    Xxtea {
+        mode: XxteaMode,
+        block_size: u32,
+        iv: Vec<u8>,
+    },
+    Aes {
+        key_size: AesKeySize,
+        mode: BlockMode,
+        iv: Vec<u8>,
+    },
+    /// The nonce travels at the start of the payload
+    ChaCha20Poly1305,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Header {
+    pub version: u8,
+    pub algorithm: AlgorithmOption,
+    pub params: Params,
+    /// Derivation of the cipher key from the typed one, `None` for the
+    /// classic Enigmas
+    pub kdf: Option<KeyDerivation>,
+    pub key_fingerprint: [u8; FINGERPRINT_LEN],
+}
+
+impl Header {
+    /// Describes a payload encrypted with `config`, along with the cipher key
+    /// derived under the new salt. Expects a config that already passed
+    /// `utils::get_algorithm`.
+    pub fn from_config(config: &Config) -> anyhow::Result<(Header, Option<DerivedKey>)> {
+        let params = match config.algorithm {
+            AlgorithmOption::Enigma if config.enigma_machine == EnigmaMachine::Binary => {
+                Params::BinaryEnigma
+            }
+            AlgorithmOption::Enigma => {
+                let enigma = config.enigma()?;
+                let greek_position = match config.enigma_machine {
+                    EnigmaMachine::M3 | EnigmaMachine::Binary => None,
+                    EnigmaMachine::M4 => Some(
+                        enigma
+                            .greek
+                            .as_ref()
+                            .ok_or(anyhow!("The M4 needs a Greek wheel"))?
+                            .position,
+                    ),
+                };
+                Params::Enigma {
+                    positions: enigma.rotors.each_ref().map(|rotor| rotor.position),
+                    greek_position,
+                }
+            }
+            AlgorithmOption::Xxtea => {
+                let xxtea = config.xxtea()?;
+                match xxtea.mode {
+                    XxteaMode::WholeMessage => Params::Xxtea {
+                        mode: xxtea.mode,
+                        block_size: 0,
+                        iv: Vec::new(),
+                    },
+                    XxteaMode::Block(_) => Params::Xxtea {
+                        mode: xxtea.mode,
+                        block_size: xxtea.block_size.try_into()?,
+                        iv: required_iv(&xxtea.iv)?,
+                    },
+                }
+            }
+            AlgorithmOption::ChaCha20Poly1305 => Params::ChaCha20Poly1305,
+            AlgorithmOption::Aes => {
+                let aes = config.aes()?;
+                Params::Aes {
+                    key_size: aes.key_size,
+                    mode: aes.mode,
+                    iv: if aes.mode.uses_iv() {
+                        required_iv(&aes.iv)?
+                    } else {
+                        Vec::new()
+                    },
+                }
+            }
+        };
+
+        let kdf = match params {
+            Params::Enigma { .. } => None,
+            _ if config.kdf_iterations == 0 => {
+                return Err(anyhow!("KDF iterations must be a positive number"))
+            }
+            _ if config.kdf_iterations > MAX_ITERATIONS => {
+                return Err(anyhow!(
+                    "At most {} KDF iterations are allowed, files with more couldn't be opened",
+                    MAX_ITERATIONS
+                ))
+            }
+            _ => Some(KeyDerivation::random(config.kdf_iterations)),
+        };
+
+        let key = match &kdf {
+            Some(kdf) => derive_key(config, kdf)?,
+            None => None,
+        };
+
+        let header = Header {
+            version: FORMAT_VERSION,
+            algorithm: config.algorithm,
+            key_fingerprint: key_fingerprint(
+                config,
+                config.algorithm,
+                key.as_deref().map(Vec::as_slice),
+            )?,
+            params,
+            kdf,
+        };
+
+        Ok((header, key))
+    }
+
+    /// Returns a copy of `config` switched to the algorithm and parameters
+    /// recorded in the header, and the cipher key derived from it. Fails if
+    /// the keys in `config` are not the ones the payload was encrypted with.
+    pub fn apply(&self, config: &Config) -> anyhow::Result<(Config, Option<DerivedKey>)> {
+        let mut config = config.clone();
+        config.algorithm = self.algorithm;
+
+        match &self.params {
+            Params::Enigma {
+                positions,
+                greek_position,
+            } => {
+                let enigma = config.enigma.as_mut().ok_or(MissingConfigError {
+                    algorithm: AlgorithmOption::Enigma,
+                })?;
+                for (rotor, position) in enigma.rotors.iter_mut().zip(positions) {
+                    rotor.position = *position;
+                }
+                match greek_position {
+                    Some(position) => {
+                        config.enigma_machine = EnigmaMachine::M4;
+                        enigma
+                            .greek
+                            .as_mut()
+                            .ok_or(anyhow!(
+                                "The file was encrypted on an M4, the settings have no Greek wheel"
+                            ))?
+                            .position = *position;
+                    }
+                    None => config.enigma_machine = EnigmaMachine::M3,
+                }
+            }
+            Params::BinaryEnigma => config.enigma_machine = EnigmaMachine::Binary,
+            Params::Xxtea {
+                mode,
+                block_size,
+                iv,
+            } => {
+                let xxtea = config.xxtea.as_mut().ok_or(MissingConfigError {
+                    algorithm: AlgorithmOption::Xxtea,
+                })?;
+                xxtea.mode = *mode;
+                if let XxteaMode::Block(_) = mode {
+                    xxtea.block_size = (*block_size).try_into()?;
+                    xxtea.iv = iv.clone();
+                }
+            }
+            Params::Aes { key_size, mode, iv } => {
+                let aes = config.aes.as_mut().ok_or(MissingConfigError {
+                    algorithm: AlgorithmOption::Aes,
+                })?;
+                aes.key_size = *key_size;
+                aes.mode = *mode;
+                if mode.uses_iv() {
+                    aes.iv = iv.clone();
+                }
+            }
+            Params::ChaCha20Poly1305 => (),
+        }
+
+        let key = match &self.kdf {
+            Some(kdf) => derive_key(&config, kdf)?,
+            None => None,
+        };
+
+        if key_fingerprint(&config, self.algorithm, key.as_deref().map(Vec::as_slice))?
+            != self.key_fingerprint
+        {
+            return Err(WrongKeyError {
+                algorithm: self.algorithm,
+            }
+            .into());
+        }
+
+        Ok((config, key))
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let params = self.encode_params();
+        let kdf = self.encode_kdf();
+
+        let mut out =
+            Vec::with_capacity(MAGIC.len() + 5 + params.len() + kdf.len() + FINGERPRINT_LEN);
+        out.extend_from_slice(MAGIC);
+        out.push(self.version);
+        out.push(self.algorithm.id());
+        out.extend_from_slice(&(params.len() as u16).to_le_bytes());
+        out.extend_from_slice(&params);
+        out.push(kdf.len() as u8);
+        out.extend_from_slice(&kdf);
+        out.extend_from_slice(&self.key_fingerprint);
+
+        out
+    }
+
+    fn encode_params(&self) -> Vec<u8> {
+        match &self.params {
+            Params::Enigma {
+                positions,
+                greek_position,
+            } => {
+                let mut out = positions.to_vec();
+                out.extend(greek_position);
+                out
+            }
+            Params::BinaryEnigma => Vec::new(),
+            Params::Xxtea {
+                mode,
+                block_size,
+                iv,
+            } => match mode {
+                XxteaMode::WholeMessage => vec![0],
+                XxteaMode::Block(block_mode) => {
+                    let mut out = vec![block_mode.id()];
+                    out.extend_from_slice(&block_size.to_le_bytes());
+                    out.extend_from_slice(iv);
+                    out
+                }
+            },
+            Params::Aes { key_size, mode, iv } => {
+                let mut out = vec![key_size.bytes() as u8, mode.id()];
+                out.extend_from_slice(iv);
+                out
+            }
+            Params::ChaCha20Poly1305 => Vec::new(),
+        }
+    }
+
+    fn encode_kdf(&self) -> Vec<u8> {
+        match &self.kdf {
+            Some(kdf) => {
+                let mut out = kdf.iterations.to_le_bytes().to_vec();
+                out.extend_from_slice(&kdf.salt);
+                out
+            }
+            None => Vec::new(),
+        }
+    }
+
+    fn decode_kdf(kdf: &[u8]) -> anyhow::Result<Option<KeyDerivation>> {
+        match kdf {
+            [] => Ok(None),
+            [i0, i1, i2, i3, salt @ ..] if salt.len() == SALT_LEN => {
+                let iterations = u32::from_le_bytes([*i0, *i1, *i2, *i3]);
+                if iterations == 0 {
+                    return Err(anyhow!("Invalid KDF iteration count in header"));
+                }
+                if iterations > MAX_ITERATIONS {
+                    return Err(anyhow!(
+                        "The header asks for {} KDF iterations, at most {} are allowed",
+                        iterations,
+                        MAX_ITERATIONS
+                    ));
+                }
+
+                Ok(Some(KeyDerivation {
+                    salt: salt.try_into().unwrap(),
+                    iterations,
+                }))
+            }
+            _ => Err(anyhow!("Invalid KDF parameters in header")),
+        }
+    }
+
+    fn decode_params(algorithm: AlgorithmOption, params: &[u8]) -> anyhow::Result<Params> {
+        match algorithm {
+            AlgorithmOption::Enigma => match params {
+                [] => Ok(Params::BinaryEnigma),
+                [r1, r2, r3] => Ok(Params::Enigma {
+                    positions: [*r1, *r2, *r3],
+                    greek_position: None,
+                }),
+                [r1, r2, r3, greek] => Ok(Params::Enigma {
+                    positions: [*r1, *r2, *r3],
+                    greek_position: Some(*greek),
+                }),
+                _ => Err(anyhow!("Invalid Enigma parameters in header")),
+            },
+            AlgorithmOption::Xxtea => match params {
+                [0] => Ok(Params::Xxtea {
+                    mode: XxteaMode::WholeMessage,
+                    block_size: 0,
+                    iv: Vec::new(),
+                }),
+                [mode_id, b0, b1, b2, b3, iv @ ..] => Ok(Params::Xxtea {
+                    mode: XxteaMode::Block(
+                        BlockMode::from_id(*mode_id)
+                            .ok_or(anyhow!("Unknown block mode {} in header", mode_id))?,
+                    ),
+                    block_size: u32::from_le_bytes([*b0, *b1, *b2, *b3]),
+                    iv: iv.to_vec(),
+                }),
+                _ => Err(anyhow!("Invalid XXTEA parameters in header")),
+            },
+            AlgorithmOption::Aes => match params {
+                [key_bytes, mode_id, iv @ ..] => Ok(Params::Aes {
+                    key_size: AesKeySize::from_bytes(*key_bytes as usize)
+                        .ok_or(anyhow!("Invalid AES key size in header"))?,
+                    mode: BlockMode::from_id(*mode_id)
+                        .ok_or(anyhow!("Unknown block mode {} in header", mode_id))?,
+                    iv: iv.to_vec(),
+                }),
+                _ => Err(anyhow!("Invalid AES parameters in header")),
+            },
+            AlgorithmOption::ChaCha20Poly1305 => {
+                if !params.is_empty() {
+                    return Err(anyhow!("Invalid ChaCha20-Poly1305 parameters in header"));
+                }
+                Ok(Params::ChaCha20Poly1305)
+            }
+        }
+    }
+
+    /// Reads the header if `reader` starts with [`MAGIC`]. Returns `None` and
+    /// consumes nothing otherwise.
+    pub async fn read_from<R: AsyncBufRead + Unpin>(
+        reader: &mut R,
+    ) -> anyhow::Result<Option<Header>> {
+        if !reader.fill_buf().await?.starts_with(MAGIC) {
+            return Ok(None);
+        }
+        reader.consume(MAGIC.len());
+
+        let version = reader.read_u8().await?;
+        if version != FORMAT_VERSION {
+            return Err(anyhow!(
+                "Unsupported container version {} (expected {})",
+                version,
+                FORMAT_VERSION
+            ));
+        }
+
+        let algorithm_id = reader.read_u8().await?;
+        let algorithm = AlgorithmOption::from_id(algorithm_id)
+            .ok_or(anyhow!("Unknown algorithm id {} in header", algorithm_id))?;
+
+        let params_len = reader.read_u16_le().await?;
+        let mut params = vec![0u8; params_len as usize];
+        reader.read_exact(&mut params).await?;
+
+        let kdf_len = reader.read_u8().await?;
+        let mut kdf = vec![0u8; kdf_len as usize];
+        reader.read_exact(&mut kdf).await?;
+
+        let mut key_fingerprint = [0u8; FINGERPRINT_LEN];
+        reader.read_exact(&mut key_fingerprint).await?;
+
+        let params = Header::decode_params(algorithm, &params)?;
+        let kdf = Header::decode_kdf(&kdf)?;
+        // Only the classic Enigmas have no password to derive a key from
+        if kdf.is_none() && !matches!(params, Params::Enigma { .. }) {
+            return Err(anyhow!("The header has no KDF parameters for the key"));
+        }
+
+        Ok(Some(Header {
+            version,
+            algorithm,
+            params,
+            kdf,
+            key_fingerprint,
+        }))
+    }
+}
+
+fn required_iv(iv: &[u8]) -> anyhow::Result<Vec<u8>> {
+    if iv.is_empty() {
+        return Err(anyhow!("IV is missing"));
+    }
+
+    Ok(iv.to_vec())
+}
+
+/// Short hash of the secret part of the configuration, lets decryption tell
+/// a wrong key apart from a corrupted file.
+///
+/// With a `key` derived by the KDF it is an HMAC under that key, so it
+/// reveals nothing about the cipher key and guessing the password from it is
+/// as slow as from the ciphertext. Without one the typed settings are hashed
+/// as they are. That is only a typo check, used for the rotor settings of
+/// the classic Enigmas, which aren't a password.
+fn key_fingerprint(
+    config: &Config,
+    algorithm: AlgorithmOption,
+    key: Option<&[u8]>,
+) -> anyhow::Result<[u8; FINGERPRINT_LEN]> {
+    let mut material: Vec<u8> = FINGERPRINT_DOMAIN.to_vec();
+    material.push(algorithm.id());
+
+    // Fields are hashed as they used to be typed into the settings, so
+    // existing files keep their fingerprints
+    let mut push_field = |field: &str| {
+        material.extend_from_slice(&(field.len() as u32).to_le_bytes());
+        material.extend_from_slice(field.as_bytes());
+    };
+    // The typed key only goes in when nothing was derived from it
+    let typed_key = key.is_none();
+
+    match algorithm {
+        AlgorithmOption::Enigma if config.enigma_machine == EnigmaMachine::Binary => {
+            if typed_key {
+                push_field(&config.binary_enigma()?.passphrase);
+            }
+        }
+        AlgorithmOption::Enigma => {
+            let enigma = config.enigma()?;
+            push_field(&enigma.reflector);
+            for rotor in &enigma.rotors {
+                let notches: Vec<_> = rotor.notches.iter().map(u8::to_string).collect();
+                push_field(&rotor.wiring);
+                push_field(&notches.join(" "));
+                push_field(&rotor.ringstellung.to_string());
+            }
+            push_field(&enigma.plugboard.to_ascii_lowercase());
+            // M3 fingerprints stay the same as before the M4 existed
+            if config.enigma_machine == EnigmaMachine::M4 {
+                let greek = enigma
+                    .greek
+                    .as_ref()
+                    .ok_or(anyhow!("The M4 needs a Greek wheel"))?;
+                push_field(&greek.wiring);
+                push_field(&greek.ringstellung.to_string());
+            }
+        }
+        AlgorithmOption::Xxtea => {
+            if typed_key {
+                push_field(&config.xxtea()?.key);
+            }
+        }
+        AlgorithmOption::Aes => {
+            let aes = config.aes()?;
+            push_field(&aes.key_size.to_string());
+            if typed_key {
+                push_field(&aes.key);
+            }
+        }
+        AlgorithmOption::ChaCha20Poly1305 => {
+            if typed_key {
+                push_field(&config.chacha20poly1305()?.key);
+            }
+        }
+    }
+
+    let digest = match key {
+        Some(key) => hmac::hmac(HashAlgorithm::Tiger, key, &material)?,
+        None => hash::hash_data(&material),
+    };
+
+    let mut fingerprint = [0u8; FINGERPRINT_LEN];
+    fingerprint.copy_from_slice(&digest[..FINGERPRINT_LEN]);
+
+    Ok(fingerprint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        key_fingerprint, Header, Params, FINGERPRINT_DOMAIN, FINGERPRINT_LEN, FORMAT_VERSION,
+        MAGIC, SALT_LEN,
+    };
+    use crate::algorithms::aes::alg::AesKeySize;
+    use crate::algorithms::enigma::alg::EnigmaMachine;
+    use crate::algorithms::enigma::catalogue;
+    use crate::algorithms::kdf::MAX_ITERATIONS;
+    use crate::algorithms::modes::alg::BlockMode;
+    use crate::algorithms::xxtea::alg::XxteaMode;
+    use crate::algorithms::AlgorithmOption;
+    use crate::config::{BinaryEnigmaConfig, Config, RotorConfig};
+    use crate::hash::hash_data;
+    use crate::utils::{decrypt_stream, encrypt_stream};
+
+    #[tokio::test]
+    async fn header_round_trip() {
+        let mut config = Config::default();
+
+        for (option, xxtea_mode, aes_mode) in [
+            (
+                AlgorithmOption::Enigma,
+                XxteaMode::default(),
+                BlockMode::default(),
+            ),
+            (
+                AlgorithmOption::Xxtea,
+                XxteaMode::WholeMessage,
+                BlockMode::default(),
+            ),
+            (
+                AlgorithmOption::Xxtea,
+                XxteaMode::Block(BlockMode::Ofb),
+                BlockMode::default(),
+            ),
+            (AlgorithmOption::Aes, XxteaMode::default(), BlockMode::Cbc),
+            (AlgorithmOption::Aes, XxteaMode::default(), BlockMode::Ecb),
+            (
+                AlgorithmOption::ChaCha20Poly1305,
+                XxteaMode::default(),
+                BlockMode::default(),
+            ),
+        ] {
+            config.algorithm = option;
+            config.xxtea.as_mut().unwrap().mode = xxtea_mode;
+            config.aes.as_mut().unwrap().mode = aes_mode;
+
+            let (header, _) = Header::from_config(&config).unwrap();
+            let mut encoded = header.encode();
+            encoded.extend_from_slice(b"payload");
+
+            let mut reader = &encoded[..];
+            let decoded = Header::read_from(&mut reader).await.unwrap().unwrap();
+
+            assert_eq!(decoded, header);
+            assert_eq!(reader, b"payload");
+        }
+    }
+
+    #[tokio::test]
+    async fn enigma_m4_header_round_trip() {
+        let greek = RotorConfig {
+            wiring: catalogue::GREEK_BETA.to_owned(),
+            notches: Vec::new(),
+            ringstellung: 0,
+            position: 0,
+        };
+        let mut config = Config {
+            enigma_machine: EnigmaMachine::M4,
+            ..Default::default()
+        };
+        config.enigma.as_mut().unwrap().greek = Some(RotorConfig {
+            position: 17,
+            ..greek.clone()
+        });
+
+        let (header, _) = Header::from_config(&config).unwrap();
+        let encoded = header.encode();
+        let decoded = Header::read_from(&mut &encoded[..]).await.unwrap().unwrap();
+        assert_eq!(decoded, header);
+
+        // An M3 that has a Greek wheel set up can switch to the M4
+        let mut other = Config::default();
+        other.enigma.as_mut().unwrap().greek = Some(greek);
+        let (applied, _) = decoded.apply(&other).unwrap();
+        assert_eq!(applied.enigma_machine, EnigmaMachine::M4);
+        assert_eq!(
+            applied.enigma.unwrap().greek.map(|greek| greek.position),
+            Some(17)
+        );
+
+        other
+            .enigma
+            .as_mut()
+            .unwrap()
+            .greek
+            .as_mut()
+            .unwrap()
+            .wiring = catalogue::GREEK_GAMMA.to_owned();
+        assert!(decoded.apply(&other).is_err());
+        assert!(decoded.apply(&Config::default()).is_err());
+    }
+
+    #[tokio::test]
+    async fn binary_enigma_header_round_trip() {
+        let passphrase = |passphrase: &str| {
+            Some(BinaryEnigmaConfig {
+                passphrase: passphrase.to_owned().into(),
+            })
+        };
+        let config = Config {
+            enigma_machine: EnigmaMachine::Binary,
+            binary_enigma: passphrase("open sesame"),
+            ..Default::default()
+        };
+
+        let (header, _) = Header::from_config(&config).unwrap();
+        let encoded = header.encode();
+        let decoded = Header::read_from(&mut &encoded[..]).await.unwrap().unwrap();
+        assert_eq!(decoded, header);
+
+        let mut other = Config {
+            binary_enigma: passphrase("open sesame"),
+            ..Default::default()
+        };
+        let (applied, _) = decoded.apply(&other).unwrap();
+        assert_eq!(applied.enigma_machine, EnigmaMachine::Binary);
+
+        other.binary_enigma = passphrase("open barley");
+        assert!(decoded.apply(&other).is_err());
+    }
+
+    #[tokio::test]
+    async fn raw_data_has_no_header() {
+        let mut reader = &b"just some ciphertext"[..];
+
+        assert!(Header::read_from(&mut reader).await.unwrap().is_none());
+        assert_eq!(reader, b"just some ciphertext");
+    }
+
+    #[tokio::test]
+    async fn other_versions_are_refused() {
+        let (header, _) = Header::from_config(&Config::default()).unwrap();
+
+        for version in [2, FORMAT_VERSION + 1] {
+            let mut encoded = header.encode();
+            encoded[MAGIC.len()] = version;
+
+            let err = Header::read_from(&mut &encoded[..]).await.unwrap_err();
+            assert!(err.to_string().contains("Unsupported"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn apply_restores_params_and_checks_key() {
+        let mut config = Config {
+            algorithm: AlgorithmOption::Xxtea,
+            ..Default::default()
+        };
+        config.xxtea.as_mut().unwrap().mode = XxteaMode::Block(BlockMode::Ctr);
+        let (header, _) = Header::from_config(&config).unwrap();
+
+        let mut other = config.clone();
+        other.algorithm = AlgorithmOption::Enigma;
+        let xxtea = other.xxtea.as_mut().unwrap();
+        xxtea.mode = XxteaMode::WholeMessage;
+        xxtea.iv = b"something else entirely".to_vec();
+
+        let (applied, _) = header.apply(&other).unwrap();
+        assert_eq!(applied.algorithm, AlgorithmOption::Xxtea);
+        assert_eq!(applied.xxtea, config.xxtea);
+        assert!(matches!(header.params, Params::Xxtea { .. }));
+
+        other.xxtea.as_mut().unwrap().key = "WrongKey".to_owned().into();
+        assert!(header.apply(&other).is_err());
+
+        other.xxtea = None;
+        assert!(header.apply(&other).is_err());
+    }
+
+    #[tokio::test]
+    async fn keys_are_derived_with_a_fresh_salt() {
+        let mut config = Config {
+            algorithm: AlgorithmOption::Aes,
+            kdf_iterations: 10,
+            ..Default::default()
+        };
+        config.aes.as_mut().unwrap().mode = BlockMode::Cbc;
+
+        let plaintext = b"the same plaintext under the same password".repeat(10);
+        let mut first = Vec::new();
+        encrypt_stream(&config, &plaintext[..], &mut first)
+            .await
+            .unwrap();
+        let mut second = Vec::new();
+        encrypt_stream(&config, &plaintext[..], &mut second)
+            .await
+            .unwrap();
+
+        let header = Header::read_from(&mut &first[..]).await.unwrap().unwrap();
+        assert_eq!(header.kdf.as_ref().unwrap().iterations, 10);
+        assert_ne!(first, second);
+
+        // Decryption takes the iterations from the header, not from the config
+        let mut other = config.clone();
+        other.kdf_iterations = 1;
+        for ciphertext in [&first, &second] {
+            let mut decrypted = Vec::new();
+            decrypt_stream(&other, &ciphertext[..], &mut decrypted)
+                .await
+                .unwrap();
+            assert_eq!(decrypted, plaintext);
+        }
+
+        other.aes.as_mut().unwrap().key = "SecureKey!".to_owned().into();
+        let mut decrypted = Vec::new();
+        assert!(decrypt_stream(&other, &first[..], &mut decrypted)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn fingerprints_are_keyed_by_the_derived_key() {
+        let mut config = Config {
+            algorithm: AlgorithmOption::Aes,
+            kdf_iterations: 10,
+            ..Default::default()
+        };
+        config.aes.as_mut().unwrap().key_size = AesKeySize::Aes256;
+        let (header, key) = Header::from_config(&config).unwrap();
+        let key = key.unwrap();
+
+        // The cipher gets the same bytes, derived once
+        let kdf = header.kdf.as_ref().unwrap();
+        assert_eq!(key, kdf.derive(&config.aes().unwrap().key, 32));
+
+        // Only the derived key goes in, as the HMAC key, so no prefix of it
+        // is hashed in the clear
+        let mut other = config.clone();
+        other.aes.as_mut().unwrap().key = "SomethingElse".to_owned().into();
+        let fingerprint = key_fingerprint(&other, AlgorithmOption::Aes, Some(&key)).unwrap();
+        assert_eq!(fingerprint, header.key_fingerprint);
+        for len in 1..=key.len() {
+            let prefix = [FINGERPRINT_DOMAIN, &key[..len]].concat();
+            assert_ne!(hash_data(&prefix)[..FINGERPRINT_LEN], fingerprint);
+        }
+
+        // A v3 header for a password without KDF parameters is refused
+        let mut encoded = header.encode();
+        let kdf_len_at = encoded.len() - FINGERPRINT_LEN - 1 - 4 - SALT_LEN;
+        encoded.drain(kdf_len_at..kdf_len_at + 1 + 4 + SALT_LEN);
+        encoded.insert(kdf_len_at, 0);
+        assert!(Header::read_from(&mut &encoded[..]).await.is_err());
+    }
+
+    #[test]
+    fn classic_enigma_has_no_kdf() {
+        let (header, key) = Header::from_config(&Config::default()).unwrap();
+        assert_eq!(header.kdf, None);
+        assert!(key.is_none());
+
+        let config = Config {
+            algorithm: AlgorithmOption::ChaCha20Poly1305,
+            kdf_iterations: 0,
+            ..Default::default()
+        };
+        assert!(Header::from_config(&config).is_err());
+    }
+
+    #[tokio::test]
+    async fn expensive_kdf_headers_are_refused() {
+        let config = Config {
+            algorithm: AlgorithmOption::ChaCha20Poly1305,
+            kdf_iterations: 10,
+            ..Default::default()
+        };
+        let (header, _) = Header::from_config(&config).unwrap();
+
+        let mut encoded = header.encode();
+        let iterations_at = encoded.len() - FINGERPRINT_LEN - 4 - SALT_LEN;
+        encoded[iterations_at..][..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let err = Header::read_from(&mut &encoded[..]).await.unwrap_err();
+        assert!(
+            err.to_string().contains(&MAX_ITERATIONS.to_string()),
+            "{}",
+            err
+        );
+
+        let config = Config {
+            kdf_iterations: MAX_ITERATIONS + 1,
+            ..config
+        };
+        assert!(Header::from_config(&config).is_err());
+    }
+}
@@ -0,0 +1,77 @@
+/// Collects input into `N`-byte blocks for the Merkle-Damgard hashes and
+/// applies their common padding: a marker byte, zeroes, then the message
+/// length in bits.
+#[derive(Clone)]
+pub(super) struct BlockBuffer<const N: usize> {
+    buffer: [u8; N],
+    buffered: usize,
+    /// Bytes seen so far
+    length: u128,
+}
+
+pub(super) enum LengthField {
+    /// MD5 and Tiger, 64-bit little endian
+    Le64,
+    /// SHA-1 and SHA-256, 64-bit big endian
+    Be64,
+    /// SHA-512, 128-bit big endian
+    Be128,
+}
+
+impl<const N: usize> BlockBuffer<N> {
+    pub fn new() -> BlockBuffer<N> {
+        BlockBuffer {
+            buffer: [0; N],
+            buffered: 0,
+            length: 0,
+        }
+    }
+
+    pub fn update(&mut self, mut data: &[u8], mut compress: impl FnMut(&[u8; N])) {
+        self.length += data.len() as u128;
+
+        if self.buffered > 0 {
+            let take = data.len().min(N - self.buffered);
+            self.buffer[self.buffered..self.buffered + take].copy_from_slice(&data[..take]);
+            self.buffered += take;
+            data = &data[take..];
+
+            if self.buffered < N {
+                return;
+            }
+
+            compress(&self.buffer);
+            self.buffered = 0;
+        }
+
+        let mut blocks = data.chunks_exact(N);
+        for block in &mut blocks {
+            compress(block.try_into().unwrap());
+        }
+
+        let rest = blocks.remainder();
+        self.buffer[..rest.len()].copy_from_slice(rest);
+        self.buffered = rest.len();
+    }
+
+    /// `marker` is `0x80` for every hash but Tiger
+    pub fn finalize(mut self, marker: u8, field: LengthField, mut compress: impl FnMut(&[u8; N])) {
+        let bits = self.length.wrapping_mul(8);
+        let length: Vec<u8> = match field {
+            LengthField::Le64 => (bits as u64).to_le_bytes().to_vec(),
+            LengthField::Be64 => (bits as u64).to_be_bytes().to_vec(),
+            LengthField::Be128 => bits.to_be_bytes().to_vec(),
+        };
+
+        self.buffer[self.buffered] = marker;
+        self.buffer[self.buffered + 1..].fill(0);
+
+        if self.buffered >= N - length.len() {
+            compress(&self.buffer);
+            self.buffer.fill(0);
+        }
+
+        self.buffer[N - length.len()..].copy_from_slice(&length);
+        compress(&self.buffer);
+    }
+}
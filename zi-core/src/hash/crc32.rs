@@ -0,0 +1,63 @@
+//! CRC-32 as used by zip, PNG and Ethernet (reflected polynomial
+//! 0xEDB88320). Catches accidental corruption only, anyone can forge it.
+
+use super::Hasher;
+
+const POLYNOMIAL: u32 = 0xEDB8_8320;
+
+const TABLE: [u32; 256] = table();
+
+const fn table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ POLYNOMIAL
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+}
+
+#[derive(Clone)]
+pub struct Crc32 {
+    crc: u32,
+}
+
+impl Crc32 {
+    pub fn new() -> Crc32 {
+        Crc32 { crc: !0 }
+    }
+}
+
+impl Hasher for Crc32 {
+    fn update(&mut self, data: &[u8]) {
+        self.crc = data.iter().fold(self.crc, |crc, &byte| {
+            (crc >> 8) ^ TABLE[usize::from((crc as u8) ^ byte)]
+        });
+    }
+
+    /// Big endian, the order the checksum is usually printed in
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        (!self.crc).to_be_bytes().to_vec()
+    }
+
+    /// CRC has no blocks, this is what HMAC pads keys to
+    fn block_size(&self) -> usize {
+        64
+    }
+
+    fn box_clone(&self) -> Box<dyn Hasher> {
+        Box::new(self.clone())
+    }
+}
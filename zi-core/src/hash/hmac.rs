@@ -0,0 +1,221 @@
+//! HMAC (RFC 2104) over any [`Hasher`], for authenticating data with a
+//! shared secret instead of only detecting accidental corruption. Tiger is
+//! what the rest of the crate uses.
+
+use anyhow::anyhow;
+
+use super::{HashAlgorithm, Hasher};
+
+const IPAD: u8 = 0x36;
+const OPAD: u8 = 0x5C;
+
+/// Incremental HMAC, used the same way as a [`Hasher`]
+pub struct Hmac {
+    inner: Box<dyn Hasher>,
+    /// Already fed with the outer padded key
+    outer: Box<dyn Hasher>,
+}
+
+impl Clone for Hmac {
+    fn clone(&self) -> Self {
+        Hmac {
+            inner: self.inner.box_clone(),
+            outer: self.outer.box_clone(),
+        }
+    }
+}
+
+impl Hmac {
+    /// Fails for hashes that aren't cryptographic, an HMAC over them can be
+    /// forged without the key
+    pub fn new(algorithm: HashAlgorithm, key: &[u8]) -> anyhow::Result<Hmac> {
+        if !algorithm.is_cryptographic() {
+            return Err(anyhow!(
+                "{} is not a cryptographic hash and can't be used with a key",
+                algorithm
+            ));
+        }
+
+        let mut inner = algorithm.hasher();
+        let mut outer = algorithm.hasher();
+
+        // Keys longer than a block are hashed first, shorter ones are padded
+        // with zeroes
+        let block_size = inner.block_size();
+        let mut key = if key.len() > block_size {
+            algorithm.hash(key)
+        } else {
+            key.to_vec()
+        };
+        key.resize(block_size, 0);
+
+        inner.update(&key.iter().map(|b| b ^ IPAD).collect::<Vec<_>>());
+        outer.update(&key.iter().map(|b| b ^ OPAD).collect::<Vec<_>>());
+
+        Ok(Hmac { inner, outer })
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    pub fn finalize(self) -> Vec<u8> {
+        let mut outer = self.outer;
+        outer.update(&self.inner.finalize());
+
+        return outer.finalize();
+    }
+}
+
+pub fn hmac(algorithm: HashAlgorithm, key: &[u8], data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut mac = Hmac::new(algorithm, key)?;
+    mac.update(data);
+
+    return Ok(mac.finalize());
+}
+
+/// Compares two tags without returning early on the first difference, so
+/// the time taken does not reveal how much of a forged tag was right
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let diff = a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y));
+
+    return std::hint::black_box(diff) == 0;
+}
+
+pub fn verify(
+    algorithm: HashAlgorithm,
+    key: &[u8],
+    data: &[u8],
+    tag: &[u8],
+) -> anyhow::Result<bool> {
+    Ok(constant_time_eq(&hmac(algorithm, key, data)?, tag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{constant_time_eq, hmac, verify, Hmac, IPAD, OPAD};
+    use crate::hash::{hash_data, HashAlgorithm};
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// H((K ^ opad) || H((K ^ ipad) || m)) spelled out with one-shot hashes
+    fn reference(algorithm: HashAlgorithm, key: &[u8], data: &[u8]) -> Vec<u8> {
+        let block_size = algorithm.hasher().block_size();
+        let mut key = if key.len() > block_size {
+            algorithm.hash(key)
+        } else {
+            key.to_vec()
+        };
+        key.resize(block_size, 0);
+
+        let mut inner: Vec<u8> = key.iter().map(|b| b ^ IPAD).collect();
+        inner.extend_from_slice(data);
+
+        let mut outer: Vec<u8> = key.iter().map(|b| b ^ OPAD).collect();
+        outer.extend(algorithm.hash(&inner));
+
+        algorithm.hash(&outer)
+    }
+
+    /// "Jefe" / "what do ya want for nothing?" from RFC 2202 and RFC 4231
+    #[test]
+    fn rfc_reference_vectors() {
+        let vectors = [
+            (HashAlgorithm::Md5, "750c783e6ab0b503eaa86e310a5db738"),
+            (
+                HashAlgorithm::Sha1,
+                "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79",
+            ),
+            (
+                HashAlgorithm::Sha256,
+                "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            ),
+            (
+                HashAlgorithm::Sha512,
+                "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea250554\
+                 9758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737",
+            ),
+        ];
+
+        for (algorithm, expected) in vectors {
+            assert_eq!(
+                hex(&hmac(algorithm, b"Jefe", b"what do ya want for nothing?").unwrap()),
+                expected,
+                "{}",
+                algorithm
+            );
+        }
+    }
+
+    #[test]
+    fn matches_the_rfc_2104_construction() {
+        let long_key = [0xAA; 131];
+        let cases: [(&[u8], &[u8]); 5] = [
+            (b"", b""),
+            (b"key", b"The quick brown fox jumps over the lazy dog"),
+            (&[0x0B; 20], b"Hi There"),
+            (&[0x42; 64], b"a key of exactly one block is used as is"),
+            (&long_key, b"a longer key is hashed down first"),
+        ];
+
+        for (key, data) in cases {
+            for algorithm in HashAlgorithm::ALL
+                .into_iter()
+                .filter(HashAlgorithm::is_cryptographic)
+            {
+                assert_eq!(
+                    hmac(algorithm, key, data).unwrap(),
+                    reference(algorithm, key, data),
+                    "{}",
+                    algorithm
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn tag_depends_on_key_and_data() {
+        let data = b"payload";
+        let tag = hmac(HashAlgorithm::Tiger, b"secret", data).unwrap();
+
+        assert_ne!(tag, hash_data(data));
+        assert_ne!(tag, hmac(HashAlgorithm::Tiger, b"secreT", data).unwrap());
+        assert!(verify(HashAlgorithm::Tiger, b"secret", data, &tag).unwrap());
+        assert!(!verify(HashAlgorithm::Tiger, b"secret", b"payloaD", &tag).unwrap());
+        assert!(!verify(HashAlgorithm::Tiger, b"secret", data, &tag[..23]).unwrap());
+    }
+
+    #[test]
+    fn non_cryptographic_hashes_are_refused() {
+        assert!(Hmac::new(HashAlgorithm::Crc32, b"key").is_err());
+        assert!(hmac(HashAlgorithm::Crc32, b"key", b"data").is_err());
+        assert!(verify(HashAlgorithm::Crc32, b"key", b"data", &[0; 4]).is_err());
+    }
+
+    #[test]
+    fn incremental_matches_one_shot() {
+        let data: Vec<u8> = (0..300).map(|i| (i * 7) as u8).collect();
+
+        let mut mac = Hmac::new(HashAlgorithm::Tiger, b"k").unwrap();
+        data.chunks(17).for_each(|piece| mac.update(piece));
+
+        assert_eq!(
+            mac.finalize(),
+            hmac(HashAlgorithm::Tiger, b"k", &data).unwrap()
+        );
+    }
+
+    #[test]
+    fn constant_time_eq_compares_whole_slices() {
+        assert!(constant_time_eq(b"", b""));
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"ab"));
+    }
+}
@@ -0,0 +1,259 @@
+use std::fmt::Display;
+
+mod block;
+mod crc32;
+pub mod hmac;
+mod md5;
+pub mod pbkdf2;
+mod s_boxes;
+mod sha1;
+mod sha2;
+pub mod sums;
+mod tiger;
+pub mod tth;
+
+pub use crc32::Crc32;
+pub use md5::Md5;
+pub use sha1::Sha1;
+pub use sha2::{Sha256, Sha512};
+pub use tiger::{hash_data, Tiger, TigerVariant};
+
+/// Incremental hash function. Boxed so the algorithm can be picked at
+/// runtime, e.g. from an id received over the network.
+pub trait Hasher: Send {
+    fn update(&mut self, data: &[u8]);
+
+    fn finalize(self: Box<Self>) -> Vec<u8>;
+
+    /// Bytes compressed at a time, HMAC pads its key to this length
+    fn block_size(&self) -> usize;
+
+    fn box_clone(&self) -> Box<dyn Hasher>;
+}
+
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum HashAlgorithm {
+    #[default]
+    Tiger,
+    Tiger160,
+    Tiger128,
+    Tiger2,
+    Sha1,
+    Sha256,
+    Sha512,
+    Md5,
+    Crc32,
+}
+
+impl Display for HashAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                HashAlgorithm::Tiger => "Tiger/192",
+                HashAlgorithm::Tiger160 => "Tiger/160",
+                HashAlgorithm::Tiger128 => "Tiger/128",
+                HashAlgorithm::Tiger2 => "Tiger2/192",
+                HashAlgorithm::Sha1 => "SHA-1",
+                HashAlgorithm::Sha256 => "SHA-256",
+                HashAlgorithm::Sha512 => "SHA-512",
+                HashAlgorithm::Md5 => "MD5",
+                HashAlgorithm::Crc32 => "CRC32",
+            }
+        )
+    }
+}
+
+impl HashAlgorithm {
+    pub const ALL: [HashAlgorithm; 9] = [
+        HashAlgorithm::Tiger,
+        HashAlgorithm::Tiger160,
+        HashAlgorithm::Tiger128,
+        HashAlgorithm::Tiger2,
+        HashAlgorithm::Sha1,
+        HashAlgorithm::Sha256,
+        HashAlgorithm::Sha512,
+        HashAlgorithm::Md5,
+        HashAlgorithm::Crc32,
+    ];
+
+    /// Stable identifier sent over the network, never reuse one
+    pub fn id(&self) -> u8 {
+        match self {
+            HashAlgorithm::Tiger => 1,
+            HashAlgorithm::Tiger160 => 2,
+            HashAlgorithm::Tiger128 => 3,
+            HashAlgorithm::Tiger2 => 4,
+            HashAlgorithm::Sha1 => 5,
+            HashAlgorithm::Sha256 => 6,
+            HashAlgorithm::Sha512 => 7,
+            HashAlgorithm::Md5 => 8,
+            HashAlgorithm::Crc32 => 9,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<HashAlgorithm> {
+        HashAlgorithm::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.id() == id)
+    }
+
+    /// Whether forging data for a given hash is infeasible. CRC32 only
+    /// catches accidental corruption.
+    pub fn is_cryptographic(&self) -> bool {
+        !matches!(self, HashAlgorithm::Crc32)
+    }
+
+    pub fn hasher(&self) -> Box<dyn Hasher> {
+        match self {
+            HashAlgorithm::Tiger => Box::new(Tiger::new()),
+            HashAlgorithm::Tiger160 => Box::new(Tiger::with_variant(TigerVariant::Tiger160)),
+            HashAlgorithm::Tiger128 => Box::new(Tiger::with_variant(TigerVariant::Tiger128)),
+            HashAlgorithm::Tiger2 => Box::new(Tiger::with_variant(TigerVariant::Tiger2)),
+            HashAlgorithm::Sha1 => Box::new(Sha1::new()),
+            HashAlgorithm::Sha256 => Box::new(Sha256::new()),
+            HashAlgorithm::Sha512 => Box::new(Sha512::new()),
+            HashAlgorithm::Md5 => Box::new(Md5::new()),
+            HashAlgorithm::Crc32 => Box::new(Crc32::new()),
+        }
+    }
+
+    pub fn hash(&self, data: &[u8]) -> Vec<u8> {
+        let mut hasher = self.hasher();
+        hasher.update(data);
+
+        hasher.finalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HashAlgorithm;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    const TWO_BLOCKS: &[u8] = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+
+    fn check(algorithm: HashAlgorithm, vectors: &[(&[u8], &str)]) {
+        for (data, expected) in vectors {
+            assert_eq!(
+                hex(&algorithm.hash(data)),
+                *expected,
+                "{} of {:?}",
+                algorithm,
+                String::from_utf8_lossy(data)
+            );
+        }
+    }
+
+    #[test]
+    fn md5_reference_vectors() {
+        check(
+            HashAlgorithm::Md5,
+            &[
+                (b"", "d41d8cd98f00b204e9800998ecf8427e"),
+                (b"abc", "900150983cd24fb0d6963f7d28e17f72"),
+                (b"message digest", "f96b697d7cb7938d525a2f31aaf161d0"),
+                (
+                    b"12345678901234567890123456789012345678901234567890123456789012345678901234567890",
+                    "57edf4a22be3c955ac49da2e2107b67a",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn sha1_reference_vectors() {
+        check(
+            HashAlgorithm::Sha1,
+            &[
+                (b"", "da39a3ee5e6b4b0d3255bfef95601890afd80709"),
+                (b"abc", "a9993e364706816aba3e25717850c26c9cd0d89d"),
+                (TWO_BLOCKS, "84983e441c3bd26ebaae4aa1f95129e5e54670f1"),
+            ],
+        );
+    }
+
+    #[test]
+    fn sha256_reference_vectors() {
+        check(
+            HashAlgorithm::Sha256,
+            &[
+                (
+                    b"",
+                    "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+                ),
+                (
+                    b"abc",
+                    "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+                ),
+                (
+                    TWO_BLOCKS,
+                    "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn sha512_reference_vectors() {
+        check(
+            HashAlgorithm::Sha512,
+            &[
+                (
+                    b"",
+                    "cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce\
+                     47d0d13c5d85f2b0ff8318d2877eec2f63b931bd47417a81a538327af927da3e",
+                ),
+                (
+                    b"abc",
+                    "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+                     2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn crc32_reference_vectors() {
+        check(
+            HashAlgorithm::Crc32,
+            &[
+                (b"", "00000000"),
+                (b"123456789", "cbf43926"),
+                (b"The quick brown fox jumps over the lazy dog", "414fa339"),
+            ],
+        );
+    }
+
+    #[test]
+    fn ids_are_unique_and_round_trip() {
+        for algorithm in HashAlgorithm::ALL {
+            assert_eq!(HashAlgorithm::from_id(algorithm.id()), Some(algorithm));
+        }
+        assert_eq!(HashAlgorithm::from_id(0), None);
+    }
+
+    #[test]
+    fn incremental_matches_one_shot() {
+        let data: Vec<u8> = (0..1000).map(|i| (i * 31 % 256) as u8).collect();
+
+        for algorithm in HashAlgorithm::ALL {
+            for len in [0, 55, 56, 63, 64, 111, 112, 127, 128, 129, 1000] {
+                let mut hasher = algorithm.hasher();
+                data[..len].chunks(7).for_each(|piece| hasher.update(piece));
+
+                assert_eq!(
+                    hasher.finalize(),
+                    algorithm.hash(&data[..len]),
+                    "{} len {}",
+                    algorithm,
+                    len
+                );
+            }
+        }
+    }
+}
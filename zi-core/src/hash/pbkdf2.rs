@@ -0,0 +1,80 @@
+//! PBKDF2 (RFC 8018) with HMAC-Tiger as the pseudorandom function.
+
+use super::hmac::Hmac;
+use super::HashAlgorithm;
+
+/// Stretches `password` into `len` bytes. Every output block costs
+/// `iterations` HMAC calls, which is what makes guessing passwords slow.
+pub fn pbkdf2(password: &[u8], salt: &[u8], iterations: u32, len: usize) -> Vec<u8> {
+    // The padded key is absorbed once and the keyed state cloned for every
+    // HMAC call
+    let keyed = Hmac::new(HashAlgorithm::Tiger, password).expect("Tiger is a cryptographic hash");
+    let mut out = Vec::with_capacity(len);
+
+    let mut index: u32 = 1;
+    while out.len() < len {
+        let mut mac = keyed.clone();
+        mac.update(salt);
+        mac.update(&index.to_be_bytes());
+        let mut u = mac.finalize();
+        let mut block = u.clone();
+
+        for _ in 1..iterations {
+            let mut mac = keyed.clone();
+            mac.update(&u);
+            u = mac.finalize();
+
+            block.iter_mut().zip(&u).for_each(|(b, u)| *b ^= u);
+        }
+
+        let take = block.len().min(len - out.len());
+        out.extend_from_slice(&block[..take]);
+        index += 1;
+    }
+
+    return out;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::pbkdf2;
+    use crate::hash::{hmac, HashAlgorithm};
+
+    fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+        hmac::hmac(HashAlgorithm::Tiger, key, data).unwrap()
+    }
+
+    #[test]
+    fn first_block_follows_the_definition() {
+        let password = b"password";
+        let salt = b"salt";
+
+        // U1 = PRF(P, S || INT(1)), U2 = PRF(P, U1), T1 = U1 ^ U2
+        let u1 = hmac(password, b"salt\x00\x00\x00\x01");
+        let u2 = hmac(password, &u1);
+        let t1: Vec<u8> = u1.iter().zip(&u2).map(|(a, b)| a ^ b).collect();
+
+        assert_eq!(pbkdf2(password, salt, 1, 24), u1);
+        assert_eq!(pbkdf2(password, salt, 2, 24), t1);
+    }
+
+    #[test]
+    fn longer_outputs_extend_shorter_ones() {
+        let long = pbkdf2(b"password", b"salt", 50, 64);
+
+        assert_eq!(long.len(), 64);
+        assert_eq!(pbkdf2(b"password", b"salt", 50, 16), long[..16]);
+        assert_eq!(pbkdf2(b"password", b"salt", 50, 32), long[..32]);
+        // The second block starts over from INT(2)
+        assert_ne!(long[..24], long[24..48]);
+    }
+
+    #[test]
+    fn salt_and_iterations_change_the_key() {
+        let key = pbkdf2(b"password", b"salt", 10, 16);
+
+        assert_ne!(pbkdf2(b"password", b"SALT", 10, 16), key);
+        assert_ne!(pbkdf2(b"password", b"salt", 11, 16), key);
+        assert_ne!(pbkdf2(b"Password", b"salt", 10, 16), key);
+    }
+}
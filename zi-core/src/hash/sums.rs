@@ -0,0 +1,342 @@
+//! Checksum files in the `sha256sum` layout, one `<hex digest>  <path>`
+//! line per file with paths relative to the directory the file describes.
+//! The algorithm is told by the extension, e.g. `photos.tiger`.
+
+use std::fmt::Display;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+
+use super::HashAlgorithm;
+
+const READ_BUFFER: usize = 64 * 1024;
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+impl HashAlgorithm {
+    /// Extension of a sum file holding digests of this algorithm
+    pub fn sum_extension(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Tiger => "tiger",
+            HashAlgorithm::Tiger160 => "tiger160",
+            HashAlgorithm::Tiger128 => "tiger128",
+            HashAlgorithm::Tiger2 => "tiger2",
+            HashAlgorithm::Sha1 => "sha1",
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Sha512 => "sha512",
+            HashAlgorithm::Md5 => "md5",
+            HashAlgorithm::Crc32 => "crc32",
+        }
+    }
+
+    pub fn from_sum_file(path: &Path) -> Option<HashAlgorithm> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+
+        HashAlgorithm::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.sum_extension() == extension)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FileDigest {
+    /// Relative to the hashed directory, always with `/` separators
+    pub name: String,
+    pub digest: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Check {
+    Ok,
+    Mismatch,
+    Missing,
+}
+
+impl Display for Check {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Check::Ok => "OK",
+                Check::Mismatch => "MISMATCH",
+                Check::Missing => "MISSING",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FileCheck {
+    pub name: String,
+    pub check: Check,
+}
+
+/// Every file below `dir`, sorted so sum files come out the same each time
+pub fn collect_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        let entries =
+            std::fs::read_dir(&dir).with_context(|| format!("Could not read {}", dir.display()))?;
+
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+
+    return Ok(files);
+}
+
+pub fn relative_name(base: &Path, path: &Path) -> String {
+    path.strip_prefix(base)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace(std::path::MAIN_SEPARATOR, "/")
+}
+
+/// Hashes `files`, naming them relative to `base`. `progress` gets the
+/// fraction of all bytes read so far.
+pub fn hash_files(
+    algorithm: HashAlgorithm,
+    base: &Path,
+    files: &[PathBuf],
+    progress: &mut dyn FnMut(f32),
+) -> Result<Vec<FileDigest>> {
+    let total: u64 = files
+        .iter()
+        .map(|path| path.metadata().map(|meta| meta.len()).unwrap_or(0))
+        .sum();
+    let mut done = 0;
+
+    files
+        .iter()
+        .map(|path| {
+            let digest = hash_file(algorithm, path, &mut |read| {
+                done += read;
+                progress(done as f32 / total.max(1) as f32);
+            })?;
+
+            Ok(FileDigest {
+                name: relative_name(base, path),
+                digest,
+            })
+        })
+        .collect()
+}
+
+fn hash_file(
+    algorithm: HashAlgorithm,
+    path: &Path,
+    progress: &mut dyn FnMut(u64),
+) -> Result<Vec<u8>> {
+    let mut file =
+        File::open(path).with_context(|| format!("Could not open {}", path.display()))?;
+    let mut hasher = algorithm.hasher();
+    let mut buffer = vec![0; READ_BUFFER];
+
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        progress(read as u64);
+    }
+
+    return Ok(hasher.finalize());
+}
+
+pub fn format(digests: &[FileDigest]) -> String {
+    digests
+        .iter()
+        .map(|file| format!("{}  {}\n", hex(&file.digest), file.name))
+        .collect()
+}
+
+/// Reads the lines of a sum file into (digest, name) pairs. A `*` before
+/// the name marks binary mode in coreutils and is ignored.
+pub fn parse(text: &str) -> Result<Vec<(Vec<u8>, String)>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| {
+            let malformed = || anyhow!("Line {} of the sum file is malformed", number + 1);
+
+            let (digest, name) = line.split_once(' ').ok_or_else(malformed)?;
+            let name = name.strip_prefix([' ', '*']).ok_or_else(malformed)?;
+            if name.is_empty() {
+                return Err(malformed());
+            }
+
+            Ok((from_hex(digest).ok_or_else(malformed)?, name.to_string()))
+        })
+        .collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Checks every file listed in `sums` against the files below `dir`. Files
+/// in the directory that the sum file does not mention are not reported.
+pub fn verify(
+    algorithm: HashAlgorithm,
+    dir: &Path,
+    sums: &str,
+    progress: &mut dyn FnMut(f32),
+) -> Result<Vec<FileCheck>> {
+    let entries = parse(sums)?;
+    let count = entries.len();
+
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(i, (expected, name))| {
+            let path = dir.join(&name);
+
+            let check = if !path.is_file() {
+                Check::Missing
+            } else if hash_file(algorithm, &path, &mut |_| {})? == expected {
+                Check::Ok
+            } else {
+                Check::Mismatch
+            };
+            progress((i + 1) as f32 / count as f32);
+
+            Ok(FileCheck { name, check })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{collect_files, format, hash_files, parse, verify, Check};
+    use crate::hash::HashAlgorithm;
+    use crate::utils::TempDir;
+
+    fn write(dir: &Path, name: &str, data: &[u8]) {
+        let path = dir.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, data).unwrap();
+    }
+
+    fn directory() -> TempDir {
+        let dir = TempDir::new().unwrap();
+
+        write(dir.path(), "abc.txt", b"abc");
+        write(dir.path(), "nested/empty", b"");
+        write(dir.path(), "nested/deeper/data.bin", &[0xA5; 5000]);
+
+        dir
+    }
+
+    #[test]
+    fn sum_file_lists_relative_paths() {
+        let dir = directory();
+        let files = collect_files(dir.path()).unwrap();
+        let digests = hash_files(HashAlgorithm::Sha256, dir.path(), &files, &mut |_| {}).unwrap();
+
+        let sums = format(&digests);
+        let lines: Vec<&str> = sums.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0],
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  abc.txt"
+        );
+        assert!(lines[1].ends_with("  nested/deeper/data.bin"));
+        assert!(lines[2].ends_with("  nested/empty"));
+    }
+
+    #[test]
+    fn verify_reports_every_listed_file() {
+        let dir = directory();
+        let files = collect_files(dir.path()).unwrap();
+        let digests = hash_files(HashAlgorithm::Tiger, dir.path(), &files, &mut |_| {}).unwrap();
+        let sums = format(&digests);
+
+        write(dir.path(), "abc.txt", b"abd");
+        std::fs::remove_file(dir.path().join("nested/empty")).unwrap();
+        write(dir.path(), "unlisted", b"not in the sum file");
+
+        let mut progress = 0.0;
+        let checks = verify(HashAlgorithm::Tiger, dir.path(), &sums, &mut |done| {
+            progress = done
+        })
+        .unwrap();
+        let checks: Vec<(&str, Check)> = checks
+            .iter()
+            .map(|file| (file.name.as_str(), file.check))
+            .collect();
+
+        assert_eq!(
+            checks,
+            [
+                ("abc.txt", Check::Mismatch),
+                ("nested/deeper/data.bin", Check::Ok),
+                ("nested/empty", Check::Missing),
+            ]
+        );
+        assert_eq!(progress, 1.0);
+    }
+
+    #[test]
+    fn parse_accepts_coreutils_output() {
+        let entries = parse("# comment\n00ff  a b.txt\n\nABCD *binary\n").unwrap();
+
+        assert_eq!(
+            entries,
+            [
+                (vec![0x00, 0xFF], String::from("a b.txt")),
+                (vec![0xAB, 0xCD], String::from("binary")),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_rejects_malformed_lines() {
+        assert!(parse("00ff").is_err());
+        assert!(parse("0ff  name").is_err());
+        assert!(parse("zz  name").is_err());
+        assert!(parse("00ff  ").is_err());
+    }
+
+    #[test]
+    fn algorithm_is_told_by_the_extension() {
+        for algorithm in HashAlgorithm::ALL {
+            let name = format!("photos.{}", algorithm.sum_extension());
+            assert_eq!(
+                HashAlgorithm::from_sum_file(Path::new(&name)),
+                Some(algorithm)
+            );
+        }
+
+        assert_eq!(
+            HashAlgorithm::from_sum_file(Path::new("SUMS.SHA256")),
+            Some(HashAlgorithm::Sha256)
+        );
+        assert_eq!(HashAlgorithm::from_sum_file(Path::new("photos.txt")), None);
+    }
+}
@@ -0,0 +1,337 @@
+use super::block::{BlockBuffer, LengthField};
+use super::s_boxes::{T1, T2, T3, T4};
+use super::Hasher;
+
+type State = [u64; 3];
+const S0: State = [
+    0x0123_4567_89AB_CDEF,
+    0xFEDC_BA98_7654_3210,
+    0xF096_A5B4_C3B2_E187,
+];
+
+const BLOCK_SIZE: usize = 64;
+
+/// Padding and output length. Tiger2 differs from Tiger only in the padding
+/// byte, Tiger/160 and Tiger/128 are prefixes of the Tiger digest.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum TigerVariant {
+    #[default]
+    Tiger,
+    Tiger160,
+    Tiger128,
+    Tiger2,
+}
+
+impl TigerVariant {
+    /// First byte appended after the message
+    fn padding_byte(&self) -> u8 {
+        match self {
+            TigerVariant::Tiger2 => 0x80,
+            _ => 0x01,
+        }
+    }
+
+    /// Digest length in bytes
+    pub fn output_len(&self) -> usize {
+        match self {
+            TigerVariant::Tiger160 => 20,
+            TigerVariant::Tiger128 => 16,
+            _ => 24,
+        }
+    }
+}
+
+/// Incremental Tiger, for data that does not fit in memory or arrives in
+/// pieces. Feeding the same bytes in any split gives the same digest.
+#[derive(Clone)]
+pub struct Tiger {
+    variant: TigerVariant,
+    state: State,
+    buffer: BlockBuffer<BLOCK_SIZE>,
+}
+
+impl Default for Tiger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tiger {
+    pub fn new() -> Tiger {
+        Tiger::with_variant(TigerVariant::Tiger)
+    }
+
+    pub fn with_variant(variant: TigerVariant) -> Tiger {
+        Tiger {
+            variant,
+            state: S0,
+            buffer: BlockBuffer::new(),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        let state = &mut self.state;
+        self.buffer
+            .update(data, |block| *state = compress(*state, block));
+    }
+
+    /// Pads the last block with the variant's padding byte, zeroes and the
+    /// bit length, and returns the digest, truncated for Tiger/160 and /128
+    pub fn finalize(self) -> Vec<u8> {
+        let Tiger {
+            variant,
+            mut state,
+            buffer,
+        } = self;
+        buffer.finalize(variant.padding_byte(), LengthField::Le64, |block| {
+            state = compress(state, block)
+        });
+
+        return state
+            .iter()
+            .flat_map(|&n| n.to_le_bytes())
+            .take(variant.output_len())
+            .collect();
+    }
+}
+
+impl Hasher for Tiger {
+    fn update(&mut self, data: &[u8]) {
+        Tiger::update(self, data);
+    }
+
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        Tiger::finalize(*self)
+    }
+
+    fn block_size(&self) -> usize {
+        BLOCK_SIZE
+    }
+
+    fn box_clone(&self) -> Box<dyn Hasher> {
+        Box::new(self.clone())
+    }
+}
+
+pub fn hash_data(data: &[u8]) -> Vec<u8> {
+    return hash_data_with(TigerVariant::Tiger, data);
+}
+
+pub fn hash_data_with(variant: TigerVariant, data: &[u8]) -> Vec<u8> {
+    let mut tiger = Tiger::with_variant(variant);
+    tiger.update(data);
+
+    return tiger.finalize();
+}
+
+fn compress(mut state: State, raw_block: &[u8; 64]) -> State {
+    let mut block: [u64; 8] = Default::default();
+
+    // [u8; 64] u [u64; 8]
+    for (i, chunk) in raw_block.chunks_exact(8).enumerate() {
+        block[i] = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+
+    let [mut a, mut b, mut c] = state;
+
+    pass(&mut a, &mut b, &mut c, &block, 5);
+    key_schedule(&mut block);
+    pass(&mut c, &mut a, &mut b, &block, 7);
+    key_schedule(&mut block);
+    pass(&mut b, &mut c, &mut a, &block, 9);
+
+    state[0] ^= a;
+    state[1] = b.wrapping_sub(state[1]);
+    state[2] = c.wrapping_add(state[2]);
+
+    state
+}
+
+fn round(a: &mut u64, b: &mut u64, c: &mut u64, x: &u64, mul: u8) {
+    *c ^= *x;
+
+    let c2: [u8; 8] = c.to_le_bytes();
+
+    let a2 = T1[usize::from(c2[0])]
+        ^ T2[usize::from(c2[2])]
+        ^ T3[usize::from(c2[4])]
+        ^ T4[usize::from(c2[6])];
+
+    let b2 = T4[usize::from(c2[1])]
+        ^ T3[usize::from(c2[3])]
+        ^ T2[usize::from(c2[5])]
+        ^ T1[usize::from(c2[7])];
+
+    *a = a.wrapping_sub(a2);
+    *b = b.wrapping_add(b2).wrapping_mul(u64::from(mul));
+}
+
+fn pass(a: &mut u64, b: &mut u64, c: &mut u64, x: &[u64; 8], mul: u8) {
+    round(a, b, c, &x[0], mul);
+    round(b, c, a, &x[1], mul);
+    round(c, a, b, &x[2], mul);
+    round(a, b, c, &x[3], mul);
+    round(b, c, a, &x[4], mul);
+    round(c, a, b, &x[5], mul);
+    round(a, b, c, &x[6], mul);
+    round(b, c, a, &x[7], mul);
+}
+
+fn key_schedule(x: &mut [u64; 8]) {
+    x[0] = x[0].wrapping_sub(x[7] ^ 0xA5A5_A5A5_A5A5_A5A5);
+    x[1] ^= x[0];
+    x[2] = x[2].wrapping_add(x[1]);
+    x[3] = x[3].wrapping_sub(x[2] ^ ((!x[1]) << 19));
+    x[4] ^= x[3];
+    x[5] = x[5].wrapping_add(x[4]);
+    x[6] = x[6].wrapping_sub(x[5] ^ ((!x[4]) >> 23));
+    x[7] ^= x[6];
+    x[0] = x[0].wrapping_add(x[7]);
+    x[1] = x[1].wrapping_sub(x[0] ^ ((!x[7]) << 19));
+    x[2] ^= x[1];
+    x[3] = x[3].wrapping_add(x[2]);
+    x[4] = x[4].wrapping_sub(x[3] ^ ((!x[2]) >> 23));
+    x[5] ^= x[4];
+    x[6] = x[6].wrapping_add(x[5]);
+    x[7] = x[7].wrapping_sub(x[6] ^ 0x0123_4567_89AB_CDEF);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hash_data, hash_data_with, Tiger, TigerVariant};
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02X}", b)).collect()
+    }
+
+    const ALPHANUMERIC: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+-";
+
+    /// Reference vectors from the Tiger authors' page and NESSIE
+    #[test]
+    fn tiger_reference_vectors() {
+        let vectors: [(&[u8], &str); 12] = [
+            (b"", "3293AC630C13F0245F92BBB1766E16167A4E58492DDE73F3"),
+            (b"a", "77BEFBEF2E7EF8AB2EC8F93BF587A7FC613E247F5F247809"),
+            (b"abc", "2AAB1484E8C158F2BFB8C5FF41B57A525129131C957B5F93"),
+            (b"Tiger", "DD00230799F5009FEC6DEBC838BB6A27DF2B9D6F110C7937"),
+            (
+                b"message digest",
+                "D981F8CB78201A950DCF3048751E441C517FCA1AA55A29F6",
+            ),
+            (
+                b"abcdefghijklmnopqrstuvwxyz",
+                "1714A472EEE57D30040412BFCC55032A0B11602FF37BEEE9",
+            ),
+            // 56 bytes, the length no longer fits in the last block
+            (
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+                "0F7BF9A19B9C58F2B7610DF7E84F0AC3A71C631E7B53F78E",
+            ),
+            // 64 bytes, exactly one block before the padding block
+            (
+                ALPHANUMERIC.as_bytes(),
+                "F71C8583902AFB879EDFE610F82C0D4786A3A534504486B5",
+            ),
+            (
+                b"ABCDEFGHIJKLMNOPQRSTUVWXYZ=abcdefghijklmnopqrstuvwxyz+0123456789",
+                "48CEEB6308B87D46E95D656112CDF18D97915F9765658957",
+            ),
+            (
+                b"Tiger - A Fast New Hash Function, by Ross Anderson and Eli Biham",
+                "8A866829040A410C729AD23F5ADA711603B3CDD357E4C15E",
+            ),
+            (
+                b"Tiger - A Fast New Hash Function, by Ross Anderson and Eli Biham, \
+                  proceedings of Fast Software Encryption 3, Cambridge.",
+                "CE55A6AFD591F5EBAC547FF84F89227F9331DAB0B611C889",
+            ),
+            (
+                b"Tiger - A Fast New Hash Function, by Ross Anderson and Eli Biham, \
+                  proceedings of Fast Software Encryption 3, Cambridge, 1996.",
+                "631ABDD103EB9A3D245B6DFD4D77B257FC7439501D1568DD",
+            ),
+        ];
+
+        for (data, expected) in vectors {
+            assert_eq!(
+                hex(&hash_data(data)),
+                expected,
+                "{}",
+                String::from_utf8_lossy(data)
+            );
+        }
+    }
+
+    #[test]
+    fn tiger_million_a() {
+        let mut tiger = Tiger::new();
+        let block = [b'a'; 1000];
+        (0..1000).for_each(|_| tiger.update(&block));
+
+        assert_eq!(
+            hex(&tiger.finalize()),
+            "6DB0E2729CBEAD93D715C6A7D36302E9B3CEE0D2BC314B41"
+        );
+    }
+
+    #[test]
+    fn tiger2_reference_vectors() {
+        let vectors: [(&[u8], &str); 2] = [
+            (b"", "4441BE75F6018773C206C22745374B924AA8313FEF919F41"),
+            (b"abc", "F68D7BC5AF4B43A06E048D7829560D4A9415658BB0B1F3BF"),
+        ];
+
+        for (data, expected) in vectors {
+            assert_eq!(hex(&hash_data_with(TigerVariant::Tiger2, data)), expected);
+        }
+    }
+
+    #[test]
+    fn truncated_variants_are_prefixes() {
+        for data in [&b""[..], b"abc", ALPHANUMERIC.as_bytes()] {
+            let full = hash_data(data);
+
+            assert_eq!(hash_data_with(TigerVariant::Tiger160, data), full[..20]);
+            assert_eq!(hash_data_with(TigerVariant::Tiger128, data), full[..16]);
+        }
+        assert_eq!(
+            hex(&hash_data_with(TigerVariant::Tiger128, b"")),
+            "3293AC630C13F0245F92BBB1766E1616"
+        );
+    }
+
+    fn data(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 131 % 251) as u8).collect()
+    }
+
+    #[test]
+    fn incremental_matches_one_shot() {
+        // Around every padding edge: empty, one short of the length field,
+        // exactly at it, full blocks and a bit past them
+        for len in [0, 1, 55, 56, 57, 63, 64, 65, 119, 120, 127, 128, 129, 1000] {
+            let data = data(len);
+            let expected = hash_data(&data);
+
+            for chunk in [1, 3, 7, 63, 64, 65, 100] {
+                let mut tiger = Tiger::new();
+                data.chunks(chunk).for_each(|piece| tiger.update(piece));
+
+                assert_eq!(tiger.finalize(), expected, "len {} chunk {}", len, chunk);
+            }
+        }
+    }
+
+    #[test]
+    fn empty_updates_change_nothing() {
+        let data = data(200);
+
+        let mut tiger = Tiger::new();
+        tiger.update(&[]);
+        tiger.update(&data[..70]);
+        tiger.update(&[]);
+        tiger.update(&data[70..]);
+
+        assert_eq!(tiger.finalize(), hash_data(&data));
+        assert_ne!(hash_data(&data[..199]), hash_data(&data));
+    }
+}
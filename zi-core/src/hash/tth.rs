@@ -0,0 +1,335 @@
+//! Tiger Tree Hash as described in THEX, the Merkle tree used by file
+//! sharing tools: 1024-byte leaves, prefix bytes separating leaf and inner
+//! nodes, and a lone node at the end of a level promoted unchanged.
+
+use super::tiger::Tiger;
+
+/// Bytes of data under each leaf
+pub const LEAF_SIZE: usize = 1024;
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// The sibling needed at one level of the path from a leaf to the root,
+/// and on which side of the running hash it goes
+#[derive(Clone, PartialEq, Debug)]
+pub enum Sibling {
+    Left(Vec<u8>),
+    Right(Vec<u8>),
+}
+
+/// Every level of the tree, leaves first and the root last
+pub struct TigerTree {
+    levels: Vec<Vec<Vec<u8>>>,
+}
+
+impl TigerTree {
+    pub fn new(data: &[u8]) -> TigerTree {
+        // Empty input still has a single, empty leaf
+        if data.is_empty() {
+            return TigerTree::from_leaves(vec![leaf_hash(data)]);
+        }
+
+        TigerTree::from_leaves(data.chunks(LEAF_SIZE).map(leaf_hash).collect())
+    }
+
+    /// Builds the tree over leaf hashes computed elsewhere, e.g. one chunk at
+    /// a time while the data is being read. The roots of runs of 2^n leaves
+    /// work as well and give the same root, the tree is then only missing
+    /// the levels below them.
+    pub fn from_leaves(leaves: Vec<Vec<u8>>) -> TigerTree {
+        let mut levels = vec![leaves];
+
+        while levels.last().unwrap().len() > 1 {
+            let level = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => node_hash(left, right),
+                    [lone] => lone.clone(),
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(level);
+        }
+
+        return TigerTree { levels };
+    }
+
+    pub fn leaves(&self) -> &[Vec<u8>] {
+        &self.levels[0]
+    }
+
+    pub fn root(&self) -> &[u8] {
+        // Only a tree built from no leaves at all has no root
+        self.levels
+            .last()
+            .and_then(|level| level.first())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Root in the unpadded Base32 used by `urn:tree:tiger:` links
+    pub fn root_base32(&self) -> String {
+        base32(self.root())
+    }
+
+    /// Siblings from the leaf at `index` up to the root, `None` when there is
+    /// no such leaf. Promoted nodes have no sibling and add nothing.
+    pub fn path(&self, mut index: usize) -> Option<Vec<Sibling>> {
+        if index >= self.leaves().len() {
+            return None;
+        }
+
+        let mut path = Vec::new();
+        for level in &self.levels[..self.levels.len() - 1] {
+            if index % 2 == 1 {
+                path.push(Sibling::Left(level[index - 1].clone()));
+            } else if let Some(right) = level.get(index + 1) {
+                path.push(Sibling::Right(right.clone()));
+            }
+            index /= 2;
+        }
+
+        return Some(path);
+    }
+}
+
+pub fn leaf_hash(chunk: &[u8]) -> Vec<u8> {
+    let mut tiger = Tiger::new();
+    tiger.update(&[LEAF_PREFIX]);
+    tiger.update(chunk);
+
+    return tiger.finalize();
+}
+
+fn node_hash(left: &[u8], right: &[u8]) -> Vec<u8> {
+    let mut tiger = Tiger::new();
+    tiger.update(&[NODE_PREFIX]);
+    tiger.update(left);
+    tiger.update(right);
+
+    return tiger.finalize();
+}
+
+/// Sides the siblings of the leaf at `index` sit on in a tree of `leaves`
+/// leaves, `true` for left, `None` when there is no such leaf. Every leaf has
+/// its own, so a path only leads to the root from the place it was made for.
+fn path_sides(mut index: usize, mut leaves: usize) -> Option<Vec<bool>> {
+    if index >= leaves {
+        return None;
+    }
+
+    let mut sides = Vec::new();
+    while leaves > 1 {
+        if index % 2 == 1 {
+            sides.push(true);
+        } else if index + 1 < leaves {
+            sides.push(false);
+        }
+        index /= 2;
+        leaves = leaves.div_ceil(2);
+    }
+
+    return Some(sides);
+}
+
+/// Checks the chunk at `index` of `leaves` against the root using its sibling
+/// path, without needing any of the other chunks. A chunk is one leaf, or a
+/// run of 2^n leaves with the path taken from a tree built over the roots of
+/// such runs. A path made for another index is refused.
+pub fn verify_chunk(
+    root: &[u8],
+    chunk: &[u8],
+    index: usize,
+    leaves: usize,
+    path: &[Sibling],
+) -> bool {
+    let Some(sides) = path_sides(index, leaves) else {
+        return false;
+    };
+    let matches_index = sides.len() == path.len()
+        && sides
+            .iter()
+            .zip(path)
+            .all(|(&left, sibling)| left == matches!(sibling, Sibling::Left(_)));
+    if !matches_index {
+        return false;
+    }
+
+    let computed =
+        path.iter().fold(
+            TigerTree::new(chunk).root().to_vec(),
+            |hash, sibling| match sibling {
+                Sibling::Left(left) => node_hash(left, &hash),
+                Sibling::Right(right) => node_hash(&hash, right),
+            },
+        );
+
+    return computed == root;
+}
+
+/// RFC 4648 Base32 without the `=` padding
+pub fn base32(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let mut buffer: u16 = 0;
+    let mut bits = 0;
+
+    for &byte in bytes {
+        buffer = (buffer << 8) | u16::from(byte);
+        bits += 8;
+
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(BASE32_ALPHABET[usize::from((buffer >> bits) & 0x1F)] as char);
+        }
+    }
+
+    if bits > 0 {
+        encoded.push(BASE32_ALPHABET[usize::from((buffer << (5 - bits)) & 0x1F)] as char);
+    }
+
+    return encoded;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{base32, path_sides, verify_chunk, Sibling, TigerTree, LEAF_SIZE};
+
+    /// Vectors from the THEX specification and the reference implementations
+    #[test]
+    fn thex_reference_vectors() {
+        let vectors: [(Vec<u8>, &str); 4] = [
+            (vec![], "LWPNACQDBZRYXW3VHJVCJ64QBZNGHOHHHZWCLNQ"),
+            (vec![0], "VK54ZIEEVTWNAUI5D5RDFIL37LX2IQNSTAXFKSA"),
+            (vec![b'A'; 1024], "L66Q4YVNAFWVS23X2HJIRA5ZJ7WXR3F26RSASFA"),
+            (vec![b'A'; 1025], "PZMRYHGY6LTBEH63ZWAHDORHSYTLO4LEFUIKHWY"),
+        ];
+
+        for (data, expected) in vectors {
+            assert_eq!(
+                TigerTree::new(&data).root_base32(),
+                expected,
+                "{} bytes",
+                data.len()
+            );
+        }
+    }
+
+    #[test]
+    fn base32_rfc_vectors() {
+        let vectors = [
+            ("", ""),
+            ("f", "MY"),
+            ("fo", "MZXQ"),
+            ("foo", "MZXW6"),
+            ("foob", "MZXW6YQ"),
+            ("fooba", "MZXW6YTB"),
+            ("foobar", "MZXW6YTBOI"),
+        ];
+
+        for (data, expected) in vectors {
+            assert_eq!(base32(data.as_bytes()), expected);
+        }
+    }
+
+    #[test]
+    fn every_chunk_verifies_against_the_root() {
+        // Five leaves, so the last one is promoted twice on its way up
+        let data: Vec<u8> = (0..LEAF_SIZE * 4 + 100).map(|i| (i % 251) as u8).collect();
+        let tree = TigerTree::new(&data);
+        assert_eq!(tree.leaves().len(), 5);
+
+        for (i, chunk) in data.chunks(LEAF_SIZE).enumerate() {
+            let path = tree.path(i).unwrap();
+            assert!(verify_chunk(tree.root(), chunk, i, 5, &path), "chunk {}", i);
+        }
+        assert_eq!(tree.path(4).unwrap().len(), 1);
+        assert_eq!(tree.path(5), None);
+    }
+
+    #[test]
+    fn runs_of_leaves_verify_like_single_leaves() {
+        // Four leaves per run, the last run has only three
+        let run = LEAF_SIZE * 4;
+        let data: Vec<u8> = (0..run * 2 + LEAF_SIZE * 2 + 5)
+            .map(|i| (i % 13) as u8)
+            .collect();
+        let runs = TigerTree::from_leaves(
+            data.chunks(run)
+                .map(|chunk| TigerTree::new(chunk).root().to_vec())
+                .collect(),
+        );
+
+        assert_eq!(runs.root(), TigerTree::new(&data).root());
+        for (i, chunk) in data.chunks(run).enumerate() {
+            assert!(verify_chunk(
+                runs.root(),
+                chunk,
+                i,
+                3,
+                &runs.path(i).unwrap()
+            ));
+        }
+        assert!(!verify_chunk(
+            runs.root(),
+            &data[1..=run],
+            0,
+            3,
+            &runs.path(0).unwrap()
+        ));
+    }
+
+    #[test]
+    fn tampered_chunk_or_path_fails() {
+        let data: Vec<u8> = (0..LEAF_SIZE * 3).map(|i| (i % 7) as u8).collect();
+        let tree = TigerTree::new(&data);
+        let path = tree.path(1).unwrap();
+
+        let mut chunk = data[LEAF_SIZE..LEAF_SIZE * 2].to_vec();
+        assert!(verify_chunk(tree.root(), &chunk, 1, 3, &path));
+
+        chunk[10] ^= 1;
+        assert!(!verify_chunk(tree.root(), &chunk, 1, 3, &path));
+
+        // Right hash, wrong side
+        let chunk = &data[LEAF_SIZE..LEAF_SIZE * 2];
+        let mut swapped = path.clone();
+        swapped[0] = match &path[0] {
+            Sibling::Left(hash) => Sibling::Right(hash.clone()),
+            Sibling::Right(hash) => Sibling::Left(hash.clone()),
+        };
+        assert!(!verify_chunk(tree.root(), chunk, 1, 3, &swapped));
+    }
+
+    #[test]
+    fn paths_only_verify_at_their_own_index() {
+        // Identical leaves, so only the index tells the chunks apart
+        let data = vec![0x5A; LEAF_SIZE * 6];
+        let tree = TigerTree::new(&data);
+        let chunk = &data[..LEAF_SIZE];
+
+        for i in 0..6 {
+            let path = tree.path(i).unwrap();
+            for at in 0..7 {
+                assert_eq!(verify_chunk(tree.root(), chunk, at, 6, &path), at == i);
+            }
+        }
+    }
+
+    #[test]
+    fn every_index_has_its_own_sides() {
+        for leaves in 1..70 {
+            let mut seen: Vec<Vec<bool>> = Vec::new();
+            for index in 0..leaves {
+                let sides = path_sides(index, leaves).unwrap();
+                assert!(!seen.contains(&sides), "{} of {}", index, leaves);
+                seen.push(sides);
+            }
+            assert_eq!(path_sides(leaves, leaves), None);
+        }
+    }
+}
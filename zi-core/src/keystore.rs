@@ -0,0 +1,209 @@
+//! A file of secrets sealed under a master password.
+//!
+//! Layout: [`MAGIC`], version, PBKDF2 iterations (u32 LE), salt, then the
+//! ChaCha20-Poly1305 output. The header is authenticated along with the
+//! contents, so lowering the iterations breaks the tag.
+
+use std::fmt::{Debug, Display};
+
+use anyhow::anyhow;
+use zeroize::Zeroizing;
+
+use crate::algorithms::chacha20poly1305::alg::{self as chacha20poly1305, ChaCha20Poly1305};
+use crate::algorithms::kdf::{KeyDerivation, MAX_ITERATIONS, SALT_LEN};
+use crate::algorithms::AuthenticationError;
+use crate::config::Secret;
+use crate::hash::hmac::constant_time_eq;
+
+pub const MAGIC: &[u8; 4] = b"ZIKS";
+pub const FORMAT_VERSION: u8 = 1;
+
+const HEADER_LEN: usize = MAGIC.len() + 1 + 4 + SALT_LEN;
+
+/// The master password does not open the keystore
+#[derive(Debug)]
+pub struct WrongPasswordError;
+
+impl Display for WrongPasswordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Wrong master password, or the keystore was tampered with"
+        )
+    }
+}
+
+impl std::error::Error for WrongPasswordError {}
+
+/// The key derived from the master password. It is kept while unlocked, so
+/// saving doesn't run the KDF again.
+#[derive(Clone)]
+pub struct Keystore {
+    kdf: KeyDerivation,
+    key: Zeroizing<Vec<u8>>,
+}
+
+impl Debug for Keystore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Keystore")
+            .field("kdf", &self.kdf)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Keystore {
+    /// A keystore under a fresh salt, also used to change the password
+    pub fn create(password: &str, iterations: u32) -> Keystore {
+        let kdf = KeyDerivation::random(iterations);
+        let key = kdf.derive(password, chacha20poly1305::KEY_SIZE);
+
+        Keystore { kdf, key }
+    }
+
+    /// Unlocks `data` and returns the keystore with its contents
+    pub fn open(password: &str, data: &[u8]) -> anyhow::Result<(Keystore, Secret)> {
+        if data.len() < HEADER_LEN || !data.starts_with(MAGIC) {
+            return Err(anyhow!("This is not a keystore"));
+        }
+
+        let (header, sealed) = data.split_at(HEADER_LEN);
+        let version = header[MAGIC.len()];
+        if version != FORMAT_VERSION {
+            return Err(anyhow!(
+                "Keystore version {} is not supported, expected {}",
+                version,
+                FORMAT_VERSION
+            ));
+        }
+
+        let iterations = u32::from_le_bytes(header[MAGIC.len() + 1..][..4].try_into().unwrap());
+        if iterations == 0 {
+            return Err(anyhow!("The keystore has no KDF iterations"));
+        }
+        if iterations > MAX_ITERATIONS {
+            return Err(anyhow!(
+                "The keystore asks for {} KDF iterations, at most {} are allowed",
+                iterations,
+                MAX_ITERATIONS
+            ));
+        }
+
+        let kdf = KeyDerivation {
+            salt: header[HEADER_LEN - SALT_LEN..].try_into().unwrap(),
+            iterations,
+        };
+        let key = kdf.derive(password, chacha20poly1305::KEY_SIZE);
+        let keystore = Keystore { kdf, key };
+
+        let contents = match keystore.cipher().open(sealed, header) {
+            Ok(contents) => Zeroizing::new(contents),
+            Err(err) if err.is::<AuthenticationError>() => return Err(WrongPasswordError.into()),
+            Err(err) => return Err(err),
+        };
+        let contents = std::str::from_utf8(&contents)
+            .map_err(|_| anyhow!("The keystore contents are not text"))?
+            .to_owned();
+
+        return Ok((keystore, Secret::new(contents)));
+    }
+
+    /// The file holding `contents`, sealed under a new nonce
+    pub fn seal(&self, contents: &str) -> Vec<u8> {
+        let header = self.header();
+        let sealed = self
+            .cipher()
+            .seal(contents.as_bytes(), &header)
+            .expect("A keystore fits in one ChaCha20 stream");
+
+        [header, sealed].concat()
+    }
+
+    /// Whether `password` is the one the keystore was unlocked with
+    pub fn matches(&self, password: &str) -> bool {
+        let key = self.kdf.derive(password, chacha20poly1305::KEY_SIZE);
+
+        constant_time_eq(&key, &self.key)
+    }
+
+    pub fn iterations(&self) -> u32 {
+        self.kdf.iterations
+    }
+
+    fn header(&self) -> Vec<u8> {
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(MAGIC);
+        header.push(FORMAT_VERSION);
+        header.extend_from_slice(&self.kdf.iterations.to_le_bytes());
+        header.extend_from_slice(&self.kdf.salt);
+
+        header
+    }
+
+    fn cipher(&self) -> ChaCha20Poly1305 {
+        ChaCha20Poly1305::new(self.key[..].try_into().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Keystore, WrongPasswordError, HEADER_LEN, MAGIC};
+    use crate::algorithms::kdf::MAX_ITERATIONS;
+
+    #[test]
+    fn contents_round_trip() {
+        let keystore = Keystore::create("master", 10);
+        let sealed = keystore.seal("aes-key = s3cret\n");
+
+        assert!(sealed.starts_with(MAGIC));
+        assert!(!sealed
+            .windows(b"s3cret".len())
+            .any(|window| window == b"s3cret"));
+
+        let (opened, contents) = Keystore::open("master", &sealed).unwrap();
+        assert_eq!(contents.as_str(), "aes-key = s3cret\n");
+        assert_eq!(opened.iterations(), 10);
+
+        // Same key, so saving again needs no password
+        let (_, again) = Keystore::open("master", &opened.seal("changed")).unwrap();
+        assert_eq!(again.as_str(), "changed");
+    }
+
+    #[test]
+    fn wrong_passwords_and_tampering_are_caught() {
+        let sealed = Keystore::create("master", 10).seal("secret");
+
+        let wrong = Keystore::open("Master", &sealed).unwrap_err();
+        assert!(wrong.is::<WrongPasswordError>());
+
+        // Fewer iterations would make guessing cheaper
+        let mut weakened = sealed.clone();
+        weakened[MAGIC.len() + 1] = 5;
+        let weakened = Keystore::open("master", &weakened).unwrap_err();
+        assert!(weakened.is::<WrongPasswordError>());
+
+        // More would be checked only after running them all
+        let mut expensive = sealed.clone();
+        expensive[MAGIC.len() + 1..][..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let expensive = Keystore::open("master", &expensive).unwrap_err();
+        assert!(!expensive.is::<WrongPasswordError>());
+        assert!(expensive.to_string().contains(&MAX_ITERATIONS.to_string()));
+
+        let mut flipped = sealed.clone();
+        *flipped.last_mut().unwrap() ^= 1;
+        assert!(Keystore::open("master", &flipped).is_err());
+
+        assert!(Keystore::open("master", &sealed[..HEADER_LEN]).is_err());
+        assert!(Keystore::open("master", b"aes-key = plain").is_err());
+    }
+
+    #[test]
+    fn new_passwords_get_a_new_salt() {
+        let old = Keystore::create("master", 10);
+        let new = Keystore::create("master", 10);
+
+        assert_ne!(old.kdf.salt, new.kdf.salt);
+        assert!(new.matches("master"));
+        assert!(!new.matches("master "));
+        assert!(Keystore::open("master", &new.seal("x")).is_ok());
+    }
+}
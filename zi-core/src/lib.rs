@@ -0,0 +1,19 @@
+//! Everything ZI does without a window: the ciphers, hashing, the container
+//! format and the TCP transfer protocol. Front ends parse their input into a
+//! [`config::Config`] and hand it to [`utils`] or [`transfer`].
+
+#![allow(
+    clippy::needless_return,
+    clippy::upper_case_acronyms,
+    clippy::module_inception,
+    clippy::enum_variant_names,
+    clippy::new_without_default
+)]
+
+pub mod algorithms;
+pub mod config;
+pub mod container;
+pub mod hash;
+pub mod keystore;
+pub mod transfer;
+pub mod utils;
@@ -0,0 +1,747 @@
+//! Sending encrypted files over TCP.
+//!
+//! A transfer is one connection speaking [`protocol`]. The digest in the
+//! file header covers the other header fields, then the ciphertext, and is
+//! an HMAC when a shared secret is set. The receiver checks it with its own
+//! hash setting, never with the one the header names.
+//!
+//! Each chunk is also checked against the Tiger tree root in the header as it
+//! arrives, so a transfer corrupted on the way stops at the first bad chunk.
+//! The tree root is only trusted once the digest covering it matches.
+
+pub mod protocol;
+
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader, BufWriter};
+use tokio::net::TcpStream;
+
+use crate::algorithms::Operation;
+use crate::config::Config;
+use crate::hash::hmac::constant_time_eq;
+use crate::hash::tth::{self, TigerTree};
+use crate::utils::{
+    decrypt_stream, encrypt_stream, get_new_file_path, Digest, HashingWriter, TempFile,
+};
+
+use protocol::{FileHeader, Frame, RemoteError, DATA_CHUNK, PROTOCOL_VERSION};
+
+/// The received content does not match the hash sent along with it
+#[derive(Debug)]
+pub struct IntegrityError;
+
+impl Display for IntegrityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Hash mismatch, the file was corrupted, tampered with or the shared secret differs"
+        )
+    }
+}
+
+impl std::error::Error for IntegrityError {}
+
+/// Encrypts the file at `file_path` with `config` and sends it to
+/// `address`. `report` is told about progress worth showing to the user.
+pub async fn send_file(
+    config: &Config,
+    file_path: &Path,
+    address: &str,
+    report: impl Fn(&str) + Send,
+) -> anyhow::Result<()> {
+    let name = file_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or(anyhow!("Couldn't extract file name"))?;
+    let mut digest = Digest::for_config(config)?;
+
+    let file = tokio::fs::File::open(file_path)
+        .await
+        .context("Couldn't open the file")?;
+
+    // The digest is sent before the content and covers its size, so the
+    // ciphertext is written to a temporary file, hashed once its size is
+    // known, then streamed from there
+    let encrypted_file = TempFile::new().context("Couldn't create a temporary file")?;
+    async {
+        let encrypted = encrypted_file.open_write().await?;
+        let mut writer = BufWriter::new(encrypted);
+        encrypt_stream(config, BufReader::new(file), &mut writer).await?;
+        writer.flush().await?;
+
+        anyhow::Ok(())
+    }
+    .await
+    .context("Error encrypting file content")?;
+    let size = tokio::fs::metadata(encrypted_file.path()).await?.len();
+
+    // The tree root is one of the hashed fields, so the content is read
+    // twice: once for the tree, once for the digest over both
+    let tree = chunk_tree(tokio::fs::File::open(encrypted_file.path()).await?)
+        .await
+        .context("Couldn't read the encrypted file back")?;
+    let mut header = FileHeader {
+        name,
+        size,
+        hash: config.integrity_hash,
+        tree_root: tree.root().to_vec(),
+        digest: Vec::new(),
+    };
+    digest.update(&header.authenticated_fields());
+    let mut writer = HashingWriter::new(tokio::io::sink(), digest);
+    tokio::io::copy(
+        &mut tokio::fs::File::open(encrypted_file.path()).await?,
+        &mut writer,
+    )
+    .await
+    .context("Couldn't read the encrypted file back")?;
+    header.digest = writer.finalize();
+
+    let mut stream = TcpStream::connect(address)
+        .await
+        .context("Failed to establish a connection")?;
+    report("Established connection");
+
+    async {
+        Frame::Hello {
+            version: PROTOCOL_VERSION,
+        }
+        .write_to(&mut stream)
+        .await?;
+        check_hello(next_frame(&mut stream).await?)?;
+
+        Frame::FileHeader(header).write_to(&mut stream).await?;
+
+        let mut encrypted = tokio::fs::File::open(encrypted_file.path()).await?;
+        let mut chunk = vec![0u8; DATA_CHUNK];
+        for index in 0.. {
+            let read = read_chunk(&mut encrypted, &mut chunk).await?;
+            if read == 0 {
+                break;
+            }
+            Frame::Data {
+                path: tree
+                    .path(index)
+                    .ok_or(anyhow!("The encrypted file changed while sending it"))?,
+                data: chunk[..read].to_vec(),
+            }
+            .write_to(&mut stream)
+            .await?;
+        }
+
+        anyhow::Ok(())
+    }
+    .await
+    .context("An error occurred while sending data")?;
+
+    report("Waiting for the receiver to check the file");
+    match next_frame(&mut stream)
+        .await
+        .context("The receiver didn't confirm the file")?
+    {
+        Frame::Ack => {}
+        frame => return Err(frame.unexpected("Ack")),
+    }
+
+    stream
+        .shutdown()
+        .await
+        .context("An error occurred while closing the connection")?;
+
+    return Ok(());
+}
+
+/// Reads one transfer from `socket`, checks its digest and decrypts it into
+/// `dest_dir`. Returns the path of the decrypted file. The sender is told
+/// whether that worked.
+pub async fn receive_file(
+    socket: TcpStream,
+    config: &Config,
+    dest_dir: &Path,
+    report: impl Fn(&str) + Send,
+) -> anyhow::Result<PathBuf> {
+    let mut socket = BufReader::new(socket);
+
+    let result = receive(&mut socket, config, dest_dir, report).await;
+
+    // The sender may already be gone, so failing to answer isn't an error
+    let answer = match &result {
+        Ok(_) => Frame::Ack,
+        Err(err) if err.is::<RemoteError>() => return result,
+        Err(err) => Frame::Error(format!("{:#}", err)),
+    };
+    let _ = answer.write_to(&mut socket).await;
+    let _ = socket.shutdown().await;
+
+    return result;
+}
+
+async fn receive(
+    socket: &mut BufReader<TcpStream>,
+    config: &Config,
+    dest_dir: &Path,
+    report: impl Fn(&str) + Send,
+) -> anyhow::Result<PathBuf> {
+    check_hello(next_frame(socket).await?)?;
+    Frame::Hello {
+        version: PROTOCOL_VERSION,
+    }
+    .write_to(socket)
+    .await?;
+
+    let header = match next_frame(socket)
+        .await
+        .context("An error occurred while extracting data")?
+    {
+        Frame::FileHeader(header) => header,
+        frame => return Err(frame.unexpected("FileHeader")),
+    };
+
+    check_file_name(&header.name)?;
+
+    // The hash is part of the settings both sides agree on, letting the
+    // sender pick would let anyone swap an HMAC for a forgeable CRC32
+    if header.hash != config.integrity_hash {
+        return Err(anyhow!(
+            "The sender hashed the file with {}, this side expects {}",
+            header.hash,
+            config.integrity_hash
+        ));
+    }
+
+    // Stream the content to a temporary file, hashing it on the way, and
+    // only decrypt once the digest checks out
+    let encrypted_file = TempFile::new().context("Couldn't create a temporary file")?;
+    let recalculated_digest = async {
+        let file = encrypted_file.open_write().await?;
+        let mut digest = Digest::for_config(config)?;
+        digest.update(&header.authenticated_fields());
+        let mut writer = HashingWriter::new(BufWriter::new(file), digest);
+
+        let chunks = usize::try_from(header.size.div_ceil(DATA_CHUNK as u64))
+            .map_err(|_| anyhow!("The file is too large"))?;
+        let mut received = 0u64;
+        let mut index = 0;
+        while received < header.size {
+            let (path, data) = match next_frame(socket).await? {
+                Frame::Data { path, data } => (path, data),
+                frame => return Err(frame.unexpected("Data")),
+            };
+
+            received += data.len() as u64;
+            if received > header.size {
+                return Err(anyhow!("The sender sent more than the file header said"));
+            }
+            // Chunks line up with subtrees only when all but the last are full
+            if (data.len() != DATA_CHUNK && received != header.size)
+                || !tth::verify_chunk(&header.tree_root, &data, index, chunks, &path)
+            {
+                return Err(anyhow::Error::from(IntegrityError)
+                    .context(format!("Chunk {} doesn't match the tree root", index)));
+            }
+            writer.write_all(&data).await?;
+            index += 1;
+        }
+        writer.flush().await?;
+
+        anyhow::Ok(writer.finalize())
+    }
+    .await
+    .context("An error occurred while extracting data")?;
+
+    if !constant_time_eq(&header.digest, &recalculated_digest) {
+        return Err(IntegrityError.into());
+    }
+
+    report("Decrypting...");
+
+    let new_file_path = get_new_file_path(Path::new(&header.name), dest_dir, Operation::Decrypt)
+        .await
+        .context("Couldn't find available name for the file")?;
+
+    let new_file = tokio::fs::File::create(&new_file_path)
+        .await
+        .context("Couldn't create file to store result into")?;
+
+    let encrypted_content = tokio::fs::File::open(encrypted_file.path())
+        .await
+        .context("Couldn't read the received data back")?;
+
+    if let Err(err) = decrypt_stream(
+        config,
+        BufReader::new(encrypted_content),
+        BufWriter::new(new_file),
+    )
+    .await
+    {
+        let _ = tokio::fs::remove_file(&new_file_path).await;
+        return Err(err.context("Error while decrypting the file"));
+    }
+
+    return Ok(new_file_path);
+}
+
+/// The next frame, an Error frame from the other side becomes a
+/// [`RemoteError`]
+async fn next_frame<R: AsyncRead + Unpin>(reader: &mut R) -> anyhow::Result<Frame> {
+    match Frame::read_from(reader).await? {
+        Frame::Error(message) => Err(RemoteError(message).into()),
+        frame => Ok(frame),
+    }
+}
+
+/// Tiger tree over the roots of each [`DATA_CHUNK`] of `content`, which has
+/// the same root as the tree over its leaves
+async fn chunk_tree<R: AsyncRead + Unpin>(mut content: R) -> anyhow::Result<TigerTree> {
+    let mut chunk = vec![0u8; DATA_CHUNK];
+    let mut roots = Vec::new();
+    loop {
+        let read = read_chunk(&mut content, &mut chunk).await?;
+        if read == 0 {
+            break;
+        }
+        roots.push(TigerTree::new(&chunk[..read]).root().to_vec());
+    }
+
+    if roots.is_empty() {
+        return Ok(TigerTree::new(&[]));
+    }
+
+    return Ok(TigerTree::from_leaves(roots));
+}
+
+/// Fills `buf` unless the reader ends first, so chunks stay aligned with the
+/// tree however the reads come back. Returns how much was read.
+async fn read_chunk<R: AsyncRead + Unpin>(reader: &mut R, buf: &mut [u8]) -> anyhow::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let read = reader.read(&mut buf[filled..]).await?;
+        if read == 0 {
+            break;
+        }
+        filled += read;
+    }
+
+    Ok(filled)
+}
+
+/// The name comes from the network, it must not lead out of the destination
+/// directory
+fn check_file_name(name: &str) -> anyhow::Result<()> {
+    let path = Path::new(name);
+
+    if name.contains(['/', '\\']) || path.file_name() != Some(path.as_os_str()) {
+        return Err(anyhow!(
+            "The sender named the file {:?}, which is not a plain file name",
+            name
+        ));
+    }
+
+    Ok(())
+}
+
+fn check_hello(frame: Frame) -> anyhow::Result<()> {
+    match frame {
+        Frame::Hello {
+            version: PROTOCOL_VERSION,
+        } => Ok(()),
+        Frame::Hello { version } => Err(anyhow!(
+            "Transfer protocol version {} is not supported, expected {}",
+            version,
+            PROTOCOL_VERSION
+        )),
+        frame => Err(frame.unexpected("Hello")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::protocol::{
+        FileHeader, Frame, RemoteError, DATA_CHUNK, PROTOCOL_VERSION, TREE_HASH_LEN,
+    };
+    use super::{chunk_tree, receive_file, send_file, IntegrityError};
+    use crate::algorithms::AlgorithmOption;
+    use crate::config::Config;
+    use crate::hash::HashAlgorithm;
+    use crate::utils::{encrypt_stream, Digest, TempDir, TempFile};
+
+    async fn transfer(
+        sender: &Config,
+        receiver: &Config,
+        name: &str,
+        content: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
+        let source = TempDir::new().unwrap();
+        let file = source.path().join(name);
+        std::fs::write(&file, content).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let receiving = {
+            let receiver = receiver.clone();
+            let dest = source.path().to_path_buf();
+            tokio::spawn(async move {
+                let (socket, _) = listener.accept().await.unwrap();
+                receive_file(socket, &receiver, &dest, |_| ()).await
+            })
+        };
+
+        let sent = send_file(sender, &file, &address, |_| ()).await;
+        let received = receiving.await.unwrap();
+
+        // The receiver tells the sender why it failed
+        match &received {
+            Ok(_) => sent.unwrap(),
+            Err(_) => assert!(sent.unwrap_err().is::<RemoteError>()),
+        }
+
+        received.map(|path| std::fs::read(path).unwrap())
+    }
+
+    fn config(algorithm: AlgorithmOption) -> Config {
+        Config {
+            algorithm,
+            kdf_iterations: 10,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn files_arrive_decrypted() {
+        let content = b"Sent over the loopback interface".repeat(1000);
+
+        for algorithm in [AlgorithmOption::Xxtea, AlgorithmOption::Aes] {
+            let config = config(algorithm);
+            let received = transfer(&config, &config, "report.txt", &content)
+                .await
+                .unwrap();
+
+            assert_eq!(received, content);
+        }
+    }
+
+    #[tokio::test]
+    async fn names_with_any_number_of_dots_arrive() {
+        let config = config(AlgorithmOption::Aes);
+
+        for name in ["archive.tar.gz", "README", ".profile"] {
+            let received = transfer(&config, &config, name, b"data").await.unwrap();
+            assert_eq!(received, b"data");
+        }
+    }
+
+    #[tokio::test]
+    async fn hashes_other_than_the_receivers_are_rejected() {
+        let receiver = Config {
+            integrity_hash: HashAlgorithm::Sha256,
+            shared_secret: Some(String::from("ours").into()),
+            ..config(AlgorithmOption::Aes)
+        };
+        let downgraded = Config {
+            integrity_hash: HashAlgorithm::Crc32,
+            shared_secret: None,
+            ..receiver.clone()
+        };
+
+        let err = transfer(&downgraded, &receiver, "a.bin", b"data")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("CRC32"), "{}", err);
+
+        let received = transfer(&receiver, &receiver, "a.bin", b"data")
+            .await
+            .unwrap();
+        assert_eq!(received, b"data");
+    }
+
+    #[tokio::test]
+    async fn different_shared_secrets_are_rejected() {
+        let sender = Config {
+            shared_secret: Some(String::from("ours").into()),
+            ..config(AlgorithmOption::Aes)
+        };
+        let receiver = Config {
+            shared_secret: Some(String::from("theirs").into()),
+            ..config(AlgorithmOption::Aes)
+        };
+
+        let err = transfer(&sender, &receiver, "a.bin", b"data")
+            .await
+            .unwrap_err();
+
+        assert!(err.is::<IntegrityError>());
+    }
+
+    #[tokio::test]
+    async fn receivers_refuse_other_versions() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let receiving = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let dest = std::env::temp_dir();
+            receive_file(socket, &config(AlgorithmOption::Aes), &dest, |_| ()).await
+        });
+
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        Frame::Hello { version: 200 }
+            .write_to(&mut stream)
+            .await
+            .unwrap();
+
+        let answer = Frame::read_from(&mut stream).await.unwrap();
+        assert!(matches!(answer, Frame::Error(message) if message.contains("200")));
+        assert!(receiving.await.unwrap().is_err());
+    }
+
+    #[tokio::test]
+    async fn names_leading_out_of_the_destination_are_refused() {
+        for name in ["../x.txt", "/tmp/x.txt", "dir\\x.txt", "..", ""] {
+            let dest = TempDir::new().unwrap();
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap();
+
+            let receiving = {
+                let dest = dest.path().to_path_buf();
+                tokio::spawn(async move {
+                    let (socket, _) = listener.accept().await.unwrap();
+                    receive_file(socket, &config(AlgorithmOption::Aes), &dest, |_| ()).await
+                })
+            };
+
+            let mut stream = TcpStream::connect(address).await.unwrap();
+            Frame::Hello {
+                version: PROTOCOL_VERSION,
+            }
+            .write_to(&mut stream)
+            .await
+            .unwrap();
+            Frame::read_from(&mut stream).await.unwrap();
+            Frame::FileHeader(FileHeader {
+                name: name.to_owned(),
+                size: 0,
+                hash: HashAlgorithm::default(),
+                tree_root: vec![0; TREE_HASH_LEN],
+                digest: Vec::new(),
+            })
+            .write_to(&mut stream)
+            .await
+            .unwrap();
+
+            let answer = Frame::read_from(&mut stream).await.unwrap();
+            assert!(
+                matches!(&answer, Frame::Error(message) if message.contains("plain file name")),
+                "{:?}",
+                answer
+            );
+            assert!(receiving.await.unwrap().is_err());
+            assert_eq!(std::fs::read_dir(dest.path()).unwrap().count(), 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn renamed_or_truncated_headers_are_rejected() {
+        let config = Config {
+            shared_secret: Some(String::from("ours").into()),
+            ..config(AlgorithmOption::Aes)
+        };
+        let mut ciphertext = Vec::new();
+        encrypt_stream(&config, &b"data"[..], &mut ciphertext)
+            .await
+            .unwrap();
+
+        let tree = chunk_tree(&ciphertext[..]).await.unwrap();
+
+        // What the sender signed, then changed on the way
+        let signed = FileHeader {
+            name: String::from("a.txt"),
+            size: ciphertext.len() as u64,
+            hash: config.integrity_hash,
+            tree_root: tree.root().to_vec(),
+            digest: Vec::new(),
+        };
+        let mut digest = Digest::for_config(&config).unwrap();
+        digest.update(&signed.authenticated_fields());
+        digest.update(&ciphertext);
+        let digest = digest.finalize();
+
+        let renamed = FileHeader {
+            name: String::from("b.txt"),
+            digest: digest.clone(),
+            ..signed.clone()
+        };
+        let truncated = FileHeader {
+            size: signed.size - 16,
+            digest,
+            ..signed
+        };
+
+        for (header, content) in [
+            (renamed, &ciphertext[..]),
+            (truncated, &ciphertext[..ciphertext.len() - 16]),
+        ] {
+            let dest = TempDir::new().unwrap();
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap();
+
+            let receiving = {
+                let config = config.clone();
+                let dest = dest.path().to_path_buf();
+                tokio::spawn(async move {
+                    let (socket, _) = listener.accept().await.unwrap();
+                    receive_file(socket, &config, &dest, |_| ()).await
+                })
+            };
+
+            let mut stream = TcpStream::connect(address).await.unwrap();
+            for frame in [
+                Frame::Hello {
+                    version: PROTOCOL_VERSION,
+                },
+                Frame::FileHeader(header),
+                Frame::Data {
+                    path: tree.path(0).unwrap(),
+                    data: content.to_vec(),
+                },
+            ] {
+                frame.write_to(&mut stream).await.unwrap();
+            }
+
+            assert!(receiving.await.unwrap().unwrap_err().is::<IntegrityError>());
+        }
+    }
+
+    /// Sends a valid header, then the Data frames `frames` makes out of the
+    /// real chunks as (index of the path, data). Returns the receiver's answer
+    /// once it refused the content.
+    async fn send_chunks(frames: impl FnOnce(Vec<Vec<u8>>) -> Vec<(usize, Vec<u8>)>) -> Frame {
+        let config = config(AlgorithmOption::Aes);
+        let mut ciphertext = Vec::new();
+        encrypt_stream(&config, &vec![7u8; DATA_CHUNK * 3][..], &mut ciphertext)
+            .await
+            .unwrap();
+        let tree = chunk_tree(&ciphertext[..]).await.unwrap();
+
+        let mut header = FileHeader {
+            name: String::from("a.bin"),
+            size: ciphertext.len() as u64,
+            hash: config.integrity_hash,
+            tree_root: tree.root().to_vec(),
+            digest: Vec::new(),
+        };
+        let mut digest = Digest::for_config(&config).unwrap();
+        digest.update(&header.authenticated_fields());
+        digest.update(&ciphertext);
+        header.digest = digest.finalize();
+
+        let dest = TempDir::new().unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let receiving = {
+            let config = config.clone();
+            let dest = dest.path().to_path_buf();
+            tokio::spawn(async move {
+                let (socket, _) = listener.accept().await.unwrap();
+                receive_file(socket, &config, &dest, |_| ()).await
+            })
+        };
+
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        Frame::Hello {
+            version: PROTOCOL_VERSION,
+        }
+        .write_to(&mut stream)
+        .await
+        .unwrap();
+        Frame::read_from(&mut stream).await.unwrap();
+        Frame::FileHeader(header)
+            .write_to(&mut stream)
+            .await
+            .unwrap();
+
+        let chunks = ciphertext.chunks(DATA_CHUNK).map(<[u8]>::to_vec).collect();
+        for (index, data) in frames(chunks) {
+            Frame::Data {
+                path: tree.path(index).unwrap(),
+                data,
+            }
+            .write_to(&mut stream)
+            .await
+            .unwrap();
+        }
+
+        // Answered without waiting for the rest of the file
+        let answer = Frame::read_from(&mut stream).await.unwrap();
+        assert!(receiving.await.unwrap().unwrap_err().is::<IntegrityError>());
+
+        answer
+    }
+
+    #[tokio::test]
+    async fn corrupted_chunks_stop_the_transfer() {
+        // The first chunk is fine, the second flipped a bit on the way
+        let answer = send_chunks(|mut chunks| {
+            chunks[1][100] ^= 1;
+            vec![(0, chunks[0].clone()), (1, chunks[1].clone())]
+        })
+        .await;
+
+        assert!(
+            matches!(&answer, Frame::Error(message) if message.contains("Chunk 1")),
+            "{:?}",
+            answer
+        );
+    }
+
+    #[tokio::test]
+    async fn chunks_only_pass_at_their_own_place() {
+        // Each chunk comes with its own, valid path
+        let reordered =
+            send_chunks(|chunks| vec![(1, chunks[1].clone()), (0, chunks[0].clone())]).await;
+        let replayed =
+            send_chunks(|chunks| vec![(0, chunks[0].clone()), (0, chunks[0].clone())]).await;
+
+        for (answer, bad) in [(reordered, "Chunk 0"), (replayed, "Chunk 1")] {
+            assert!(
+                matches!(&answer, Frame::Error(message) if message.contains(bad)),
+                "{:?}",
+                answer
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn secrets_need_a_cryptographic_hash() {
+        let config = Config {
+            integrity_hash: HashAlgorithm::Crc32,
+            shared_secret: Some(String::from("ours").into()),
+            ..config(AlgorithmOption::Aes)
+        };
+
+        let file = TempFile::new().unwrap();
+        std::fs::write(file.path(), b"data").unwrap();
+        let err = send_file(&config, file.path(), "127.0.0.1:9", |_| ())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("CRC32"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn missing_files_are_not_sent() {
+        let config = config(AlgorithmOption::Aes);
+
+        assert!(
+            send_file(&config, Path::new("/no/such/file"), "127.0.0.1:9", |_| ())
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,377 @@
+//! Frames of the TCP transfer protocol.
+//!
+//! Every frame is `kind u8 | payload len u32 LE | payload`. A transfer goes:
+//! the sender and then the receiver send [`Frame::Hello`], the sender sends a
+//! [`Frame::FileHeader`] and [`Frame::Data`] frames until `size` bytes were
+//! sent, and the receiver answers [`Frame::Ack`] once the file is checked and
+//! decrypted. Either side sends [`Frame::Error`] instead of a frame it can't
+//! send, then hangs up.
+//!
+//! The header carries the Tiger tree root of the content and every Data frame
+//! the sibling path of its [`DATA_CHUNK`] up to that root, so the receiver
+//! checks each piece as it arrives rather than after the whole file.
+
+use std::fmt::Display;
+
+use anyhow::anyhow;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::hash::tth::Sibling;
+use crate::hash::HashAlgorithm;
+
+pub const MAGIC: &[u8; 4] = b"ZITP";
+pub const PROTOCOL_VERSION: u8 = 2;
+
+/// Content is sent in Data frames of this many bytes, only the last one may
+/// be shorter. A power of two of tree leaves, so every chunk is a subtree.
+pub const DATA_CHUNK: usize = 64 * 1024;
+/// Length of a Tiger tree node
+pub const TREE_HASH_LEN: usize = 24;
+/// Longest payload read, so a bad length can't make the reader allocate much
+const MAX_PAYLOAD: u32 = 1 << 20;
+
+const HELLO: u8 = 1;
+const FILE_HEADER: u8 = 2;
+const DATA: u8 = 3;
+const ACK: u8 = 4;
+const ERROR: u8 = 5;
+
+/// The other side sent an Error frame
+#[derive(Debug)]
+pub struct RemoteError(pub String);
+
+impl Display for RemoteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "The other side reported: {}", self.0)
+    }
+}
+
+impl std::error::Error for RemoteError {}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct FileHeader {
+    pub name: String,
+    /// Bytes of content that follow in Data frames
+    pub size: u64,
+    pub hash: HashAlgorithm,
+    /// Tiger tree root of the content, [`TREE_HASH_LEN`] bytes
+    pub tree_root: Vec<u8>,
+    /// Hash of the other fields and the content, an HMAC when a shared
+    /// secret is set
+    pub digest: Vec<u8>,
+}
+
+impl FileHeader {
+    /// Name, size, hash id and tree root as encoded in the frame. The digest
+    /// covers them ahead of the content, so they can't be changed on the way.
+    pub fn authenticated_fields(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        leb128::write::unsigned(&mut out, self.name.len() as u64)
+            .expect("Writing to a Vec can't fail");
+        out.extend_from_slice(self.name.as_bytes());
+        out.extend_from_slice(&self.size.to_le_bytes());
+        out.push(self.hash.id());
+        out.extend_from_slice(&self.tree_root);
+
+        out
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Frame {
+    /// [`MAGIC`] and the version the side speaks, the first frame of both
+    Hello {
+        version: u8,
+    },
+    FileHeader(FileHeader),
+    /// A piece of the content, at most [`DATA_CHUNK`] bytes, and the siblings
+    /// leading from it to the tree root
+    Data {
+        path: Vec<Sibling>,
+        data: Vec<u8>,
+    },
+    /// The file arrived, matched its digest and was decrypted
+    Ack,
+    Error(String),
+}
+
+impl Frame {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Frame::Hello { .. } => "Hello",
+            Frame::FileHeader(_) => "FileHeader",
+            Frame::Data { .. } => "Data",
+            Frame::Ack => "Ack",
+            Frame::Error(_) => "Error",
+        }
+    }
+
+    /// The error for receiving this frame where `expected` was due
+    pub fn unexpected(&self, expected: &str) -> anyhow::Error {
+        anyhow!("Expected a {} frame, got {}", expected, self.name())
+    }
+
+    fn kind(&self) -> u8 {
+        match self {
+            Frame::Hello { .. } => HELLO,
+            Frame::FileHeader(_) => FILE_HEADER,
+            Frame::Data { .. } => DATA,
+            Frame::Ack => ACK,
+            Frame::Error(_) => ERROR,
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let payload = self.encode_payload();
+
+        let mut out = Vec::with_capacity(5 + payload.len());
+        out.push(self.kind());
+        out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        out.extend_from_slice(&payload);
+
+        out
+    }
+
+    fn encode_payload(&self) -> Vec<u8> {
+        match self {
+            Frame::Hello { version } => {
+                let mut out = MAGIC.to_vec();
+                out.push(*version);
+                out
+            }
+            Frame::FileHeader(header) => {
+                let mut out = header.authenticated_fields();
+                out.push(header.digest.len() as u8);
+                out.extend_from_slice(&header.digest);
+                out
+            }
+            Frame::Data { path, data } => {
+                let mut out = vec![path.len() as u8];
+                for sibling in path {
+                    let (side, hash) = match sibling {
+                        Sibling::Left(hash) => (0, hash),
+                        Sibling::Right(hash) => (1, hash),
+                    };
+                    out.push(side);
+                    out.extend_from_slice(hash);
+                }
+                out.extend_from_slice(data);
+                out
+            }
+            Frame::Ack => Vec::new(),
+            Frame::Error(message) => message.as_bytes().to_vec(),
+        }
+    }
+
+    pub fn decode(kind: u8, payload: &[u8]) -> anyhow::Result<Frame> {
+        match kind {
+            HELLO => match payload {
+                [magic @ .., version] if magic == MAGIC => Ok(Frame::Hello { version: *version }),
+                _ => Err(anyhow!(
+                    "The other side doesn't speak the ZI transfer protocol"
+                )),
+            },
+            FILE_HEADER => Frame::decode_file_header(payload).map(Frame::FileHeader),
+            DATA => Frame::decode_data(payload),
+            ACK if payload.is_empty() => Ok(Frame::Ack),
+            ACK => Err(anyhow!("Invalid Ack frame")),
+            ERROR => Ok(Frame::Error(String::from_utf8_lossy(payload).into_owned())),
+            _ => Err(anyhow!("Unknown frame kind {}", kind)),
+        }
+    }
+
+    fn decode_file_header(payload: &[u8]) -> anyhow::Result<FileHeader> {
+        let invalid = || anyhow!("Invalid FileHeader frame");
+
+        let mut rest = payload;
+        let name_len = leb128::read::unsigned(&mut rest).map_err(|_| invalid())?;
+        let name_len = usize::try_from(name_len).map_err(|_| invalid())?;
+        if rest.len() < name_len {
+            return Err(invalid());
+        }
+        let (name, rest) = rest.split_at(name_len);
+        let name =
+            String::from_utf8(name.to_vec()).map_err(|_| anyhow!("The file name is not UTF-8"))?;
+
+        if rest.len() < 9 + TREE_HASH_LEN {
+            return Err(invalid());
+        }
+        let (size, rest) = rest.split_at(8);
+        let (id, rest) = (rest[0], &rest[1..]);
+        let (tree_root, rest) = rest.split_at(TREE_HASH_LEN);
+
+        match rest {
+            [digest_len, digest @ ..] if digest.len() == *digest_len as usize => Ok(FileHeader {
+                name,
+                size: u64::from_le_bytes(size.try_into().unwrap()),
+                hash: HashAlgorithm::from_id(id).ok_or(anyhow!("Unknown hash algorithm {}", id))?,
+                tree_root: tree_root.to_vec(),
+                digest: digest.to_vec(),
+            }),
+            _ => Err(invalid()),
+        }
+    }
+
+    fn decode_data(payload: &[u8]) -> anyhow::Result<Frame> {
+        let invalid = || anyhow!("Invalid Data frame");
+
+        let (siblings, mut rest) = payload.split_first().ok_or_else(invalid)?;
+        let mut path = Vec::with_capacity(*siblings as usize);
+        for _ in 0..*siblings {
+            if rest.len() < 1 + TREE_HASH_LEN {
+                return Err(invalid());
+            }
+            let (hash, next) = rest[1..].split_at(TREE_HASH_LEN);
+            path.push(match rest[0] {
+                0 => Sibling::Left(hash.to_vec()),
+                1 => Sibling::Right(hash.to_vec()),
+                _ => return Err(invalid()),
+            });
+            rest = next;
+        }
+
+        Ok(Frame::Data {
+            path,
+            data: rest.to_vec(),
+        })
+    }
+
+    pub async fn write_to<W: AsyncWrite + Unpin>(&self, writer: &mut W) -> anyhow::Result<()> {
+        writer.write_all(&self.encode()).await?;
+        writer.flush().await?;
+
+        Ok(())
+    }
+
+    pub async fn read_from<R: AsyncRead + Unpin>(reader: &mut R) -> anyhow::Result<Frame> {
+        let kind = reader.read_u8().await?;
+
+        let len = reader.read_u32_le().await?;
+        if len > MAX_PAYLOAD {
+            return Err(anyhow!("Frame of {} bytes is too long", len));
+        }
+
+        let mut payload = vec![0u8; len as usize];
+        reader.read_exact(&mut payload).await?;
+
+        Frame::decode(kind, &payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FileHeader, Frame, MAGIC, MAX_PAYLOAD, TREE_HASH_LEN};
+    use crate::hash::tth::Sibling;
+    use crate::hash::HashAlgorithm;
+
+    fn frames() -> Vec<Frame> {
+        vec![
+            Frame::Hello { version: 1 },
+            Frame::FileHeader(FileHeader {
+                name: String::from("отчёт.txt"),
+                size: 1 << 40,
+                hash: HashAlgorithm::Sha256,
+                tree_root: vec![3; TREE_HASH_LEN],
+                digest: vec![7; 32],
+            }),
+            Frame::Data {
+                path: vec![
+                    Sibling::Right(vec![1; TREE_HASH_LEN]),
+                    Sibling::Left(vec![2; TREE_HASH_LEN]),
+                ],
+                data: b"ciphertext".to_vec(),
+            },
+            Frame::Data {
+                path: Vec::new(),
+                data: Vec::new(),
+            },
+            Frame::Ack,
+            Frame::Error(String::from("Hash mismatch")),
+        ]
+    }
+
+    #[tokio::test]
+    async fn frames_round_trip_back_to_back() {
+        let mut stream = Vec::new();
+        for frame in frames() {
+            frame.write_to(&mut stream).await.unwrap();
+        }
+
+        let mut reader = &stream[..];
+        for frame in frames() {
+            assert_eq!(Frame::read_from(&mut reader).await.unwrap(), frame);
+        }
+        assert!(reader.is_empty());
+        assert!(Frame::read_from(&mut reader).await.is_err());
+    }
+
+    #[test]
+    fn frames_follow_the_spec() {
+        let mut hello = vec![1, 5, 0, 0, 0];
+        hello.extend_from_slice(MAGIC);
+        hello.push(1);
+        assert_eq!(Frame::Hello { version: 1 }.encode(), hello);
+
+        assert_eq!(Frame::Ack.encode(), [4, 0, 0, 0, 0]);
+        let data = Frame::Data {
+            path: vec![Sibling::Left(vec![5; TREE_HASH_LEN])],
+            data: vec![9, 9],
+        };
+        let mut expected = vec![3, 28, 0, 0, 0, 1, 0];
+        expected.extend_from_slice(&[5; TREE_HASH_LEN]);
+        expected.extend_from_slice(&[9, 9]);
+        assert_eq!(data.encode(), expected);
+
+        let header = Frame::FileHeader(FileHeader {
+            name: String::from("a"),
+            size: 258,
+            hash: HashAlgorithm::Crc32,
+            tree_root: vec![0xCD; TREE_HASH_LEN],
+            digest: vec![0xAB; 4],
+        });
+        let mut expected = vec![2, 40, 0, 0, 0, 1, b'a', 2, 1, 0, 0, 0, 0, 0, 0];
+        expected.push(HashAlgorithm::Crc32.id());
+        expected.extend_from_slice(&[0xCD; TREE_HASH_LEN]);
+        expected.extend_from_slice(&[4, 0xAB, 0xAB, 0xAB, 0xAB]);
+        assert_eq!(header.encode(), expected);
+    }
+
+    #[tokio::test]
+    async fn malformed_frames_are_rejected() {
+        let read = |bytes: Vec<u8>| async move { Frame::read_from(&mut &bytes[..]).await };
+
+        // Another protocol, unknown kinds and truncated payloads
+        assert!(read(b"\x01\x05\x00\x00\x00HTTP1".to_vec()).await.is_err());
+        assert!(read(vec![9, 0, 0, 0, 0]).await.is_err());
+        assert!(read(vec![3, 4, 0, 0, 0, 1, 2]).await.is_err());
+        assert!(read(vec![4, 1, 0, 0, 0, 1]).await.is_err());
+
+        let too_long = (MAX_PAYLOAD + 1).to_le_bytes();
+        assert!(read([&[3], &too_long[..]].concat()).await.is_err());
+
+        // A sibling cut short, an unknown side and no sibling count at all
+        let mut sibling = vec![3, 24, 0, 0, 0, 1, 0];
+        sibling.extend_from_slice(&[5; TREE_HASH_LEN - 1]);
+        assert!(read(sibling).await.is_err());
+        let mut side = vec![3, 26, 0, 0, 0, 1, 2];
+        side.extend_from_slice(&[5; TREE_HASH_LEN]);
+        assert!(read(side).await.is_err());
+        assert!(read(vec![3, 0, 0, 0, 0]).await.is_err());
+
+        // Digest length past the end of the payload
+        let mut header = Frame::FileHeader(FileHeader {
+            name: String::from("a"),
+            size: 1,
+            hash: HashAlgorithm::Sha256,
+            tree_root: vec![1; TREE_HASH_LEN],
+            digest: vec![1; 32],
+        })
+        .encode();
+        header.pop();
+        header[1] -= 1;
+        assert!(read(header).await.is_err());
+
+        let bad_name = vec![2, 12, 0, 0, 0, 1, 0xFF, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0];
+        assert!(read(bad_name).await.is_err());
+    }
+}
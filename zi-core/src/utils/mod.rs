@@ -0,0 +1,444 @@
+use anyhow::anyhow;
+use std::path::Path;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::io::{
+    AsyncBufRead, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader, BufWriter,
+};
+
+use crate::algorithms::aes::alg::Aes;
+use crate::algorithms::chacha20poly1305::alg::{self as chacha20poly1305, ChaCha20Poly1305};
+use crate::algorithms::enigma::alg::{Enigma, EnigmaMachine, Mechanics};
+use crate::algorithms::enigma::binary::BinaryEnigma;
+use crate::algorithms::kdf::{DerivedKey, KeyDerivation};
+use crate::algorithms::modes::alg::BlockModeCipher;
+use crate::algorithms::xxtea::alg::{Xxtea, XxteaBlock, XxteaMode};
+use crate::algorithms::{AlgorithmOption, Operation, StreamProcessor, StreamingAlgorithm};
+use crate::config::Config;
+use crate::container::{ContainerFormat, Header};
+use crate::hash::hmac::Hmac;
+use crate::hash::{HashAlgorithm, Hasher};
+
+/// Size of the buffer used to pump data through a [`StreamProcessor`].
+pub const STREAM_BUFFER_SIZE: usize = 64 * 1024;
+
+const XXTEA_KEY_LEN: usize = 16;
+/// Same length as the Tiger digest the passphrase seed is otherwise made of
+const BINARY_ENIGMA_SEED_LEN: usize = 24;
+
+/// Encrypts or decrypts `file` into a new file in `dest_dir` and returns its
+/// path
+pub async fn process_file(
+    file: &PathBuf,
+    config: &Config,
+    op: Operation,
+    dest_dir: &Path,
+) -> anyhow::Result<PathBuf> {
+    let file_handle = tokio::fs::OpenOptions::new().read(true).open(&file).await?;
+
+    let new_file_path = get_new_file_path(file, dest_dir, op).await?;
+    let new_file = tokio::fs::File::create(&new_file_path).await?;
+
+    let reader = BufReader::new(file_handle);
+    let writer = BufWriter::new(new_file);
+
+    let result = match op {
+        Operation::Encrypt => encrypt_stream(config, reader, writer).await,
+        Operation::Decrypt => decrypt_stream(config, reader, writer).await,
+    };
+
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&new_file_path).await;
+    }
+
+    result.map(|_| new_file_path)
+}
+
+/// Encrypts `reader` into `writer`, prefixed with a container [`Header`]
+/// unless raw output is selected in `config`.
+pub async fn encrypt_stream<R, W>(config: &Config, reader: R, mut writer: W) -> anyhow::Result<u64>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut written = 0;
+    let alg = match config.container_format {
+        ContainerFormat::Container => {
+            let (header, key) = Header::from_config(config)?;
+            let alg = get_keyed_algorithm(config, key.as_deref().map(Vec::as_slice))?;
+
+            let header = header.encode();
+            writer.write_all(&header).await?;
+            written += header.len() as u64;
+
+            alg
+        }
+        ContainerFormat::Raw => get_algorithm(config)?,
+    };
+
+    let mut encryptor = alg.encryptor();
+    written += process_stream(reader, writer, encryptor.as_mut()).await?;
+
+    Ok(written)
+}
+
+/// Decrypts `reader` into `writer`. A container header, if present, decides
+/// the algorithm and its parameters; raw input is only accepted when raw
+/// mode is selected in `config`.
+pub async fn decrypt_stream<R, W>(config: &Config, mut reader: R, writer: W) -> anyhow::Result<u64>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let alg = match config.container_format {
+        ContainerFormat::Container => match Header::read_from(&mut reader).await? {
+            Some(header) => {
+                let (config, key) = header.apply(config)?;
+                get_keyed_algorithm(&config, key.as_deref().map(Vec::as_slice))?
+            }
+            None => {
+                return Err(anyhow!(
+                    "The data has no ZI container header. Select the raw format in settings to decrypt files made by older versions"
+                ))
+            }
+        },
+        ContainerFormat::Raw => get_algorithm(config)?,
+    };
+
+    let mut decryptor = alg.decryptor();
+    process_stream(reader, writer, decryptor.as_mut()).await
+}
+
+/// Reads `reader` until EOF, feeds everything through `processor` and writes
+/// the output to `writer`. Only one buffer's worth of data is held in memory.
+///
+/// Returns the number of bytes written.
+pub async fn process_stream<R, W>(
+    mut reader: R,
+    mut writer: W,
+    processor: &mut (dyn StreamProcessor + Send),
+) -> anyhow::Result<u64>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut buffer = vec![0u8; STREAM_BUFFER_SIZE];
+    let mut written: u64 = 0;
+
+    loop {
+        let n = reader.read(&mut buffer).await?;
+        if n == 0 {
+            break;
+        }
+
+        let output = processor.update(&buffer[..n])?;
+        writer.write_all(&output).await?;
+        written += output.len() as u64;
+    }
+
+    let output = processor.finalize()?;
+    writer.write_all(&output).await?;
+    written += output.len() as u64;
+
+    writer.flush().await?;
+
+    Ok(written)
+}
+
+/// Digest sent along with a transfer: the bare integrity hash, or an HMAC
+/// when a shared secret is set so the digest cannot be swapped on the way
+pub enum Digest {
+    Plain(Box<dyn Hasher>),
+    Keyed(Hmac),
+}
+
+impl Digest {
+    /// Fails when a secret is paired with a hash that can't key an HMAC
+    pub fn new(algorithm: HashAlgorithm, secret: Option<&str>) -> anyhow::Result<Digest> {
+        match secret {
+            Some(secret) => Ok(Digest::Keyed(Hmac::new(algorithm, secret.as_bytes())?)),
+            None => Ok(Digest::Plain(algorithm.hasher())),
+        }
+    }
+
+    pub fn for_config(config: &Config) -> anyhow::Result<Digest> {
+        Digest::new(
+            config.integrity_hash,
+            config.shared_secret.as_ref().map(|secret| secret.as_str()),
+        )
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Digest::Plain(hasher) => hasher.update(data),
+            Digest::Keyed(mac) => mac.update(data),
+        }
+    }
+
+    pub fn finalize(self) -> Vec<u8> {
+        match self {
+            Digest::Plain(hasher) => hasher.finalize(),
+            Digest::Keyed(mac) => mac.finalize(),
+        }
+    }
+}
+
+/// Passes writes through to `inner` and hashes every byte it accepted, so
+/// data can be hashed on its way to disk or the network
+pub struct HashingWriter<W> {
+    inner: W,
+    hasher: Digest,
+}
+
+impl<W> HashingWriter<W> {
+    pub fn new(inner: W, hasher: Digest) -> HashingWriter<W> {
+        HashingWriter { inner, hasher }
+    }
+
+    /// Digest of everything written so far
+    pub fn finalize(self) -> Vec<u8> {
+        self.hasher.finalize()
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for HashingWriter<W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_write(cx, buf);
+
+        if let Poll::Ready(Ok(n)) = poll {
+            this.hasher.update(&buf[..n]);
+        }
+
+        poll
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+/// A file in the temp directory that is deleted when this is dropped.
+/// Received data goes through it, so it gets a random name, is created here
+/// rather than by whoever opens it next, and only the user can read it.
+pub struct TempFile {
+    path: PathBuf,
+}
+
+impl TempFile {
+    pub fn new() -> std::io::Result<TempFile> {
+        let path = std::env::temp_dir().join(format!("zi-{}.tmp", random_name()));
+
+        let mut options = std::fs::OpenOptions::new();
+        // Fails on anything already there, a planted symlink included
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options.open(&path)?;
+
+        Ok(TempFile { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Opens the file [`TempFile::new`] created for writing, never creating
+    /// one in its place
+    pub async fn open_write(&self) -> std::io::Result<tokio::fs::File> {
+        tokio::fs::OpenOptions::new()
+            .write(true)
+            .open(&self.path)
+            .await
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// A directory in the temp directory, removed with everything in it when
+/// this is dropped
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    pub fn new() -> std::io::Result<TempDir> {
+        let path = std::env::temp_dir().join(format!("zi-{}", random_name()));
+
+        let mut builder = std::fs::DirBuilder::new();
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        builder.create(&path)?;
+
+        Ok(TempDir { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Hard to guess, so nobody can set up the path ahead of us
+fn random_name() -> String {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).expect("System random number generator is unavailable");
+
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub async fn get_new_file_path(
+    file: &Path,
+    dest_dir: &Path,
+    op: Operation,
+) -> anyhow::Result<PathBuf> {
+    let file_stem = file.file_stem().unwrap_or_default().to_str().unwrap();
+    let extension = file
+        .extension()
+        .unwrap_or_default()
+        .to_str()
+        .unwrap_or_default();
+
+    for i in 0..100 {
+        let num = format!(" ({})", i);
+        let num = if i == 0 { "" } else { &num };
+
+        let name = match op {
+            Operation::Encrypt => format!("{}_encrypted{}.{}", file_stem, num, extension),
+            Operation::Decrypt => format!("{}_decrypted{}.{}", file_stem, num, extension),
+        };
+
+        let new_path = dest_dir.join(name);
+
+        if !tokio::fs::try_exists(&new_path).await? {
+            return Ok(new_path);
+        }
+    }
+
+    Err(anyhow!("Couldn't find available name for the result file"))
+}
+
+pub fn get_algorithm(config: &Config) -> anyhow::Result<Box<dyn StreamingAlgorithm + Send + Sync>> {
+    match config.algorithm {
+        AlgorithmOption::Enigma => match config.enigma_machine {
+            EnigmaMachine::Binary => Ok(Box::new(BinaryEnigma::new(config.binary_enigma()?))),
+            machine @ (EnigmaMachine::M3 | EnigmaMachine::M4) => {
+                let enigma = Enigma::try_new(config.enigma()?, machine)?;
+
+                // Raw files come from versions whose rotors didn't turn like
+                // the real machine, containers are written with the fix
+                Ok(Box::new(match config.container_format {
+                    ContainerFormat::Container => enigma,
+                    ContainerFormat::Raw => enigma.with_mechanics(Mechanics::Legacy),
+                }))
+            }
+        },
+        AlgorithmOption::Xxtea => {
+            let xxtea = config.xxtea()?;
+            match xxtea.mode {
+                XxteaMode::WholeMessage => Ok(Box::new(Xxtea::new(xxtea))),
+                XxteaMode::Block(mode) => Ok(Box::new(BlockModeCipher::try_new(
+                    Arc::new(XxteaBlock::try_new(xxtea)?),
+                    mode,
+                    &xxtea.iv,
+                )?)),
+            }
+        }
+        AlgorithmOption::Aes => {
+            let aes = config.aes()?;
+            Ok(Box::new(BlockModeCipher::try_new(
+                Arc::new(Aes::from_config(aes)),
+                aes.mode,
+                &aes.iv,
+            )?))
+        }
+        AlgorithmOption::ChaCha20Poly1305 => Ok(Box::new(ChaCha20Poly1305::try_new(
+            config.chacha20poly1305()?,
+        )?)),
+    }
+}
+
+/// The cipher key `kdf` derives from the typed key of `config`, run once
+/// per container and shared by [`get_keyed_algorithm`] and the key
+/// fingerprint. The rotor settings of the classic Enigmas are not a password
+/// and derive nothing.
+pub fn derive_key(config: &Config, kdf: &KeyDerivation) -> anyhow::Result<Option<DerivedKey>> {
+    let key = match config.algorithm {
+        AlgorithmOption::Enigma => match config.enigma_machine {
+            EnigmaMachine::Binary => {
+                kdf.derive(&config.binary_enigma()?.passphrase, BINARY_ENIGMA_SEED_LEN)
+            }
+            EnigmaMachine::M3 | EnigmaMachine::M4 => return Ok(None),
+        },
+        AlgorithmOption::Xxtea => kdf.derive(&config.xxtea()?.key, XXTEA_KEY_LEN),
+        AlgorithmOption::Aes => {
+            let aes = config.aes()?;
+            kdf.derive(&aes.key, aes.key_size.bytes())
+        }
+        AlgorithmOption::ChaCha20Poly1305 => {
+            kdf.derive(&config.chacha20poly1305()?.key, chacha20poly1305::KEY_SIZE)
+        }
+    };
+
+    Ok(Some(key))
+}
+
+/// Like [`get_algorithm`], but with a `key` from [`derive_key`] in place of
+/// the typed keys
+pub fn get_keyed_algorithm(
+    config: &Config,
+    key: Option<&[u8]>,
+) -> anyhow::Result<Box<dyn StreamingAlgorithm + Send + Sync>> {
+    let Some(key) = key else {
+        return get_algorithm(config);
+    };
+
+    match config.algorithm {
+        AlgorithmOption::Enigma => match config.enigma_machine {
+            EnigmaMachine::Binary => Ok(Box::new(BinaryEnigma::from_seed(key))),
+            EnigmaMachine::M3 | EnigmaMachine::M4 => get_algorithm(config),
+        },
+        AlgorithmOption::Xxtea => {
+            let xxtea = config.xxtea()?;
+            match xxtea.mode {
+                XxteaMode::WholeMessage => Ok(Box::new(Xxtea::with_key(key))),
+                XxteaMode::Block(mode) => Ok(Box::new(BlockModeCipher::try_new(
+                    Arc::new(XxteaBlock::try_with_key(xxtea, key)?),
+                    mode,
+                    &xxtea.iv,
+                )?)),
+            }
+        }
+        AlgorithmOption::Aes => {
+            let aes = config.aes()?;
+            Ok(Box::new(BlockModeCipher::try_new(
+                Arc::new(Aes::new(key)),
+                aes.mode,
+                &aes.iv,
+            )?))
+        }
+        AlgorithmOption::ChaCha20Poly1305 => Ok(Box::new(ChaCha20Poly1305::new(
+            key.try_into()
+                .map_err(|_| anyhow!("ChaCha20-Poly1305 needs a 32 byte key"))?,
+        ))),
+    }
+}